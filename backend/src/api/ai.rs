@@ -1,28 +1,180 @@
 use actix_web::{HttpResponse, Responder, web};
+use chrono::{Datelike, Duration, NaiveDate, TimeZone, Utc};
+use uuid::Uuid;
 
-use crate::models::ai::{AiChatRequest, AiSuggestRequest};
+use crate::models::ai::{AiBudget, AiBudgetStatus, AiCall, AiChatRequest, AiSuggestRequest, AiUsageQuery, AiUsageResponse, UsageTotals};
+use crate::models::plan::parse_date;
 use crate::services::ai_service::AiService;
+use crate::services::policy_service::PolicyService;
+use crate::services::response_cache::ResponseCache;
+use crate::services::retrieval_service::RetrievalService;
+use crate::services::usage_service::UsageService;
 use crate::utils::response::wrap;
 use crate::db::AppState;
+use crate::middleware::auth::{Admin, Identity};
 use crate::utils::errors::ApiError;
 use crate::db::repository;
 
+#[derive(serde::Deserialize)]
+struct BudgetQuery {
+    user_id: Uuid,
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/chat").route(web::post().to(chat)))
-        .service(web::resource("/suggest").route(web::post().to(suggest)));
+        .service(web::resource("/suggest").route(web::post().to(suggest)))
+        .service(web::resource("/usage").route(web::get().to(get_usage)))
+        .service(web::resource("/budget").route(web::get().to(get_budget)).route(web::put().to(update_budget)))
+        .service(web::resource("/prompts").route(web::get().to(list_prompts)))
+        .service(web::resource("/prompts/reload").route(web::post().to(reload_prompts)));
 }
 
 async fn chat(state: web::Data<AppState>, payload: web::Json<AiChatRequest>) -> Result<impl Responder, ApiError> {
-    let response = AiService::chat(&payload, &state.config).await;
+    PolicyService::check_input("message", &payload.message, &state.config)?;
+    let candidates = match RetrievalService::match_query(&payload.message) {
+        Some(query) if state.config.retrieval_top_k > 0 => {
+            let range = RetrievalService::date_range(&payload.message, Utc::now().date_naive())
+                .map(|(from, to)| (from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string()));
+            let (from, to) = range.unzip();
+            let conn = state.db.lock().expect("db lock");
+            repository::search_knowledge(&conn, payload.user_id, &query, from.as_deref(), to.as_deref(), state.config.retrieval_top_k * 4)
+                .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to search notes"))?
+        }
+        _ => Vec::new(),
+    };
+    let (candidates, withheld) = PolicyService::filter_passages(candidates);
+    let passages = RetrievalService::rank(&payload.message, candidates, &state.ai, state.config.retrieval_top_k).await;
+    let mut call = begin_ai_call(&state, payload.user_id, "chat")?;
+    call.cache = payload.cache.unwrap_or(state.config.ai_cache_chat);
+    let mut response = AiService::chat(&payload, &passages, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &state.config, &mut call).await;
+    finish_ai_call(&state, &call)?;
+    response.withheld_sources = withheld;
     if state.config.ai_provider == "openrouter" {
         let conn = state.db.lock().expect("db lock");
-        repository::insert_conversation(&conn, &payload, &response.response)
+        repository::insert_conversation(&conn, &payload, &response.response, response.prompt_version.as_deref())
             .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store conversation"))?;
     }
     Ok(HttpResponse::Ok().json(wrap(response)))
 }
 
 async fn suggest(state: web::Data<AppState>, payload: web::Json<AiSuggestRequest>) -> Result<impl Responder, ApiError> {
-    let response = AiService::suggest(&payload, &state.config).await;
+    let tasks = {
+        let conn = state.db.lock().expect("db lock");
+        repository::list_user_tasks(&conn, payload.user_id)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tasks"))?
+    };
+    let mut call = begin_ai_call(&state, payload.user_id, "suggest")?;
+    let response = AiService::suggest(&payload, &tasks, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &state.config, &mut call).await;
+    finish_ai_call(&state, &call)?;
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+pub(crate) fn usage_window(from_date: Option<&str>, to_date: Option<&str>, default_from: NaiveDate) -> Result<(String, String), ApiError> {
+    let from = match from_date {
+        Some(date) => parse_date(date).map_err(|_| ApiError::validation("Invalid from_date format"))?,
+        None => default_from,
+    };
+    let to = match to_date {
+        Some(date) => parse_date(date).map_err(|_| ApiError::validation("Invalid to_date format"))?,
+        None => Utc::now().date_naive(),
+    };
+    let from = Utc.from_utc_datetime(&from.and_hms_opt(0, 0, 0).unwrap_or_default()).to_rfc3339();
+    let to = Utc.from_utc_datetime(&(to + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default()).to_rfc3339();
+    Ok((from, to))
+}
+
+async fn get_usage(state: web::Data<AppState>, identity: Identity, query: web::Query<AiUsageQuery>) -> Result<impl Responder, ApiError> {
+    identity.ensure(query.user_id)?;
+    let today = Utc::now().date_naive();
+    let (from, to) = usage_window(query.from_date.as_deref(), query.to_date.as_deref(), today.with_day(1).unwrap_or(today))?;
+
+    let conn = state.db.lock().expect("db lock");
+    let load = |group: &str| {
+        repository::get_usage_breakdown(&conn, query.user_id, group, &from, &to)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load AI usage"))
+    };
+    let by_feature = load("feature")?;
+    let by_model = load("model")?;
+    let by_day = load("day")?;
+    let totals = by_day.iter().fold(UsageTotals::default(), |mut totals, day| {
+        totals.requests += day.totals.requests;
+        totals.prompt_tokens += day.totals.prompt_tokens;
+        totals.completion_tokens += day.totals.completion_tokens;
+        totals.total_tokens += day.totals.total_tokens;
+        totals.cost_usd += day.totals.cost_usd;
+        totals
+    });
+    let budget = budget_status(&state, &conn, query.user_id)?;
+
+    let response = AiUsageResponse {
+        user_id: query.user_id,
+        totals,
+        by_feature,
+        by_model,
+        by_day,
+        budget,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn get_budget(state: web::Data<AppState>, identity: Identity, query: web::Query<BudgetQuery>) -> Result<impl Responder, ApiError> {
+    identity.ensure(query.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    let response = budget_status(&state, &conn, query.user_id)?;
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn update_budget(state: web::Data<AppState>, _admin: Admin, payload: web::Json<AiBudget>) -> Result<impl Responder, ApiError> {
+    UsageService::validate_budget(&payload).map_err(ApiError::validation)?;
+    let conn = state.db.lock().expect("db lock");
+    repository::upsert_ai_budget(&conn, &payload)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to save AI budget"))?;
+    let response = budget_status(&state, &conn, payload.user_id)?;
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn list_prompts(state: web::Data<AppState>) -> Result<impl Responder, ApiError> {
+    let response = serde_json::json!({
+        "prompts": state.prompts.list(),
+    });
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn reload_prompts(state: web::Data<AppState>, _admin: Admin) -> Result<impl Responder, ApiError> {
+    let errors = state.prompts.reload();
+    let response = serde_json::json!({
+        "prompts": state.prompts.list(),
+        "errors": errors,
+    });
     Ok(HttpResponse::Ok().json(wrap(response)))
 }
+
+fn budget_status(state: &AppState, conn: &rusqlite::Connection, user_id: Uuid) -> Result<AiBudgetStatus, ApiError> {
+    let budget = repository::get_ai_budget(conn, user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load AI budget"))?
+        .unwrap_or_else(|| UsageService::default_budget(user_id, &state.config));
+    let now = Utc::now();
+    let day_start = Utc.from_utc_datetime(&now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default());
+    let month_start = Utc.from_utc_datetime(&now.date_naive().with_day(1).unwrap_or(now.date_naive()).and_hms_opt(0, 0, 0).unwrap_or_default());
+    let used_today = repository::get_tokens_used_since(conn, user_id, day_start).unwrap_or(0);
+    let used_this_month = repository::get_tokens_used_since(conn, user_id, month_start).unwrap_or(0);
+    Ok(AiBudgetStatus {
+        budget,
+        used_today,
+        used_this_month,
+    })
+}
+
+pub(crate) fn begin_ai_call(state: &AppState, user_id: Uuid, feature: &str) -> Result<AiCall, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    let status = budget_status(state, &conn, user_id)?;
+    let mut call = AiCall::new(user_id, feature);
+    UsageService::apply_budget(&mut call, &status.budget, status.used_today, status.used_this_month);
+    Ok(call)
+}
+
+pub(crate) fn finish_ai_call(state: &AppState, call: &AiCall) -> Result<(), ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    repository::insert_ai_usage(&conn, call)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to record AI usage"))
+}
@@ -0,0 +1,317 @@
+use actix_web::{HttpResponse, Responder, web};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::db::{AppState, repository};
+use crate::models::flashcard::{
+    ApkgImportQuery, ApkgImportResponse, Card, CardCreateRequest, CardReview, CardReviewRequest, CardReviewResponse, Deck, DeckCreateRequest,
+    DeckDetailResponse, DeckExportQuery, DeckListQuery, DeckQuery, DeckListResponse, DueCardsQuery, DueCardsResponse,
+};
+use crate::models::plan::parse_date;
+use crate::services::apkg_service::ApkgService;
+use crate::services::flashcard_service::FlashcardService;
+use crate::services::policy_service::PolicyService;
+use crate::utils::{errors::ApiError, response::wrap};
+
+const DEFAULT_DUE_LIMIT: usize = 50;
+const MAX_DUE_LIMIT: usize = 500;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/decks").route(web::post().to(create_deck)).route(web::get().to(list_decks)))
+        .service(web::resource("/decks/{deck_id}").route(web::get().to(get_deck)))
+        .service(web::resource("/decks/{deck_id}/cards").route(web::post().to(add_card)))
+        .service(web::resource("/decks/{deck_id}/export").route(web::get().to(export_deck)))
+        .service(web::resource("/import").route(web::post().to(import_apkg)))
+        .service(web::resource("/due").route(web::get().to(due_cards)))
+        .service(web::resource("/cards/{card_id}/review").route(web::post().to(review_card)));
+}
+
+fn today() -> String {
+    Utc::now().date_naive().format("%Y-%m-%d").to_string()
+}
+
+async fn create_deck(
+    state: web::Data<AppState>,
+    payload: web::Json<DeckCreateRequest>,
+) -> Result<impl Responder, ApiError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::validation("name must not be empty"));
+    }
+    PolicyService::check_input("name", name, &state.config)?;
+    let conn = state.db.lock().expect("db lock");
+    let task = match payload.task_id {
+        Some(task_id) => Some(
+            repository::get_stored_task(&conn, task_id)
+                .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load task"))?
+                .filter(|task| task.user_id == payload.user_id)
+                .ok_or_else(|| ApiError::not_found("Task not found"))?,
+        ),
+        None => None,
+    };
+    let subject = payload
+        .subject
+        .clone()
+        .or_else(|| task.as_ref().map(|task| task.task.subject.clone()))
+        .map(|subject| subject.trim().to_string())
+        .filter(|subject| !subject.is_empty())
+        .ok_or_else(|| ApiError::validation("Either task_id or subject is required"))?;
+    PolicyService::check_input("subject", &subject, &state.config)?;
+
+    let deck = Deck {
+        deck_id: Uuid::new_v4(),
+        user_id: payload.user_id,
+        name: name.to_string(),
+        subject,
+        task_id: payload.task_id,
+        created_at: Utc::now(),
+    };
+    repository::insert_deck(&conn, &deck)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store deck"))?;
+    let summary = repository::get_deck(&conn, deck.deck_id, &today())
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load deck"))?
+        .ok_or_else(|| ApiError::not_found("Deck not found"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(summary)))
+}
+
+async fn list_decks(
+    state: web::Data<AppState>,
+    query: web::Query<DeckListQuery>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    let decks = repository::list_decks(&conn, query.user_id, query.subject.as_deref(), &today())
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load decks"))?;
+
+    let response = DeckListResponse {
+        total: decks.len(),
+        decks,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn get_deck(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<DeckQuery>,
+) -> Result<impl Responder, ApiError> {
+    let deck_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    let summary = repository::get_deck(&conn, deck_id, &today())
+        .map_err(|_| ApiError::not_found("Deck not found"))?
+        .filter(|summary| summary.deck.user_id == query.user_id)
+        .ok_or_else(|| ApiError::not_found("Deck not found"))?;
+    let cards = repository::list_deck_cards(&conn, deck_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load cards"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(DeckDetailResponse { summary, cards })))
+}
+
+async fn add_card(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<CardCreateRequest>,
+) -> Result<impl Responder, ApiError> {
+    let deck_id = path.into_inner();
+    let tags = FlashcardService::validate_card(&payload.front, &payload.back, &payload.tags)?;
+    PolicyService::check_input("front", &payload.front, &state.config)?;
+    PolicyService::check_input("back", &payload.back, &state.config)?;
+
+    let conn = state.db.lock().expect("db lock");
+    repository::get_deck(&conn, deck_id, &today())
+        .map_err(|_| ApiError::not_found("Deck not found"))?
+        .filter(|summary| summary.deck.user_id == payload.user_id)
+        .ok_or_else(|| ApiError::not_found("Deck not found"))?;
+    let card = Card {
+        card_id: Uuid::new_v4(),
+        deck_id,
+        front: payload.front.trim().to_string(),
+        back: payload.back.trim().to_string(),
+        tags,
+        schedule: FlashcardService::new_schedule(Utc::now().date_naive()),
+        created_at: Utc::now(),
+    };
+    repository::insert_card(&conn, &card)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store card"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(card)))
+}
+
+async fn due_cards(
+    state: web::Data<AppState>,
+    query: web::Query<DueCardsQuery>,
+) -> Result<impl Responder, ApiError> {
+    let date = match &query.date {
+        Some(date) => parse_date(date).map_err(|_| ApiError::validation("Invalid date format"))?.format("%Y-%m-%d").to_string(),
+        None => today(),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_DUE_LIMIT);
+    if !(1..=MAX_DUE_LIMIT).contains(&limit) {
+        return Err(ApiError::validation(format!("limit must be between 1 and {}", MAX_DUE_LIMIT)));
+    }
+    let conn = state.db.lock().expect("db lock");
+    let cards = repository::list_due_cards(&conn, query.user_id, &date, query.deck_id, limit)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load due cards"))?;
+
+    let response = DueCardsResponse {
+        date,
+        total: cards.len(),
+        cards,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn review_card(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<CardReviewRequest>,
+) -> Result<impl Responder, ApiError> {
+    let card_id = path.into_inner();
+    if payload.quality > 5 {
+        return Err(ApiError::validation("quality must be between 0 and 5"));
+    }
+    let conn = state.db.lock().expect("db lock");
+    let (mut card, _) = repository::get_card(&conn, card_id)
+        .map_err(|_| ApiError::not_found("Card not found"))?
+        .filter(|(_, owner)| *owner == payload.user_id)
+        .ok_or_else(|| ApiError::not_found("Card not found"))?;
+    card.schedule = FlashcardService::schedule(&card.schedule, payload.quality, Utc::now());
+    let review = CardReview {
+        review_id: Uuid::new_v4(),
+        card_id,
+        user_id: payload.user_id,
+        quality: payload.quality,
+        ease_factor: card.schedule.ease_factor,
+        interval_days: card.schedule.interval_days,
+        reviewed_at: card.schedule.last_reviewed.unwrap_or_else(Utc::now),
+    };
+    repository::record_card_review(&conn, card_id, &card.schedule, &review)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store review"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(CardReviewResponse { card, review })))
+}
+
+async fn import_apkg(
+    state: web::Data<AppState>,
+    query: web::Query<ApkgImportQuery>,
+    body: web::Payload,
+) -> Result<impl Responder, ApiError> {
+    let subject = query.subject.trim();
+    if subject.is_empty() {
+        return Err(ApiError::validation("subject must not be empty"));
+    }
+    PolicyService::check_input("subject", subject, &state.config)?;
+    let limit = state.config.flashcard_import_max_bytes;
+    let bytes = body
+        .to_bytes_limited(limit)
+        .await
+        .map_err(|_| {
+            ApiError::new(actix_web::http::StatusCode::PAYLOAD_TOO_LARGE, "APKG_TOO_LARGE", format!("The package exceeds {} bytes", limit))
+                .with_details(serde_json::json!({"limit": limit}))
+        })?
+        .map_err(|_| ApiError::validation("Failed to read the uploaded package"))?;
+    let package = ApkgService::read(&bytes, Utc::now().date_naive(), limit as u64 * 4)?;
+
+    let mut conn = state.db.lock().expect("db lock");
+    let tx = conn
+        .transaction()
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to start import"))?;
+    let mut decks = Vec::new();
+    let mut imported_cards = 0;
+    let mut imported_reviews = 0;
+    let skipped_notes = package.skipped_notes;
+    for imported in package.decks {
+        let deck = Deck {
+            deck_id: Uuid::new_v4(),
+            user_id: query.user_id,
+            name: imported.name,
+            subject: subject.to_string(),
+            task_id: None,
+            created_at: Utc::now(),
+        };
+        repository::insert_deck(&tx, &deck)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store deck"))?;
+        for imported_card in imported.cards {
+            let card = Card {
+                card_id: Uuid::new_v4(),
+                deck_id: deck.deck_id,
+                front: imported_card.front,
+                back: imported_card.back,
+                tags: imported_card.tags,
+                schedule: imported_card.schedule,
+                created_at: Utc::now(),
+            };
+            repository::insert_card(&tx, &card)
+                .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store card"))?;
+            for (quality, ease_factor, interval_days, reviewed_at) in imported_card.reviews {
+                let review = CardReview {
+                    review_id: Uuid::new_v4(),
+                    card_id: card.card_id,
+                    user_id: query.user_id,
+                    quality,
+                    ease_factor,
+                    interval_days,
+                    reviewed_at,
+                };
+                repository::insert_card_review(&tx, &review)
+                    .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store review"))?;
+                imported_reviews += 1;
+            }
+            imported_cards += 1;
+        }
+        if let Some(summary) = repository::get_deck(&tx, deck.deck_id, &today())
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load deck"))?
+        {
+            decks.push(summary);
+        }
+    }
+    if decks.is_empty() {
+        return Err(ApiError::new(actix_web::http::StatusCode::BAD_REQUEST, "INVALID_APKG", "The package does not contain any notes"));
+    }
+    tx.commit()
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store import"))?;
+
+    let response = ApkgImportResponse {
+        decks,
+        imported_cards,
+        imported_reviews,
+        skipped_notes,
+        media_skipped: package.media_files,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn export_deck(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<DeckExportQuery>,
+) -> Result<impl Responder, ApiError> {
+    let deck_id = path.into_inner();
+    let (summary, cards, reviews) = {
+        let conn = state.db.lock().expect("db lock");
+        let summary = repository::get_deck(&conn, deck_id, &today())
+            .map_err(|_| ApiError::not_found("Deck not found"))?
+            .filter(|summary| summary.deck.user_id == query.user_id)
+            .ok_or_else(|| ApiError::not_found("Deck not found"))?;
+        let cards = repository::list_deck_cards(&conn, deck_id)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load cards"))?;
+        let reviews = repository::list_deck_reviews(&conn, deck_id)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load reviews"))?;
+        (summary, cards, reviews)
+    };
+    let package = ApkgService::write(&summary.deck, &cards, &reviews)?;
+    let file_name = summary
+        .deck
+        .name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_') { ch } else { '_' })
+        .collect::<String>();
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.apkg\"", file_name)))
+        .body(package))
+}
@@ -0,0 +1,184 @@
+use actix_web::{HttpResponse, Responder, web};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::db::{AppState, repository};
+use crate::models::focus::{
+    FocusActionRequest, FocusPauseRequest, FocusSession, FocusSessionListQuery, FocusSessionListResponse, FocusSessionResponse,
+    FocusStartRequest, FocusStopRequest,
+};
+use crate::services::focus_service::{FocusError, FocusService};
+use crate::services::policy_service::PolicyService;
+use crate::utils::config::AppConfig;
+use crate::utils::{errors::ApiError, response::wrap};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("").route(web::post().to(start_session)).route(web::get().to(list_sessions)))
+        .service(web::resource("/{session_id}").route(web::get().to(get_session)))
+        .service(web::resource("/{session_id}/pause").route(web::post().to(pause_session)))
+        .service(web::resource("/{session_id}/resume").route(web::post().to(resume_session)))
+        .service(web::resource("/{session_id}/heartbeat").route(web::post().to(heartbeat_session)))
+        .service(web::resource("/{session_id}/stop").route(web::post().to(stop_session)));
+}
+
+pub(crate) fn close_idle_sessions(conn: &rusqlite::Connection, config: &AppConfig) -> Result<(), ApiError> {
+    let now = Utc::now();
+    let timeout = Duration::seconds(config.focus_idle_timeout_secs.max(1));
+    let idle = repository::list_idle_focus_sessions(conn, now - timeout)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load focus sessions"))?;
+    for mut session in idle {
+        if FocusService::expire(&mut session, timeout, now) {
+            close_session(conn, &session, false)?;
+        }
+    }
+    Ok(())
+}
+
+fn close_session(conn: &rusqlite::Connection, session: &FocusSession, complete_task: bool) -> Result<Option<u32>, ApiError> {
+    repository::update_focus_session(conn, session)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store focus session"))?;
+    repository::credit_focus_minutes(conn, session.task_id, session.user_id, FocusService::credited_minutes(session), complete_task)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to update task duration"))
+}
+
+fn respond(conn: &rusqlite::Connection, session: FocusSession) -> Result<FocusSessionResponse, ApiError> {
+    let task_actual_duration = repository::get_task_actual_duration(conn, session.task_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load task"))?;
+    Ok(FocusSessionResponse {
+        elapsed_seconds: FocusService::elapsed_seconds(&session, Utc::now()),
+        session,
+        task_actual_duration,
+    })
+}
+
+fn load_session(conn: &rusqlite::Connection, session_id: Uuid, user_id: Uuid) -> Result<FocusSession, ApiError> {
+    repository::get_focus_session(conn, session_id)
+        .map_err(|_| ApiError::not_found("Focus session not found"))?
+        .filter(|session| session.user_id == user_id)
+        .ok_or_else(|| ApiError::not_found("Focus session not found"))
+}
+
+async fn start_session(
+    state: web::Data<AppState>,
+    payload: web::Json<FocusStartRequest>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    repository::get_task_user_id(&conn, payload.task_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load task"))?
+        .filter(|owner| *owner == payload.user_id)
+        .ok_or_else(|| ApiError::not_found("Task not found"))?;
+    if let Some(open) = repository::get_open_focus_session(&conn, payload.user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load focus sessions"))?
+    {
+        return Err(FocusError::AlreadyOpen { session_id: open.session_id }.into());
+    }
+
+    let session = FocusService::start(payload.task_id, payload.user_id, Utc::now());
+    repository::insert_focus_session(&conn, &session)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store focus session"))?;
+    repository::mark_task_in_progress(&conn, payload.task_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to update task"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(respond(&conn, session)?)))
+}
+
+async fn list_sessions(
+    state: web::Data<AppState>,
+    query: web::Query<FocusSessionListQuery>,
+) -> Result<impl Responder, ApiError> {
+    if let Some(status) = &query.status
+        && !matches!(status.as_str(), "active" | "paused" | "completed" | "abandoned")
+    {
+        return Err(ApiError::validation("status must be active, paused, completed or abandoned"));
+    }
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    let sessions = repository::list_focus_sessions(&conn, query.user_id, query.task_id, query.status.as_deref())
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load focus sessions"))?
+        .into_iter()
+        .map(|session| respond(&conn, session))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let response = FocusSessionListResponse {
+        total: sessions.len(),
+        focused_seconds: sessions.iter().map(|session| session.elapsed_seconds).sum(),
+        sessions,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn get_session(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<FocusActionRequest>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    let session = load_session(&conn, path.into_inner(), query.user_id)?;
+
+    Ok(HttpResponse::Ok().json(wrap(respond(&conn, session)?)))
+}
+
+async fn pause_session(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<FocusPauseRequest>,
+) -> Result<impl Responder, ApiError> {
+    if let Some(reason) = &payload.reason {
+        PolicyService::check_input("reason", reason, &state.config)?;
+    }
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    let mut session = load_session(&conn, path.into_inner(), payload.user_id)?;
+    FocusService::pause(&mut session, payload.reason.clone(), Utc::now())?;
+    repository::update_focus_session(&conn, &session)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store focus session"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(respond(&conn, session)?)))
+}
+
+async fn resume_session(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<FocusActionRequest>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    let mut session = load_session(&conn, path.into_inner(), payload.user_id)?;
+    FocusService::resume(&mut session, Utc::now())?;
+    repository::update_focus_session(&conn, &session)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store focus session"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(respond(&conn, session)?)))
+}
+
+async fn heartbeat_session(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<FocusActionRequest>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    let mut session = load_session(&conn, path.into_inner(), payload.user_id)?;
+    FocusService::heartbeat(&mut session, Utc::now())?;
+    repository::update_focus_session(&conn, &session)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store focus session"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(respond(&conn, session)?)))
+}
+
+async fn stop_session(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<FocusStopRequest>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    close_idle_sessions(&conn, &state.config)?;
+    let mut session = load_session(&conn, path.into_inner(), payload.user_id)?;
+    FocusService::stop(&mut session, Utc::now())?;
+    close_session(&conn, &session, payload.complete_task)?;
+
+    Ok(HttpResponse::Ok().json(wrap(respond(&conn, session)?)))
+}
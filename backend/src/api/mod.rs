@@ -1,8 +1,12 @@
 use actix_web::web;
 
 pub mod ai;
+pub mod flashcards;
+pub mod focus;
 pub mod plans;
+pub mod quizzes;
 pub mod reminders;
+pub mod search;
 pub mod tools;
 pub mod users;
 
@@ -10,7 +14,11 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/plans").configure(plans::configure))
         .service(web::scope("/tools").configure(tools::configure))
         .service(web::scope("/ai").configure(ai::configure))
+        .service(web::scope("/quizzes").configure(quizzes::configure))
+        .service(web::scope("/flashcards").configure(flashcards::configure))
+        .service(web::scope("/focus-sessions").configure(focus::configure))
         .service(web::scope("/users").configure(users::configure))
         .service(web::scope("/reminders").configure(reminders::configure))
+        .service(web::scope("/search").configure(search::configure))
         .service(web::scope("/tasks").configure(plans::configure_task_routes));
 }
@@ -2,9 +2,12 @@ use actix_web::{HttpResponse, Responder, web};
 use chrono::Datelike;
 use uuid::Uuid;
 
+use crate::api::ai::{begin_ai_call, finish_ai_call};
 use crate::db::{AppState, repository};
 use crate::models::plan::{DailyPlan, DailyPlanResponse, PlanGenerateRequest, PlanResponse, RegenerateRequest, RegenerateResponse, SuggestedTool, TaskUpdateRequest, TaskUpdateResponse, parse_date};
 use crate::services::plan_service::PlanService;
+use crate::services::policy_service::PolicyService;
+use crate::services::response_cache::ResponseCache;
 use crate::utils::{errors::ApiError, response::wrap};
 
 #[derive(serde::Deserialize)]
@@ -27,12 +30,31 @@ async fn generate_plan(
     state: web::Data<AppState>,
     payload: web::Json<PlanGenerateRequest>,
 ) -> Result<impl Responder, ApiError> {
-    let plan = PlanService::generate_plan(&payload);
+    PolicyService::check_input("goals", &payload.goals, &state.config)?;
+    let mastery = {
+        let conn = state.db.lock().expect("db lock");
+        repository::get_topic_mastery(&conn, payload.user_id)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load quiz results"))?
+    };
+    let mut call = begin_ai_call(&state, payload.user_id, "plan")?;
+    let mut plan = PlanService::generate(&payload, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &state.config, &mut call).await;
+    finish_ai_call(&state, &call)?;
+    PlanService::reinforce_weak_topics(&mut plan, &mastery, PlanService::daily_budget(&payload));
+    let due_cards = {
+        let conn = state.db.lock().expect("db lock");
+        let last_day = plan.weekly_plan.daily_plans.last().map(|day| day.date.clone()).unwrap_or_default();
+        repository::get_due_card_counts(&conn, payload.user_id, &last_day)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load due flashcards"))?
+    };
+    PlanService::schedule_flashcard_reviews(&mut plan, &due_cards, PlanService::daily_budget(&payload));
     let response = PlanResponse {
         plan_id: plan.plan_id,
         weekly_plan: plan.weekly_plan.clone(),
         ai_rationale: plan.ai_rationale.clone(),
         generated_at: plan.generated_at,
+        prompt_version: plan.prompt_version.clone(),
+        cached: plan.cached,
+        fallback: plan.fallback.clone(),
     };
 
     let conn = state.db.lock().expect("db lock");
@@ -80,6 +102,9 @@ async fn update_task(
     payload: web::Json<TaskUpdateRequest>,
 ) -> Result<impl Responder, ApiError> {
     let task_id = path.into_inner();
+    if let Some(notes) = &payload.notes {
+        PolicyService::check_input("notes", notes, &state.config)?;
+    }
     let conn = state.db.lock().expect("db lock");
     let entry = repository::update_task(&conn, task_id, &payload.status, payload.actual_duration, payload.notes.clone())
         .map_err(|_| ApiError::not_found("Task not found"))?;
@@ -0,0 +1,166 @@
+use actix_web::{HttpResponse, Responder, web};
+use uuid::Uuid;
+
+use crate::api::ai::{begin_ai_call, finish_ai_call};
+use crate::db::{AppState, repository};
+use crate::models::quiz::{
+    QuizAttemptListResponse, QuizAttemptResponse, QuizGenerateRequest, QuizListQuery, QuizListResponse, QuizQuery, QuizResponse,
+    QuizSubmitRequest, TopicMastery,
+};
+use crate::services::policy_service::PolicyService;
+use crate::services::quiz_service::{QuizService, QuizSpec};
+use crate::services::response_cache::ResponseCache;
+use crate::utils::{errors::ApiError, response::wrap};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/generate").route(web::post().to(generate_quiz)))
+        .service(web::resource("").route(web::get().to(list_quizzes)))
+        .service(web::resource("/{quiz_id}").route(web::get().to(get_quiz)))
+        .service(web::resource("/{quiz_id}/attempts").route(web::post().to(submit_attempt)).route(web::get().to(list_attempts)));
+}
+
+async fn generate_quiz(
+    state: web::Data<AppState>,
+    payload: web::Json<QuizGenerateRequest>,
+) -> Result<impl Responder, ApiError> {
+    let task = match payload.task_id {
+        Some(task_id) => {
+            let conn = state.db.lock().expect("db lock");
+            let task = repository::get_stored_task(&conn, task_id)
+                .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load task"))?
+                .filter(|task| task.user_id == payload.user_id)
+                .ok_or_else(|| ApiError::not_found("Task not found"))?;
+            Some(task)
+        }
+        None => None,
+    };
+    let subject = payload
+        .subject
+        .clone()
+        .or_else(|| task.as_ref().map(|task| task.task.subject.clone()))
+        .filter(|subject| !subject.trim().is_empty())
+        .ok_or_else(|| ApiError::validation("Either task_id or subject is required"))?;
+    let topic = payload
+        .topic
+        .clone()
+        .or_else(|| task.as_ref().map(|task| task.task.topic.clone()))
+        .filter(|topic| !topic.trim().is_empty())
+        .unwrap_or_else(|| subject.clone());
+    PolicyService::check_input("subject", &subject, &state.config)?;
+    PolicyService::check_input("topic", &topic, &state.config)?;
+    let question_count = payload.question_count.unwrap_or(5);
+    if !(1..=20).contains(&question_count) {
+        return Err(ApiError::validation("question_count must be between 1 and 20"));
+    }
+    let spec = QuizSpec {
+        user_id: payload.user_id,
+        task_id: payload.task_id,
+        subject: &subject,
+        topic: &topic,
+        question_count,
+        difficulty_level: payload.difficulty_level.as_deref().unwrap_or("intermediate"),
+    };
+
+    let mut call = begin_ai_call(&state, payload.user_id, "quiz")?;
+    let quiz = QuizService::generate(&spec, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &mut call).await;
+    finish_ai_call(&state, &call)?;
+
+    let conn = state.db.lock().expect("db lock");
+    repository::insert_quiz(&conn, &quiz)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store quiz"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(QuizResponse::from(&quiz))))
+}
+
+async fn list_quizzes(
+    state: web::Data<AppState>,
+    query: web::Query<QuizListQuery>,
+) -> Result<impl Responder, ApiError> {
+    let conn = state.db.lock().expect("db lock");
+    let quizzes = repository::list_quizzes(&conn, query.user_id, query.subject.as_deref())
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load quizzes"))?
+        .iter()
+        .map(QuizResponse::from)
+        .collect::<Vec<_>>();
+
+    let response = QuizListResponse {
+        total: quizzes.len(),
+        quizzes,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn get_quiz(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<QuizQuery>,
+) -> Result<impl Responder, ApiError> {
+    let quiz_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    let quiz = repository::get_quiz(&conn, quiz_id)
+        .map_err(|_| ApiError::not_found("Quiz not found"))?
+        .filter(|quiz| quiz.user_id == query.user_id)
+        .ok_or_else(|| ApiError::not_found("Quiz not found"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(QuizResponse::from(&quiz))))
+}
+
+async fn submit_attempt(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    payload: web::Json<QuizSubmitRequest>,
+) -> Result<impl Responder, ApiError> {
+    let quiz_id = path.into_inner();
+    let quiz = {
+        let conn = state.db.lock().expect("db lock");
+        repository::get_quiz(&conn, quiz_id)
+            .map_err(|_| ApiError::not_found("Quiz not found"))?
+            .filter(|quiz| quiz.user_id == payload.user_id)
+            .ok_or_else(|| ApiError::not_found("Quiz not found"))?
+    };
+    if let Some(answer) = payload.answers.iter().find(|answer| !quiz.questions.iter().any(|question| question.id == answer.question_id)) {
+        return Err(ApiError::validation(format!("Unknown question_id {}", answer.question_id)));
+    }
+
+    for answer in &payload.answers {
+        PolicyService::check_input("answer", &answer.answer, &state.config)?;
+    }
+
+    let mut call = begin_ai_call(&state, payload.user_id, "quiz")?;
+    let attempt = QuizService::grade(&quiz, &payload.answers, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &mut call).await;
+    finish_ai_call(&state, &call)?;
+
+    let conn = state.db.lock().expect("db lock");
+    repository::insert_quiz_attempt(&conn, &attempt)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store quiz attempt"))?;
+    let mastery = repository::get_topic_mastery(&conn, payload.user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load quiz results"))?
+        .into_iter()
+        .find(|mastery| mastery.subject == quiz.subject && mastery.topic == quiz.topic)
+        .unwrap_or(TopicMastery {
+            subject: quiz.subject.clone(),
+            topic: quiz.topic.clone(),
+            average_score: attempt.score,
+            attempts: 1,
+        });
+
+    Ok(HttpResponse::Ok().json(wrap(QuizAttemptResponse { attempt, mastery })))
+}
+
+async fn list_attempts(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<QuizQuery>,
+) -> Result<impl Responder, ApiError> {
+    let quiz_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    repository::get_quiz(&conn, quiz_id)
+        .map_err(|_| ApiError::not_found("Quiz not found"))?
+        .filter(|quiz| quiz.user_id == query.user_id)
+        .ok_or_else(|| ApiError::not_found("Quiz not found"))?;
+    let attempts = repository::list_quiz_attempts(&conn, quiz_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load quiz attempts"))?;
+
+    Ok(HttpResponse::Ok().json(wrap(QuizAttemptListResponse { attempts })))
+}
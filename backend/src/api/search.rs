@@ -0,0 +1,71 @@
+use actix_web::{HttpResponse, Responder, web};
+
+use crate::db::{AppState, repository};
+use crate::middleware::auth::Identity;
+use crate::models::search::{DocumentSearch, SearchQuery, SearchResponse};
+use crate::services::search_service::{MATCH_END, MATCH_START, SEARCH_SORTS, SearchService};
+use crate::utils::{errors::ApiError, response::wrap};
+
+const MAX_LIMIT: usize = 100;
+const CHUNK_HEADROOM: usize = 4;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("").route(web::get().to(search)));
+}
+
+async fn search(
+    state: web::Data<AppState>,
+    identity: Identity,
+    query: web::Query<SearchQuery>,
+) -> Result<impl Responder, ApiError> {
+    identity.ensure(query.user_id)?;
+    let Some(match_query) = SearchService::match_query(&query.q) else {
+        return Err(ApiError::validation("q must contain at least one word"));
+    };
+    let types = SearchService::parse_types(query.types.as_deref())?;
+    let sort = query.sort.as_deref().unwrap_or("relevance");
+    if !SEARCH_SORTS.contains(&sort) {
+        return Err(ApiError::validation(format!("sort must be one of {}", SEARCH_SORTS.join(", "))));
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(ApiError::validation("from must not be after to"));
+    }
+    let from = query.from.map(|date| date.to_string());
+    let to = query.to.map(|date| date.to_string());
+
+    let limit = query.limit.unwrap_or(20).min(MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let search = DocumentSearch {
+        user_id: identity.user_id,
+        query: &match_query,
+        source_types: &types,
+        from: from.as_deref(),
+        to: to.as_deref(),
+        recent_first: sort == "recent",
+    };
+
+    let (hits, total) = {
+        let conn = state.db.lock().expect("db lock");
+        let hits = repository::search_documents(&conn, &search, (MATCH_START, MATCH_END), offset.saturating_add(limit).saturating_mul(CHUNK_HEADROOM))
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to search"))?;
+        let total = repository::count_documents(&conn, &search)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to search"))?;
+        (hits, total)
+    };
+    let mut results = SearchService::rank(hits, sort).into_iter().skip(offset).take(limit).collect::<Vec<_>>();
+    for result in &mut results {
+        result.snippet = SearchService::highlight(&result.snippet);
+    }
+
+    let response = SearchResponse {
+        query: query.q.clone(),
+        results,
+        total,
+        limit,
+        offset,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
@@ -1,26 +1,80 @@
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
 use uuid::Uuid;
 
+use crate::api::ai::{begin_ai_call, finish_ai_call, usage_window};
 use crate::db::{AppState, repository};
+use crate::middleware::auth::{Admin, Identity};
 use crate::models::tool::{
-    ToolDeleteResponse, ToolDetailResponse, ToolEditRequest, ToolEditResponse, ToolGenerateRequest,
-    ToolListQuery, ToolListResponse, ToolResponse,
+    CatalogueQuery, CatalogueResponse, Tool, ToolDeleteResponse, ToolDetailResponse, ToolDiffQuery, ToolEditRequest, ToolEditResponse, ToolExportQuery, ToolFork, ToolForkResponse,
+    ToolGenerateRequest, ToolImportQuery, ToolImportResponse, ToolListQuery, ToolListResponse, ToolMergeResponse, ToolMetadata, ToolOwnerQuery, ToolProvenance, ToolPublishRequest,
+    ToolAccess, ToolRatingRequest, ToolRatingResponse, ToolResponse, ToolRollbackRequest, ToolRollbackResponse, ToolStateEntry,
+    ToolStateListResponse, ToolStatePatchRequest, ToolStatePutRequest, ToolStateQuery, ToolUsageAnalyticsQuery,
+    ToolUsageEvent, ToolUsageEventRequest, ToolUsageEventResponse, ToolUsageQuery, ToolUsageResponse, ToolUsageTotals,
+    ToolUpstreamStatus, ToolVersion, ToolVersionListResponse, UserToolUsageResponse,
 };
+use crate::services::policy_service::PolicyService;
+use crate::services::response_cache::ResponseCache;
+use crate::services::tool_bundle_service::ToolBundleService;
+use crate::services::tool_catalogue_service::{CATALOGUE_SORTS, ForkError, ToolCatalogueService};
+use crate::services::tool_preview::{RuntimeBundle, ToolPreview};
 use crate::services::tool_service::ToolService;
-use crate::utils::{errors::ApiError, response::wrap};
+use crate::services::tool_state_service::{ToolStateError, ToolStateService};
+use crate::services::tool_validator::ToolValidator;
+use crate::utils::{config::AppConfig, errors::ApiError, response::wrap};
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/generate").route(web::post().to(generate_tool)))
         .service(web::resource("").route(web::get().to(list_tools)))
+        .service(web::resource("/catalogue").route(web::get().to(catalogue)))
+        .service(web::resource("/import").route(web::post().to(import_tool)))
+        .service(web::resource("/signing-key").route(web::get().to(signing_key)))
+        .service(web::resource("/templates").route(web::get().to(list_templates)))
+        .service(web::resource("/templates/reload").route(web::post().to(reload_templates)))
+        .service(web::resource("/usage").route(web::get().to(user_tool_usage)))
         .service(web::resource("/{tool_id}").route(web::get().to(get_tool)).route(web::delete().to(delete_tool)))
-        .service(web::resource("/{tool_id}/edit").route(web::post().to(edit_tool)));
+        .service(web::resource("/{tool_id}/edit").route(web::post().to(edit_tool)))
+        .service(web::resource("/{tool_id}/export").route(web::get().to(export_tool)))
+        .service(web::resource("/{tool_id}/publish").route(web::put().to(publish_tool)).route(web::delete().to(unpublish_tool)))
+        .service(web::resource("/{tool_id}/rating").route(web::put().to(rate_tool)))
+        .service(web::resource("/{tool_id}/fork").route(web::post().to(fork_tool)))
+        .service(web::resource("/{tool_id}/upstream").route(web::get().to(upstream_status)))
+        .service(web::resource("/{tool_id}/upstream/merge").route(web::post().to(merge_upstream)))
+        .service(web::resource("/{tool_id}/versions").route(web::get().to(list_versions)))
+        .service(web::resource("/{tool_id}/diff").route(web::get().to(diff_versions)))
+        .service(web::resource("/{tool_id}/rollback").route(web::post().to(rollback_tool)))
+        .service(web::resource("/{tool_id}/usage").route(web::post().to(record_usage)).route(web::get().to(tool_usage)))
+        .service(web::resource("/{tool_id}/state").route(web::get().to(list_state)))
+        .service(
+            web::resource("/{tool_id}/state/{key}")
+                .route(web::get().to(get_state))
+                .route(web::put().to(put_state))
+                .route(web::patch().to(patch_state)),
+        );
+}
+
+pub fn configure_preview(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/runtime.js").route(web::get().to(preview_runtime)))
+        .service(web::resource("/{tool_id}").route(web::get().to(preview_tool)));
 }
 
 async fn generate_tool(
     state: web::Data<AppState>,
+    identity: Identity,
     payload: web::Json<ToolGenerateRequest>,
 ) -> Result<impl Responder, ApiError> {
-    let tool = ToolService::generate_tool(&payload, &state.config);
+    identity.ensure(payload.user_id)?;
+    let template = state.templates.select(&payload.tool_type, payload.template.as_deref())?;
+    PolicyService::check_input("context", &payload.context, &state.config)?;
+    PolicyService::check_input("requirements", &payload.requirements, &state.config)?;
+    let mut call = begin_ai_call(&state, payload.user_id, "tool")?;
+    let tool = ToolService::generate_tool(&payload, &template, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &mut call).await?;
+    finish_ai_call(&state, &call)?;
+    let diagnostics = ToolValidator::validate(&tool.component_code);
+    if ToolValidator::has_errors(&diagnostics) {
+        return Err(ApiError::new(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY, "TOOL_VALIDATION_FAILED", "The generated component failed validation")
+            .with_details(serde_json::json!({"diagnostics": diagnostics})));
+    }
     let response = ToolResponse {
         tool_id: tool.tool_id,
         tool_type: tool.tool_type.clone(),
@@ -28,19 +82,38 @@ async fn generate_tool(
         description: tool.description.clone(),
         component_code: tool.component_code.clone(),
         metadata: tool.metadata.clone(),
-        preview_url: format!("/tools/preview/{}", tool.tool_id),
+        preview_url: preview_url(tool.tool_id, payload.user_id),
+        diagnostics: diagnostics.clone(),
     };
     let conn = state.db.lock().expect("db lock");
-    repository::insert_tool(&conn, &tool)
+    repository::insert_tool(&conn, &tool, &diagnostics)
         .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store tool"))?;
 
     Ok(HttpResponse::Ok().json(wrap(response)))
 }
 
+async fn list_templates(state: web::Data<AppState>) -> Result<impl Responder, ApiError> {
+    let response = serde_json::json!({
+        "templates": state.templates.list(),
+    });
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn reload_templates(state: web::Data<AppState>, _admin: Admin) -> Result<impl Responder, ApiError> {
+    let errors = state.templates.reload();
+    let response = serde_json::json!({
+        "templates": state.templates.list(),
+        "errors": errors,
+    });
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
 async fn list_tools(
     state: web::Data<AppState>,
+    identity: Identity,
     query: web::Query<ToolListQuery>,
 ) -> Result<impl Responder, ApiError> {
+    identity.ensure(query.user_id)?;
     let conn = state.db.lock().expect("db lock");
     let mut tools = repository::list_tools(&conn, query.user_id, query.tool_type.as_deref())
         .unwrap_or_default()
@@ -75,13 +148,16 @@ async fn list_tools(
 
 async fn get_tool(
     state: web::Data<AppState>,
+    identity: Identity,
     path: web::Path<Uuid>,
 ) -> Result<impl Responder, ApiError> {
     let tool_id = path.into_inner();
     let conn = state.db.lock().expect("db lock");
-    let tool = repository::get_tool(&conn, tool_id)
-        .map_err(|_| ApiError::not_found("Tool not found"))?
-        .ok_or_else(|| ApiError::not_found("Tool not found"))?;
+    let tool = readable_tool(&conn, tool_id, &identity)?;
+    let provenance = repository::get_tool_provenance(&conn, tool_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool provenance"))?;
+    let fork = repository::get_tool_fork(&conn, tool_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load fork"))?;
 
     let response = ToolDetailResponse {
         tool_id: tool.tool_id,
@@ -91,6 +167,124 @@ async fn get_tool(
         description: tool.description,
         component_code: tool.component_code,
         metadata: tool.metadata,
+        provenance,
+        fork,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn export_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    query: web::Query<ToolExportQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    let (tool, head, entries) = {
+        let conn = state.db.lock().expect("db lock");
+        let tool = readable_tool(&conn, tool_id, &identity)?;
+        let head = load_version(&conn, tool_id, &tool.metadata.version)?;
+        let entries = if query.include_state {
+            Some(
+                repository::list_tool_state(&conn, tool_id, identity.user_id)
+                    .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool state"))?,
+            )
+        } else {
+            None
+        };
+        (tool, head, entries)
+    };
+    let bundle = ToolBundleService::export(&tool, &head, entries.as_deref(), state.config.tool_signing_key.as_deref(), Utc::now())?;
+    let file_name = tool
+        .name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_') { ch } else { '_' })
+        .collect::<String>();
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.tool.json\"", file_name)))
+        .json(bundle))
+}
+
+async fn signing_key(state: web::Data<AppState>) -> Result<impl Responder, ApiError> {
+    let public_key = state.config.tool_signing_key.as_deref().map(ToolBundleService::public_key).transpose()?;
+    let response = serde_json::json!({
+        "public_key": public_key,
+    });
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn import_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    query: web::Query<ToolImportQuery>,
+    payload: web::Json<serde_json::Value>,
+) -> Result<impl Responder, ApiError> {
+    identity.ensure(query.user_id)?;
+    let (bundle, verification) = ToolBundleService::verify(&payload, &state.config.tool_trusted_keys)?;
+    let content = bundle.content;
+    PolicyService::check_input("name", &content.tool.name, &state.config)?;
+    PolicyService::check_input("description", &content.tool.description, &state.config)?;
+    let diagnostics = ToolValidator::validate(&content.tool.component_code);
+    if ToolValidator::has_errors(&diagnostics) {
+        return Err(ApiError::new(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY, "TOOL_VALIDATION_FAILED", "The imported component failed validation")
+            .with_details(serde_json::json!({"diagnostics": diagnostics})));
+    }
+    let mut state_json = Vec::new();
+    for entry in content.state.iter().flatten() {
+        ToolStateService::validate_key(&entry.key)?;
+        let stored = state_json.iter().map(|(_, json): &(&str, String)| json.len()).sum();
+        state_json.push((entry.key.as_str(), ToolStateService::encode(&entry.value, stored, &state.config)?));
+    }
+
+    let now = Utc::now();
+    let tool = Tool {
+        tool_id: Uuid::new_v4(),
+        user_id: query.user_id,
+        tool_type: content.tool.tool_type.clone(),
+        name: content.tool.name.trim().to_string(),
+        description: content.tool.description.clone(),
+        component_code: content.tool.component_code.clone(),
+        metadata: ToolMetadata {
+            version: content.head.version.trim().to_string(),
+            created_at: now,
+            ..content.tool.metadata.clone()
+        },
+        usage_count: 0,
+        last_used: None,
+    };
+    let provenance = ToolProvenance {
+        tool_id: tool.tool_id,
+        source_tool_id: content.source_tool_id,
+        source_version: tool.metadata.version.clone(),
+        content_hash: verification.content_hash,
+        exported_at: content.exported_at,
+        signer_public_key: verification.signer_public_key,
+        signature_trusted: verification.trusted,
+        imported_at: now,
+    };
+    let conn = state.db.lock().expect("db lock");
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store tool");
+    repository::insert_tool(&conn, &tool, &diagnostics).map_err(database_error)?;
+    repository::insert_tool_provenance(&conn, &provenance).map_err(database_error)?;
+    for (key, json) in &state_json {
+        repository::put_tool_state(&conn, tool.tool_id, tool.user_id, key, json, 1).map_err(database_error)?;
+    }
+
+    let response = ToolImportResponse {
+        tool: ToolResponse {
+            tool_id: tool.tool_id,
+            tool_type: tool.tool_type,
+            name: tool.name,
+            description: tool.description,
+            component_code: tool.component_code,
+            metadata: tool.metadata,
+            preview_url: preview_url(tool.tool_id, query.user_id),
+            diagnostics,
+        },
+        provenance,
+        imported_state_keys: state_json.len(),
     };
 
     Ok(HttpResponse::Ok().json(wrap(response)))
@@ -98,36 +292,725 @@ async fn get_tool(
 
 async fn edit_tool(
     state: web::Data<AppState>,
+    identity: Identity,
     path: web::Path<Uuid>,
     payload: web::Json<ToolEditRequest>,
 ) -> Result<impl Responder, ApiError> {
     let tool_id = path.into_inner();
+    PolicyService::check_input("edit_instruction", &payload.edit_instruction, &state.config)?;
+    let mut payload = payload.into_inner();
+    let tool = {
+        let conn = state.db.lock().expect("db lock");
+        let tool = owned_tool(&conn, tool_id, &identity)?;
+        if payload.current_state.is_none()
+            && let Some(reference) = &payload.state_ref
+        {
+            identity.ensure(reference.user_id)?;
+            payload.current_state = Some(stored_state(&conn, tool_id, reference.user_id, reference.keys.as_deref())?);
+        }
+        tool
+    };
+    let mut call = begin_ai_call(&state, tool.user_id, "tool")?;
+    let edit = ToolService::edit_tool(&tool, &payload, &state.ai, &state.prompts, &ResponseCache::new(&state.db, &state.config), &mut call).await;
+    finish_ai_call(&state, &call)?;
+    let edit = edit?;
+
     let conn = state.db.lock().expect("db lock");
-    let tool = repository::get_tool(&conn, tool_id)
-        .map_err(|_| ApiError::not_found("Tool not found"))?
-        .ok_or_else(|| ApiError::not_found("Tool not found"))?;
-    let updated_code = format!("{}\n// {}", tool.component_code, payload.edit_instruction);
-    let version = "1.1.0".to_string();
-    repository::update_tool(&conn, tool_id, &updated_code, &version)
-        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to update tool"))?;
+    let revision = ToolVersion {
+        version_id: Uuid::new_v4(),
+        tool_id,
+        version: edit.version.clone(),
+        component_code: edit.component_code.clone(),
+        instruction: Some(payload.edit_instruction.clone()),
+        changes_summary: edit.changes_summary.clone(),
+        change_type: edit.change_type.clone(),
+        ai_model: Some(edit.ai_model),
+        prompt_version: Some(edit.prompt_version.clone()),
+        diagnostics: edit.diagnostics.clone(),
+        created_at: Utc::now(),
+    };
+    if !repository::update_tool(&conn, &revision, identity.user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to update tool"))?
+    {
+        return Err(ApiError::not_found("Tool not found"));
+    }
 
     let response = ToolEditResponse {
         tool_id,
-        updated_component_code: updated_code,
-        changes_summary: "Applied AI edits".to_string(),
-        version,
+        updated_component_code: edit.component_code,
+        changes_summary: edit.changes_summary,
+        change_type: edit.change_type,
+        previous_version: tool.metadata.version,
+        version: edit.version,
+        prompt_version: edit.prompt_version,
+        diagnostics: edit.diagnostics,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn list_versions(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    let tool = readable_tool(&conn, tool_id, &identity)?;
+    let mut versions = repository::list_tool_versions(&conn, tool_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool versions"))?;
+    versions.reverse();
+
+    let response = ToolVersionListResponse {
+        tool_id,
+        current_version: tool.metadata.version,
+        versions,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn diff_versions(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    query: web::Query<ToolDiffQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    let tool = readable_tool(&conn, tool_id, &identity)?;
+    let from = load_version(&conn, tool_id, &query.from)?;
+    let to = load_version(&conn, tool_id, query.to.as_deref().unwrap_or(&tool.metadata.version))?;
+
+    Ok(HttpResponse::Ok().json(wrap(ToolService::diff(&from, &to))))
+}
+
+async fn rollback_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    payload: web::Json<ToolRollbackRequest>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    let tool = owned_tool(&conn, tool_id, &identity)?;
+    let target = load_version(&conn, tool_id, &payload.version)?;
+    if target.version == tool.metadata.version {
+        return Err(ApiError::validation(format!("Version {} is already the current version", target.version)));
+    }
+    let revision = ToolVersion {
+        version_id: Uuid::new_v4(),
+        tool_id,
+        version: ToolService::bump_version(&tool.metadata.version, "patch"),
+        component_code: target.component_code.clone(),
+        instruction: None,
+        changes_summary: format!("Rolled back to version {}", target.version),
+        change_type: "rollback".to_string(),
+        ai_model: None,
+        prompt_version: target.prompt_version.clone(),
+        diagnostics: target.diagnostics.clone(),
+        created_at: Utc::now(),
+    };
+    if !repository::update_tool(&conn, &revision, identity.user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to roll back tool"))?
+    {
+        return Err(ApiError::not_found("Tool not found"));
+    }
+
+    let response = ToolRollbackResponse {
+        tool_id,
+        restored_from: target.version,
+        previous_version: tool.metadata.version,
+        version: revision.version,
+        component_code: revision.component_code,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+fn preview_url(tool_id: Uuid, user_id: Uuid) -> String {
+    format!("/tools/preview/{}?user_id={}", tool_id, user_id)
+}
+
+async fn preview_tool(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    query: web::Query<ToolOwnerQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool = {
+        let conn = state.db.lock().expect("db lock");
+        readable_tool(&conn, path.into_inner(), &Identity { user_id: query.user_id })?
+    };
+    let runtime = current_runtime(&state)?;
+    let script = ToolPreview::transpile(&tool.component_code).map_err(|diagnostics| {
+        ApiError::new(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY, "TOOL_PREVIEW_FAILED", "The component could not be compiled for preview")
+            .with_details(serde_json::json!({"diagnostics": diagnostics}))
+    })?;
+    let nonce = format!("{:032x}", rand::random::<u128>());
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header(("Content-Security-Policy", ToolPreview::content_security_policy(&nonce)))
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .insert_header(("Referrer-Policy", "no-referrer"))
+        .insert_header(("Cache-Control", "no-store"))
+        .insert_header(("X-Preview-Runtime", runtime.digest.clone()))
+        .body(ToolPreview::render_page(&tool, &script, &runtime.digest, &nonce)))
+}
+
+async fn preview_runtime(state: web::Data<AppState>, req: HttpRequest) -> Result<impl Responder, ApiError> {
+    let runtime = current_runtime(&state)?;
+    let etag = format!("\"{}\"", runtime.digest);
+    let cached = req
+        .headers()
+        .get(actix_web::http::header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+    let mut response = if cached { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+    response
+        .insert_header(("ETag", etag))
+        .insert_header(("Cache-Control", "public, max-age=31536000, immutable"))
+        .insert_header(("X-Content-Type-Options", "nosniff"));
+    if cached {
+        return Ok(response.finish());
+    }
+    Ok(response.content_type("text/javascript; charset=utf-8").body(runtime.source.clone()))
+}
+
+fn current_runtime(state: &AppState) -> Result<std::sync::Arc<RuntimeBundle>, ApiError> {
+    state.preview_runtime.current().ok_or_else(|| {
+        ApiError::new(actix_web::http::StatusCode::SERVICE_UNAVAILABLE, "PREVIEW_RUNTIME_MISSING", "The preview runtime bundle is not installed")
+    })
+}
+
+const USAGE_EVENT_TYPES: [&str; 3] = ["open", "interact", "close"];
+const MAX_USAGE_DURATION_SECONDS: u32 = 24 * 60 * 60;
+
+async fn record_usage(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    payload: web::Json<ToolUsageEventRequest>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    if !USAGE_EVENT_TYPES.contains(&payload.event_type.as_str()) {
+        return Err(ApiError::validation(format!("event_type must be one of {}", USAGE_EVENT_TYPES.join(", "))));
+    }
+    if payload.duration_seconds.is_some_and(|seconds| seconds > MAX_USAGE_DURATION_SECONDS) {
+        return Err(ApiError::validation("duration_seconds cannot exceed one day"));
+    }
+    identity.ensure(payload.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    let owner = readable_tool(&conn, tool_id, &identity)?.user_id;
+    let subject = match payload.task_id {
+        Some(task_id) => {
+            let task = repository::get_stored_task(&conn, task_id)
+                .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load task"))?
+                .filter(|task| task.user_id == identity.user_id)
+                .ok_or_else(|| ApiError::not_found("Task not found"))?;
+            Some(task.task.subject)
+        }
+        None => None,
+    };
+    let event = ToolUsageEvent {
+        event_id: Uuid::new_v4(),
+        tool_id,
+        user_id: payload.user_id,
+        event_type: payload.event_type.clone(),
+        task_id: payload.task_id,
+        subject,
+        duration_seconds: payload.duration_seconds,
+        created_at: Utc::now(),
+    };
+    repository::insert_tool_usage_event(&conn, &event)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to record tool usage"))?;
+    let (usage_count, last_used) = if owner == identity.user_id {
+        let tool = load_tool(&conn, tool_id)?;
+        (tool.usage_count, tool.last_used)
+    } else {
+        repository::get_user_tool_usage(&conn, tool_id, identity.user_id)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool usage"))?
+    };
+
+    let response = ToolUsageEventResponse {
+        event_id: event.event_id,
+        tool_id,
+        usage_count,
+        last_used,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn tool_usage(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    query: web::Query<ToolUsageQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    let (from, to) = usage_window(query.from_date.as_deref(), query.to_date.as_deref(), Utc::now().date_naive() - chrono::Duration::days(29))?;
+    let conn = state.db.lock().expect("db lock");
+    let tool = owned_tool(&conn, tool_id, &identity)?;
+    let load = |group: &str| {
+        repository::get_tool_usage_breakdown(&conn, "tool", tool_id, group, &from, &to)
+            .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool usage"))
+    };
+    let by_subject = load("subject")?;
+    let by_day = load("day")?;
+
+    let response = ToolUsageResponse {
+        tool_id,
+        usage_count: tool.usage_count,
+        last_used: tool.last_used,
+        totals: sum_usage(by_day.iter().map(|day| &day.totals)),
+        by_subject,
+        by_day,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn user_tool_usage(
+    state: web::Data<AppState>,
+    identity: Identity,
+    query: web::Query<ToolUsageAnalyticsQuery>,
+) -> Result<impl Responder, ApiError> {
+    let (from, to) = usage_window(query.from_date.as_deref(), query.to_date.as_deref(), Utc::now().date_naive() - chrono::Duration::days(29))?;
+    identity.ensure(query.user_id)?;
+    let top = query.top.unwrap_or(5).clamp(1, 50);
+    let conn = state.db.lock().expect("db lock");
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool usage");
+    let by_day = repository::get_tool_usage_breakdown(&conn, "user", query.user_id, "day", &from, &to).map_err(database_error)?;
+    let top_tools = repository::get_top_tools(&conn, query.user_id, &from, &to, top).map_err(database_error)?;
+    let by_subject = repository::get_top_tools_by_subject(&conn, query.user_id, &from, &to, top).map_err(database_error)?;
+
+    let response = UserToolUsageResponse {
+        user_id: query.user_id,
+        totals: sum_usage(by_day.iter().map(|day| &day.totals)),
+        top_tools,
+        by_subject,
+        by_day,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+fn sum_usage<'a>(rows: impl Iterator<Item = &'a ToolUsageTotals>) -> ToolUsageTotals {
+    rows.fold(ToolUsageTotals::default(), |mut totals, row| {
+        totals.opens += row.opens;
+        totals.interactions += row.interactions;
+        totals.duration_seconds += row.duration_seconds;
+        totals
+    })
+}
+
+async fn list_state(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    query: web::Query<ToolStateQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    identity.ensure(query.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    readable_tool(&conn, tool_id, &identity)?;
+    let entries = repository::list_tool_state(&conn, tool_id, query.user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool state"))?;
+
+    let response = ToolStateListResponse {
+        tool_id,
+        user_id: query.user_id,
+        total_bytes: entries.iter().map(|entry| entry.size_bytes).sum(),
+        limit_bytes: state.config.tool_state_max_total_bytes,
+        entries,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn get_state(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<(Uuid, String)>,
+    query: web::Query<ToolStateQuery>,
+) -> Result<impl Responder, ApiError> {
+    let (tool_id, key) = path.into_inner();
+    ToolStateService::validate_key(&key)?;
+    identity.ensure(query.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    readable_tool(&conn, tool_id, &identity)?;
+    let entry = load_state(&conn, tool_id, query.user_id, &key)?.ok_or_else(|| ApiError::not_found(format!("No state stored under '{}'", key)))?;
+    Ok(HttpResponse::Ok().json(wrap(entry)))
+}
+
+async fn put_state(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<(Uuid, String)>,
+    payload: web::Json<ToolStatePutRequest>,
+) -> Result<impl Responder, ApiError> {
+    let (tool_id, key) = path.into_inner();
+    ToolStateService::validate_key(&key)?;
+    identity.ensure(payload.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    readable_tool(&conn, tool_id, &identity)?;
+    let entry = save_state(&state.config, &conn, tool_id, payload.user_id, &key, payload.expected_revision, |_| payload.value.clone())?;
+    Ok(HttpResponse::Ok().json(wrap(entry)))
+}
+
+async fn patch_state(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<(Uuid, String)>,
+    payload: web::Json<ToolStatePatchRequest>,
+) -> Result<impl Responder, ApiError> {
+    let (tool_id, key) = path.into_inner();
+    ToolStateService::validate_key(&key)?;
+    identity.ensure(payload.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    readable_tool(&conn, tool_id, &identity)?;
+    let entry = save_state(&state.config, &conn, tool_id, payload.user_id, &key, payload.expected_revision, |current| {
+        let mut value = current.map(|entry| entry.value.clone()).unwrap_or(serde_json::Value::Null);
+        ToolStateService::merge_patch(&mut value, &payload.patch);
+        value
+    })?;
+    Ok(HttpResponse::Ok().json(wrap(entry)))
+}
+
+fn load_state(conn: &rusqlite::Connection, tool_id: Uuid, user_id: Uuid, key: &str) -> Result<Option<ToolStateEntry>, ApiError> {
+    repository::get_tool_state(conn, tool_id, user_id, key)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool state"))
+}
+
+fn save_state(
+    config: &AppConfig,
+    conn: &rusqlite::Connection,
+    tool_id: Uuid,
+    user_id: Uuid,
+    key: &str,
+    expected_revision: Option<u64>,
+    update: impl FnOnce(Option<&ToolStateEntry>) -> serde_json::Value,
+) -> Result<ToolStateEntry, ApiError> {
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to store tool state");
+    let current = load_state(conn, tool_id, user_id, key)?;
+    let revision = ToolStateService::check_revision(current.as_ref(), expected_revision)?;
+    let other_keys = repository::get_tool_state_size(conn, tool_id, user_id, key).map_err(database_error)?;
+    let json = ToolStateService::encode(&update(current.as_ref()), other_keys, config)?;
+    if !repository::put_tool_state(conn, tool_id, user_id, key, &json, revision).map_err(database_error)? {
+        let current = load_state(conn, tool_id, user_id, key)?.map(|entry| entry.revision).unwrap_or(0);
+        return Err(ToolStateError::Conflict { expected: revision - 1, current }.into());
+    }
+    load_state(conn, tool_id, user_id, key)?.ok_or_else(|| ApiError::not_found("Tool state not found"))
+}
+
+fn stored_state(conn: &rusqlite::Connection, tool_id: Uuid, user_id: Uuid, keys: Option<&[String]>) -> Result<serde_json::Value, ApiError> {
+    let entries = repository::list_tool_state(conn, tool_id, user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool state"))?;
+    Ok(serde_json::Value::Object(
+        entries
+            .into_iter()
+            .filter(|entry| keys.is_none_or(|keys| keys.contains(&entry.key)))
+            .map(|entry| (entry.key, entry.value))
+            .collect(),
+    ))
+}
+
+async fn catalogue(
+    state: web::Data<AppState>,
+    query: web::Query<CatalogueQuery>,
+) -> Result<impl Responder, ApiError> {
+    let sort = query.sort.as_deref().unwrap_or("recent");
+    if !CATALOGUE_SORTS.contains(&sort) {
+        return Err(ApiError::validation(format!("sort must be one of {}", CATALOGUE_SORTS.join(", "))));
+    }
+    let text = query.q.as_deref().map(str::trim).filter(|text| !text.is_empty());
+    let subject = query.subject.as_deref().map(str::trim).filter(|subject| !subject.is_empty());
+    let conn = state.db.lock().expect("db lock");
+    let mut tools = repository::search_catalogue(&conn, query.tool_type.as_deref(), subject, text, sort)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to search the catalogue"))?;
+
+    let total = tools.len();
+    let limit = query.limit.unwrap_or(20);
+    let offset = query.offset.unwrap_or(0);
+    if offset < tools.len() {
+        tools = tools.into_iter().skip(offset).take(limit).collect();
+    } else {
+        tools.clear();
+    }
+
+    let response = CatalogueResponse {
+        tools,
+        total,
+        limit,
+        offset,
     };
 
     Ok(HttpResponse::Ok().json(wrap(response)))
 }
 
+async fn publish_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    payload: web::Json<ToolPublishRequest>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    identity.ensure(payload.user_id)?;
+    let subject = ToolCatalogueService::normalize_subject(payload.subject.as_deref())?;
+    if let Some(subject) = &subject {
+        PolicyService::check_input("subject", subject, &state.config)?;
+    }
+    let conn = state.db.lock().expect("db lock");
+    owned_tool(&conn, tool_id, &identity)?;
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to publish tool");
+    repository::publish_tool(&conn, tool_id, subject.as_deref()).map_err(database_error)?;
+    let entry = repository::get_catalogue_entry(&conn, tool_id)
+        .map_err(database_error)?
+        .ok_or(ForkError::NotPublished(tool_id))?;
+
+    Ok(HttpResponse::Ok().json(wrap(entry)))
+}
+
+async fn unpublish_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    query: web::Query<ToolOwnerQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    identity.ensure(query.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    owned_tool(&conn, tool_id, &identity)?;
+    let removed = repository::unpublish_tool(&conn, tool_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to unpublish tool"))?;
+    if !removed {
+        return Err(ForkError::NotPublished(tool_id).into());
+    }
+
+    let response = serde_json::json!({
+        "tool_id": tool_id,
+        "published": false,
+    });
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn rate_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    payload: web::Json<ToolRatingRequest>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    identity.ensure(payload.user_id)?;
+    ToolCatalogueService::validate_rating(payload.rating)?;
+    let conn = state.db.lock().expect("db lock");
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to rate tool");
+    let entry = repository::get_catalogue_entry(&conn, tool_id)
+        .map_err(database_error)?
+        .ok_or(ForkError::NotPublished(tool_id))?;
+    if entry.owner_id == payload.user_id {
+        return Err(ApiError::validation("You cannot rate your own tool"));
+    }
+    repository::upsert_tool_rating(&conn, tool_id, payload.user_id, payload.rating).map_err(database_error)?;
+    let entry = repository::get_catalogue_entry(&conn, tool_id)
+        .map_err(database_error)?
+        .ok_or(ForkError::NotPublished(tool_id))?;
+
+    let response = ToolRatingResponse {
+        tool_id,
+        rating: payload.rating,
+        rating_average: entry.rating_average,
+        rating_count: entry.rating_count,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn fork_tool(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    payload: web::Json<ToolOwnerQuery>,
+) -> Result<impl Responder, ApiError> {
+    let upstream_id = path.into_inner();
+    identity.ensure(payload.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to fork tool");
+    let upstream = readable_tool(&conn, upstream_id, &identity)?;
+    let head = load_version(&conn, upstream_id, &upstream.metadata.version)?;
+
+    let now = Utc::now();
+    let tool = Tool {
+        tool_id: Uuid::new_v4(),
+        user_id: payload.user_id,
+        metadata: ToolMetadata {
+            created_at: now,
+            ..upstream.metadata.clone()
+        },
+        usage_count: 0,
+        last_used: None,
+        ..upstream
+    };
+    let fork = ToolFork {
+        tool_id: tool.tool_id,
+        upstream_tool_id: upstream_id,
+        synced_version: head.version.clone(),
+        forked_at: now,
+    };
+    repository::insert_tool(&conn, &tool, &head.diagnostics).map_err(database_error)?;
+    repository::insert_tool_fork(&conn, &fork).map_err(database_error)?;
+
+    let response = ToolForkResponse {
+        tool: ToolResponse {
+            tool_id: tool.tool_id,
+            tool_type: tool.tool_type,
+            name: tool.name,
+            description: tool.description,
+            component_code: tool.component_code,
+            metadata: tool.metadata,
+            preview_url: preview_url(tool.tool_id, payload.user_id),
+            diagnostics: head.diagnostics,
+        },
+        fork,
+    };
+
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+fn load_upstream(conn: &rusqlite::Connection, tool_id: Uuid, identity: &Identity) -> Result<(ToolFork, ToolVersion, Tool), ApiError> {
+    let fork = repository::get_tool_fork(conn, tool_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load fork"))?
+        .ok_or(ForkError::NotAFork(tool_id))?;
+    let upstream = match repository::get_tool_for_user(conn, fork.upstream_tool_id, identity.user_id)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load upstream tool"))?
+    {
+        Some((upstream, ToolAccess::Owner | ToolAccess::Shared)) => upstream,
+        _ => return Err(ForkError::UpstreamGone.into()),
+    };
+    let base = repository::get_tool_version(conn, fork.upstream_tool_id, &fork.synced_version)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load upstream version"))?
+        .ok_or(ForkError::UpstreamGone)?;
+    Ok((fork, base, upstream))
+}
+
+async fn upstream_status(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    let conn = state.db.lock().expect("db lock");
+    let tool = readable_tool(&conn, tool_id, &identity)?;
+    let (fork, base, upstream) = load_upstream(&conn, tool_id, &identity)?;
+    let behind = upstream.metadata.version != fork.synced_version;
+    let (diff, mergeable) = if behind {
+        let head = load_version(&conn, upstream.tool_id, &upstream.metadata.version)?;
+        let mergeable = ToolCatalogueService::merge_upstream(&base.component_code, &tool.component_code, &head.component_code, &head.version).is_ok();
+        (Some(ToolService::diff(&base, &head)), mergeable)
+    } else {
+        (None, false)
+    };
+
+    let response = ToolUpstreamStatus {
+        tool_id,
+        upstream_tool_id: fork.upstream_tool_id,
+        synced_version: fork.synced_version,
+        upstream_version: upstream.metadata.version,
+        behind,
+        mergeable,
+        diff,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+async fn merge_upstream(
+    state: web::Data<AppState>,
+    identity: Identity,
+    path: web::Path<Uuid>,
+    payload: web::Json<ToolOwnerQuery>,
+) -> Result<impl Responder, ApiError> {
+    let tool_id = path.into_inner();
+    identity.ensure(payload.user_id)?;
+    let conn = state.db.lock().expect("db lock");
+    let tool = owned_tool(&conn, tool_id, &identity)?;
+    let (fork, base, upstream) = load_upstream(&conn, tool_id, &identity)?;
+    if upstream.metadata.version == fork.synced_version {
+        return Err(ForkError::UpToDate(fork.synced_version).into());
+    }
+    let merged = ToolCatalogueService::merge_upstream(&base.component_code, &tool.component_code, &upstream.component_code, &upstream.metadata.version)?;
+    let diagnostics = ToolValidator::validate(&merged);
+    if ToolValidator::has_errors(&diagnostics) {
+        return Err(ApiError::new(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY, "TOOL_VALIDATION_FAILED", "The merged component failed validation")
+            .with_details(serde_json::json!({"diagnostics": diagnostics})));
+    }
+
+    let revision = ToolVersion {
+        version_id: Uuid::new_v4(),
+        tool_id,
+        version: ToolService::bump_version(&tool.metadata.version, "minor"),
+        component_code: merged,
+        instruction: None,
+        changes_summary: format!("Merged upstream version {}", upstream.metadata.version),
+        change_type: "minor".to_string(),
+        ai_model: None,
+        prompt_version: upstream.metadata.prompt_version.clone(),
+        diagnostics,
+        created_at: Utc::now(),
+    };
+    let database_error = |_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to merge upstream changes");
+    if !repository::update_tool(&conn, &revision, identity.user_id).map_err(database_error)? {
+        return Err(ApiError::not_found("Tool not found"));
+    }
+    repository::update_fork_sync(&conn, tool_id, &upstream.metadata.version).map_err(database_error)?;
+
+    let response = ToolMergeResponse {
+        tool_id,
+        previous_version: tool.metadata.version,
+        version: revision.version,
+        upstream_version: upstream.metadata.version,
+        component_code: revision.component_code,
+        diagnostics: revision.diagnostics,
+    };
+    Ok(HttpResponse::Ok().json(wrap(response)))
+}
+
+fn readable_tool(conn: &rusqlite::Connection, tool_id: Uuid, identity: &Identity) -> Result<Tool, ApiError> {
+    authorize_tool(conn, tool_id, identity, false)
+}
+
+fn owned_tool(conn: &rusqlite::Connection, tool_id: Uuid, identity: &Identity) -> Result<Tool, ApiError> {
+    authorize_tool(conn, tool_id, identity, true)
+}
+
+fn authorize_tool(conn: &rusqlite::Connection, tool_id: Uuid, identity: &Identity, write: bool) -> Result<Tool, ApiError> {
+    let (tool, access) = repository::get_tool_for_user(conn, tool_id, identity.user_id)
+        .map_err(|_| ApiError::not_found("Tool not found"))?
+        .ok_or_else(|| ApiError::not_found("Tool not found"))?;
+    match access {
+        ToolAccess::Owner => Ok(tool),
+        ToolAccess::Shared if !write => Ok(tool),
+        ToolAccess::Shared => Err(ApiError::new(actix_web::http::StatusCode::FORBIDDEN, "TOOL_ACCESS_DENIED", "Only the owner can change this tool")),
+        ToolAccess::Denied => Err(ApiError::new(actix_web::http::StatusCode::FORBIDDEN, "TOOL_ACCESS_DENIED", "This tool belongs to another user and is not shared")),
+    }
+}
+
+fn load_tool(conn: &rusqlite::Connection, tool_id: Uuid) -> Result<Tool, ApiError> {
+    repository::get_tool(conn, tool_id)
+        .map_err(|_| ApiError::not_found("Tool not found"))?
+        .ok_or_else(|| ApiError::not_found("Tool not found"))
+}
+
+fn load_version(conn: &rusqlite::Connection, tool_id: Uuid, version: &str) -> Result<ToolVersion, ApiError> {
+    repository::get_tool_version(conn, tool_id, version)
+        .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load tool version"))?
+        .ok_or_else(|| ApiError::not_found(format!("Version {} not found", version)))
+}
+
 async fn delete_tool(
     state: web::Data<AppState>,
+    identity: Identity,
     path: web::Path<Uuid>,
 ) -> Result<impl Responder, ApiError> {
     let tool_id = path.into_inner();
     let conn = state.db.lock().expect("db lock");
-    let removed = repository::delete_tool(&conn, tool_id)
+    owned_tool(&conn, tool_id, &identity)?;
+    let removed = repository::delete_tool(&conn, tool_id, identity.user_id)
         .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to delete tool"))?;
     if !removed {
         return Err(ApiError::not_found("Tool not found"));
@@ -40,6 +40,7 @@ async fn get_stats(
     {
         return Err(ApiError::not_found("User not found"));
     }
+    crate::api::focus::close_idle_sessions(&conn, &state.config)?;
     let response = repository::get_user_stats(&conn, user_id)
         .map_err(|_| ApiError::new(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR", "Failed to load user stats"))?;
 
@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use actix_web::{App, HttpResponse, HttpServer, web};
+use backend::AppState;
+use backend::models::ai::AiCall;
+use backend::services::eval_service::{CaseReport, EvalService};
+use backend::services::plan_service::PlanService;
+use backend::services::response_cache::ResponseCache;
+use backend::utils::config::AppConfig;
+
+struct Options {
+    corpus: PathBuf,
+    baseline: PathBuf,
+    tolerance: f64,
+    live: bool,
+    record: bool,
+    write_baseline: bool,
+}
+
+type Replies = Arc<Mutex<VecDeque<String>>>;
+
+async fn replay(replies: web::Data<Replies>, body: web::Json<serde_json::Value>) -> HttpResponse {
+    let content = replies.lock().expect("replies lock").pop_front();
+    match content {
+        Some(content) => HttpResponse::Ok().json(serde_json::json!({
+            "model": body["model"],
+            "choices": [{"message": {"role": "assistant", "content": content}}],
+        })),
+        None => HttpResponse::BadRequest().json(serde_json::json!({"error": {"message": "no recorded response for this request"}})),
+    }
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        corpus: PathBuf::from("eval/corpus"),
+        baseline: PathBuf::from("eval/baseline.json"),
+        tolerance: 0.0,
+        live: false,
+        record: false,
+        write_baseline: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--corpus" => options.corpus = args.next().ok_or("--corpus needs a directory")?.into(),
+            "--baseline" => options.baseline = args.next().ok_or("--baseline needs a file")?.into(),
+            "--tolerance" => {
+                options.tolerance = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--tolerance needs a number")?
+            }
+            "--live" => options.live = true,
+            "--record" => options.record = true,
+            "--write-baseline" => options.write_baseline = true,
+            "--help" | "-h" => {
+                return Err("usage: eval [--corpus DIR] [--baseline FILE] [--tolerance N] [--live [--record]] [--write-baseline]".to_string());
+            }
+            other => return Err(format!("unknown argument {}", other)),
+        }
+    }
+    if options.record && !options.live {
+        return Err("--record requires --live".to_string());
+    }
+    Ok(options)
+}
+
+#[actix_web::main]
+async fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    let mut cases = match EvalService::load_corpus(&options.corpus) {
+        Ok(cases) => cases,
+        Err(message) => {
+            eprintln!("failed to load corpus: {}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let replies: Replies = Arc::new(Mutex::new(VecDeque::new()));
+    let mut config = if options.live {
+        let _ = dotenvy::dotenv();
+        AppConfig::from_env()
+    } else {
+        let data = web::Data::new(replies.clone());
+        let server = HttpServer::new(move || App::new().app_data(data.clone()).route("/chat/completions", web::post().to(replay)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .expect("bind replay server");
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        let mut config = AppConfig::for_test();
+        config.ai_provider = "openrouter".to_string();
+        config.openrouter_api_key = Some("replay".to_string());
+        config.openrouter_base_url = format!("http://{}", address);
+        config.ai_max_retries = 0;
+        config.ai_breaker_threshold = u32::MAX;
+        config
+    };
+    config.database_path = ":memory:".to_string();
+    config.ai_cache_ttl_secs = 0;
+    config.ai_plan_generation = true;
+    let state = AppState::new(config).expect("failed to initialize eval state");
+    if !state.ai.is_enabled() {
+        eprintln!("no AI provider is configured; set OPENROUTER_API_KEY or run without --live");
+        std::process::exit(2);
+    }
+
+    let mut reports: Vec<CaseReport> = Vec::new();
+    for case in cases.iter_mut() {
+        *replies.lock().expect("replies lock") = case.responses.iter().cloned().collect();
+        let mut call = AiCall::new(case.request.user_id, "plan");
+        let cache = ResponseCache::new(&state.db, &state.config);
+        let (plan, transcript) = PlanService::generate_traced(&case.request, &state.ai, &state.prompts, &cache, &state.config, &mut call).await;
+        if let Some(fallback) = &plan.fallback {
+            eprintln!("{}: {} ({})", case.name, fallback.message, fallback.code);
+        }
+        if options.record && !transcript.is_empty() {
+            case.responses = transcript.clone();
+            let path = options.corpus.join(format!("{}.json", case.name));
+            let written = serde_json::to_string_pretty(&case).map_err(|err| err.to_string()).and_then(|text| std::fs::write(&path, text + "\n").map_err(|err| err.to_string()));
+            if let Err(message) = written {
+                eprintln!("failed to record {}: {}", path.display(), message);
+            }
+        }
+        let raw = transcript.last().cloned().unwrap_or_default();
+        reports.push(EvalService::score_plan_output(&case.name, &case.request, &raw));
+    }
+
+    let baseline = EvalService::load_baseline(&options.baseline);
+    let comparisons = EvalService::compare(&reports, &baseline, options.tolerance);
+    print!("{}", EvalService::render_report(&reports, &comparisons));
+
+    if options.write_baseline {
+        let scores = reports.iter().map(|report| (report.name.clone(), report.score)).collect::<BTreeMap<_, _>>();
+        let text = serde_json::to_string_pretty(&scores).unwrap_or_default() + "\n";
+        if let Err(err) = std::fs::write(&options.baseline, text) {
+            eprintln!("failed to write {}: {}", options.baseline.display(), err);
+            std::process::exit(2);
+        }
+        println!("baseline written to {}", options.baseline.display());
+    } else if comparisons.iter().any(|comparison| comparison.regressed) {
+        std::process::exit(1);
+    }
+}
@@ -3,6 +3,10 @@ use std::fs;
 use std::path::Path;
 use std::sync::Mutex;
 
+use crate::services::ai_client::AiClient;
+use crate::services::prompt_registry::PromptRegistry;
+use crate::services::template_registry::TemplateRegistry;
+use crate::services::tool_preview::PreviewRuntime;
 use crate::utils::config::AppConfig;
 
 pub mod repository;
@@ -11,21 +15,29 @@ pub mod schema;
 pub struct AppState {
     pub db: Mutex<Connection>,
     pub config: AppConfig,
+    pub ai: AiClient,
+    pub prompts: PromptRegistry,
+    pub templates: TemplateRegistry,
+    pub preview_runtime: PreviewRuntime,
 }
 
 impl AppState {
     pub fn new(config: AppConfig) -> Result<Self, rusqlite::Error> {
-        if config.database_path != ":memory:" {
-            if let Some(parent) = Path::new(&config.database_path).parent() {
-                if !parent.as_os_str().is_empty() {
-                    let _ = fs::create_dir_all(parent);
-                }
-            }
+        if config.database_path != ":memory:"
+            && let Some(parent) = Path::new(&config.database_path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            let _ = fs::create_dir_all(parent);
         }
         let connection = Connection::open(&config.database_path)?;
         schema::apply(&connection)?;
+        repository::rebuild_knowledge_index(&connection)?;
         Ok(Self {
             db: Mutex::new(connection),
+            ai: AiClient::new(&config),
+            prompts: PromptRegistry::load(&config.data_dir),
+            templates: TemplateRegistry::load(&config.data_dir),
+            preview_runtime: PreviewRuntime::new(&config.preview_runtime_path),
             config,
         })
     }
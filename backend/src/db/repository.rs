@@ -3,10 +3,14 @@ use rusqlite::{Connection, OptionalExtension, Result, params};
 use uuid::Uuid;
 
 use crate::models::{
-    ai::AiChatRequest,
+    ai::{AiBudget, AiCall, AiChatRequest, CachedResponse, KnowledgeChunk, UsageBreakdown, UsageTotals},
+    flashcard::{Card, CardReview, CardSchedule, Deck, DeckDueCount, DeckSummary, DueCard},
+    focus::FocusSession,
     plan::{Plan, PlanGenerateRequest, StoredTask, Task},
+    quiz::{Quiz, QuizAttempt, TopicMastery},
     reminder::Reminder,
-    tool::{Tool, ToolMetadata},
+    search::{DocumentSearch, SearchResult},
+    tool::{CatalogueEntry, Tool, ToolAccess, ToolDiagnostic, ToolFork, ToolMetadata, ToolProvenance, ToolStateEntry, ToolSubjectUsage, ToolUsageBreakdown, ToolUsageEvent, ToolUsageRanking, ToolUsageTotals, ToolVersion},
     user::{UserProfile, UserProfileRequest, UserStatsResponse, WeeklyActivity},
 };
 
@@ -54,15 +58,16 @@ pub fn ensure_user_id(conn: &Connection, user_id: Uuid) -> Result<()> {
 pub fn insert_plan(conn: &Connection, request: &PlanGenerateRequest, plan: &Plan) -> Result<()> {
     ensure_user_id(conn, request.user_id)?;
     conn.execute(
-        "INSERT INTO study_plans (id, user_id, plan_type, start_date, end_date, subjects_json, ai_rationale)
-         VALUES (?1, ?2, 'weekly', ?3, ?4, ?5, ?6)",
+        "INSERT INTO study_plans (id, user_id, plan_type, start_date, end_date, subjects_json, ai_rationale, prompt_version)
+         VALUES (?1, ?2, 'weekly', ?3, ?4, ?5, ?6, ?7)",
         params![
             plan.plan_id.to_string(),
             request.user_id.to_string(),
             plan.weekly_plan.week_start,
             plan.weekly_plan.week_end,
             serde_json::to_string(&plan.weekly_plan.subjects).unwrap_or_default(),
-            plan.ai_rationale
+            plan.ai_rationale,
+            plan.prompt_version
         ],
     )?;
 
@@ -85,6 +90,7 @@ pub fn insert_plan(conn: &Connection, request: &PlanGenerateRequest, plan: &Plan
                     task.ai_notes
                 ],
             )?;
+            index_task(conn, request.user_id, &daily.date, task)?;
         }
     }
     Ok(())
@@ -160,18 +166,18 @@ pub fn get_overdue_tasks(conn: &Connection, user_id: Uuid, today: chrono::NaiveD
     let mut overdue = Vec::new();
     for row in rows {
         let (id, subject, topic, due_date, priority, status, parsed) = row?;
-        if let Some(due) = parsed {
-            if due < today {
-                overdue.push(serde_json::json!({
-                    "task_id": id,
-                    "subject": subject,
-                    "topic": topic,
-                    "due_date": due_date,
-                    "days_overdue": (today - due).num_days(),
-                    "priority": priority,
-                    "status": status,
-                }));
-            }
+        if let Some(due) = parsed
+            && due < today
+        {
+            overdue.push(serde_json::json!({
+                "task_id": id,
+                "subject": subject,
+                "topic": topic,
+                "due_date": due_date,
+                "days_overdue": (today - due).num_days(),
+                "priority": priority,
+                "status": status,
+            }));
         }
     }
     Ok(overdue)
@@ -183,38 +189,68 @@ pub fn update_task(conn: &Connection, task_id: Uuid, status: &str, actual_durati
     }
     let completed_at = if status == "completed" { Some(Utc::now().to_rfc3339()) } else { None };
     conn.execute(
-        "UPDATE tasks SET status = ?1, actual_duration = ?2, user_notes = ?3, completed_at = ?4 WHERE id = ?5",
+        "UPDATE tasks SET status = ?1, actual_duration = COALESCE(?2, actual_duration), user_notes = ?3, completed_at = ?4 WHERE id = ?5",
         params![status, actual_duration, notes, completed_at, task_id.to_string()],
     )?;
-    let mut stmt = conn.prepare(
-        "SELECT t.subject, t.topic, t.duration_minutes, t.start_time, t.due_date, t.priority, t.resources_json, t.ai_notes, t.status, t.actual_duration, t.user_notes, p.user_id, t.date
-         FROM tasks t JOIN study_plans p ON t.plan_id = p.id WHERE t.id = ?1",
-    )?;
-    let stored = stmt.query_row([task_id.to_string()], |row| {
-        let resources: String = row.get(6)?;
-        Ok(StoredTask {
-            task: Task {
-                id: task_id,
-                subject: row.get(0)?,
-                topic: row.get(1)?,
-                duration_minutes: row.get(2)?,
-                start_time: row.get(3)?,
-                due_date: row.get(4)?,
-                priority: row.get(5)?,
-                resources: serde_json::from_str(&resources).unwrap_or_default(),
-                ai_notes: row.get(7)?,
-            },
-            status: row.get(8)?,
-            actual_duration: row.get::<_, Option<u32>>(9)?,
-            notes: row.get(10)?,
-            updated_at: Utc::now(),
-            user_id: Uuid::parse_str(&row.get::<_, String>(11)?).unwrap_or_else(|_| Uuid::new_v4()),
-            plan_date: row.get(12)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!("{} WHERE t.id = ?1", STORED_TASK_SELECT))?;
+    let stored = stmt.query_row([task_id.to_string()], map_stored_task)?;
+    if actual_duration.is_some() {
+        refresh_study_hours(conn, stored.user_id, &Utc::now().to_rfc3339())?;
+    }
+    index_document(
+        conn,
+        stored.user_id,
+        "user_note",
+        &task_id.to_string(),
+        &format!("{}: {}", stored.task.subject, stored.task.topic),
+        Some(&stored.plan_date),
+        stored.notes.as_deref().unwrap_or_default(),
+    )?;
     Ok(stored)
 }
 
+const STORED_TASK_SELECT: &str = "SELECT t.id, t.subject, t.topic, t.duration_minutes, t.start_time, t.due_date, t.priority, t.resources_json, t.ai_notes, t.status, t.actual_duration, t.user_notes, p.user_id, t.date, t.completed_at
+     FROM tasks t JOIN study_plans p ON t.plan_id = p.id";
+
+fn map_stored_task(row: &rusqlite::Row<'_>) -> Result<StoredTask> {
+    let resources: String = row.get(7)?;
+    Ok(StoredTask {
+        task: Task {
+            id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+            subject: row.get(1)?,
+            topic: row.get(2)?,
+            duration_minutes: row.get(3)?,
+            start_time: row.get(4)?,
+            due_date: row.get(5)?,
+            priority: row.get(6)?,
+            resources: serde_json::from_str(&resources).unwrap_or_default(),
+            ai_notes: row.get(8)?,
+        },
+        status: row.get(9)?,
+        actual_duration: row.get::<_, Option<u32>>(10)?,
+        notes: row.get(11)?,
+        updated_at: Utc::now(),
+        user_id: Uuid::parse_str(&row.get::<_, String>(12)?).unwrap_or_else(|_| Uuid::new_v4()),
+        plan_date: row.get(13)?,
+        completed_at: row.get::<_, Option<String>>(14)?.and_then(|value| DateTime::parse_from_rfc3339(&value).ok()).map(|dt| dt.with_timezone(&Utc)),
+    })
+}
+
+pub fn get_stored_task(conn: &Connection, task_id: Uuid) -> Result<Option<StoredTask>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE t.id = ?1", STORED_TASK_SELECT))?;
+    stmt.query_row([task_id.to_string()], map_stored_task).optional()
+}
+
+pub fn list_user_tasks(conn: &Connection, user_id: Uuid) -> Result<Vec<StoredTask>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE p.user_id = ?1 ORDER BY t.date, t.start_time", STORED_TASK_SELECT))?;
+    let rows = stmt.query_map([user_id.to_string()], map_stored_task)?;
+    let mut tasks = Vec::new();
+    for row in rows {
+        tasks.push(row?);
+    }
+    Ok(tasks)
+}
+
 pub fn list_tools(conn: &Connection, user_id: Uuid, tool_type: Option<&str>) -> Result<Vec<Tool>> {
     let mut query = String::from("SELECT id, user_id, name, tool_type, description, component_code, metadata_json, version, usage_count, last_used FROM tools WHERE user_id = ?1");
     if tool_type.is_some() {
@@ -235,11 +271,14 @@ pub fn list_tools(conn: &Connection, user_id: Uuid, tool_type: Option<&str>) ->
 
 fn map_tool(row: &rusqlite::Row<'_>) -> Result<Tool> {
     let metadata_json: String = row.get(6)?;
-    let metadata: ToolMetadata = serde_json::from_str(&metadata_json).unwrap_or(ToolMetadata {
+    let mut metadata: ToolMetadata = serde_json::from_str(&metadata_json).unwrap_or(ToolMetadata {
         version: row.get(7)?,
         created_at: DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
         ai_model: "local".to_string(),
+        prompt_version: None,
+        template: None,
     });
+    metadata.version = row.get(7)?;
     Ok(Tool {
         tool_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
         user_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
@@ -253,11 +292,11 @@ fn map_tool(row: &rusqlite::Row<'_>) -> Result<Tool> {
     })
 }
 
-pub fn insert_tool(conn: &Connection, tool: &Tool) -> Result<()> {
+pub fn insert_tool(conn: &Connection, tool: &Tool, diagnostics: &[ToolDiagnostic]) -> Result<()> {
     ensure_user_id(conn, tool.user_id)?;
     conn.execute(
-        "INSERT INTO tools (id, user_id, name, tool_type, description, component_code, metadata_json, version, usage_count, last_used)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        "INSERT INTO tools (id, user_id, name, tool_type, description, component_code, metadata_json, version, prompt_version, usage_count, last_used)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
         params![
             tool.tool_id.to_string(),
             tool.user_id.to_string(),
@@ -267,44 +306,465 @@ pub fn insert_tool(conn: &Connection, tool: &Tool) -> Result<()> {
             tool.component_code,
             serde_json::to_string(&tool.metadata).unwrap_or_default(),
             tool.metadata.version,
+            tool.metadata.prompt_version,
             tool.usage_count,
             tool.last_used.map(|dt| dt.to_rfc3339()),
         ],
     )?;
-    Ok(())
+    insert_tool_version(
+        conn,
+        &ToolVersion {
+            version_id: Uuid::new_v4(),
+            tool_id: tool.tool_id,
+            version: tool.metadata.version.clone(),
+            component_code: tool.component_code.clone(),
+            instruction: None,
+            changes_summary: "Initial version".to_string(),
+            change_type: "initial".to_string(),
+            ai_model: Some(tool.metadata.ai_model.clone()),
+            prompt_version: tool.metadata.prompt_version.clone(),
+            diagnostics: diagnostics.to_vec(),
+            created_at: tool.metadata.created_at,
+        },
+    )?;
+    index_tool(conn, tool.user_id, &tool.tool_id.to_string(), &tool.name, &tool.metadata.created_at.date_naive().to_string(), &tool.description)
 }
 
-pub fn update_tool(conn: &Connection, tool_id: Uuid, component_code: &str, version: &str) -> Result<()> {
+pub fn update_tool(conn: &Connection, revision: &ToolVersion, owner_id: Uuid) -> Result<bool> {
+    let rows = conn.execute(
+        "UPDATE tools SET component_code = ?1, version = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3 AND user_id = ?4",
+        params![revision.component_code, revision.version, revision.tool_id.to_string(), owner_id.to_string()],
+    )?;
+    if rows == 0 {
+        return Ok(false);
+    }
+    insert_tool_version(conn, revision)?;
+    Ok(true)
+}
+
+fn insert_tool_version(conn: &Connection, revision: &ToolVersion) -> Result<()> {
     conn.execute(
-        "UPDATE tools SET component_code = ?1, version = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
-        params![component_code, version, tool_id.to_string()],
+        "INSERT INTO tool_versions (id, tool_id, version, component_code, instruction, changes_summary, change_type, ai_model, prompt_version, diagnostics_json, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            revision.version_id.to_string(),
+            revision.tool_id.to_string(),
+            revision.version,
+            revision.component_code,
+            revision.instruction,
+            revision.changes_summary,
+            revision.change_type,
+            revision.ai_model,
+            revision.prompt_version,
+            serde_json::to_string(&revision.diagnostics).unwrap_or_else(|_| "[]".to_string()),
+            revision.created_at.to_rfc3339(),
+        ],
     )?;
     Ok(())
 }
 
+fn map_tool_version(row: &rusqlite::Row<'_>) -> Result<ToolVersion> {
+    Ok(ToolVersion {
+        version_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+        tool_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+        version: row.get(2)?,
+        component_code: row.get(3)?,
+        instruction: row.get(4)?,
+        changes_summary: row.get(5)?,
+        change_type: row.get(6)?,
+        ai_model: row.get(7)?,
+        prompt_version: row.get(8)?,
+        diagnostics: row.get::<_, Option<String>>(9)?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default(),
+        created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?).map(|dt| dt.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
+    })
+}
+
+pub fn list_tool_versions(conn: &Connection, tool_id: Uuid) -> Result<Vec<ToolVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, tool_id, version, component_code, instruction, changes_summary, change_type, ai_model, prompt_version, diagnostics_json, created_at
+         FROM tool_versions WHERE tool_id = ?1 ORDER BY created_at, rowid",
+    )?;
+    let rows = stmt.query_map([tool_id.to_string()], map_tool_version)?;
+    rows.collect()
+}
+
+pub fn get_tool_version(conn: &Connection, tool_id: Uuid, version: &str) -> Result<Option<ToolVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, tool_id, version, component_code, instruction, changes_summary, change_type, ai_model, prompt_version, diagnostics_json, created_at
+         FROM tool_versions WHERE tool_id = ?1 AND version = ?2",
+    )?;
+    stmt.query_row(params![tool_id.to_string(), version], map_tool_version).optional()
+}
+
 pub fn get_tool(conn: &Connection, tool_id: Uuid) -> Result<Option<Tool>> {
     let mut stmt = conn.prepare("SELECT id, user_id, name, tool_type, description, component_code, metadata_json, version, usage_count, last_used FROM tools WHERE id = ?1")?;
     stmt.query_row([tool_id.to_string()], map_tool).optional()
 }
 
-pub fn delete_tool(conn: &Connection, tool_id: Uuid) -> Result<bool> {
-    let rows = conn.execute("DELETE FROM tools WHERE id = ?1", [tool_id.to_string()])?;
+pub fn get_tool_for_user(conn: &Connection, tool_id: Uuid, user_id: Uuid) -> Result<Option<(Tool, ToolAccess)>> {
+    let Some(tool) = get_tool(conn, tool_id)? else {
+        return Ok(None);
+    };
+    let access = if tool.user_id == user_id {
+        ToolAccess::Owner
+    } else if conn.query_row("SELECT EXISTS(SELECT 1 FROM tool_catalogue WHERE tool_id = ?1)", [tool_id.to_string()], |row| row.get(0))? {
+        ToolAccess::Shared
+    } else {
+        ToolAccess::Denied
+    };
+    Ok(Some((tool, access)))
+}
+
+pub fn delete_tool(conn: &Connection, tool_id: Uuid, owner_id: Uuid) -> Result<bool> {
+    let rows = conn.execute("DELETE FROM tools WHERE id = ?1 AND user_id = ?2", params![tool_id.to_string(), owner_id.to_string()])?;
+    if rows > 0 {
+        conn.execute("DELETE FROM knowledge_index WHERE source_type = 'tool' AND source_id = ?1", [tool_id.to_string()])?;
+    }
     Ok(rows > 0)
 }
 
-pub fn insert_conversation(conn: &Connection, request: &AiChatRequest, response: &str) -> Result<Uuid> {
+pub fn insert_tool_usage_event(conn: &Connection, event: &ToolUsageEvent) -> Result<()> {
+    ensure_user_id(conn, event.user_id)?;
+    conn.execute(
+        "INSERT INTO tool_usage_events (id, tool_id, user_id, event_type, task_id, subject, duration_seconds, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            event.event_id.to_string(),
+            event.tool_id.to_string(),
+            event.user_id.to_string(),
+            event.event_type,
+            event.task_id.map(|id| id.to_string()),
+            event.subject,
+            event.duration_seconds,
+            event.created_at.to_rfc3339(),
+        ],
+    )?;
+    conn.execute(
+        "UPDATE tools SET usage_count = COALESCE(usage_count, 0) + ?2, last_used = ?3 WHERE id = ?1 AND user_id = ?4",
+        params![event.tool_id.to_string(), (event.event_type == "open") as u32, event.created_at.to_rfc3339(), event.user_id.to_string()],
+    )?;
+    Ok(())
+}
+
+pub fn get_user_tool_usage(conn: &Connection, tool_id: Uuid, user_id: Uuid) -> Result<(u32, Option<DateTime<Utc>>)> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(event_type = 'open'), 0), MAX(created_at) FROM tool_usage_events WHERE tool_id = ?1 AND user_id = ?2",
+        params![tool_id.to_string(), user_id.to_string()],
+        |row| {
+            let last_used = row.get::<_, Option<String>>(1)?.and_then(|value| DateTime::parse_from_rfc3339(&value).ok()).map(|dt| dt.with_timezone(&Utc));
+            Ok((row.get(0)?, last_used))
+        },
+    )
+}
+
+const TOOL_USAGE_TOTALS: &str = "SUM(e.event_type = 'open'), SUM(e.event_type = 'interact'), COALESCE(SUM(e.duration_seconds), 0)";
+
+fn map_tool_usage_totals(row: &rusqlite::Row<'_>, offset: usize) -> Result<ToolUsageTotals> {
+    Ok(ToolUsageTotals {
+        opens: row.get::<_, Option<u32>>(offset)?.unwrap_or(0),
+        interactions: row.get::<_, Option<u32>>(offset + 1)?.unwrap_or(0),
+        duration_seconds: row.get::<_, i64>(offset + 2)?.max(0) as u64,
+    })
+}
+
+pub fn get_tool_usage_breakdown(conn: &Connection, scope: &str, id: Uuid, group: &str, from: &str, to: &str) -> Result<Vec<ToolUsageBreakdown>> {
+    let column = if scope == "tool" { "e.tool_id" } else { "e.user_id" };
+    let key = match group {
+        "subject" => "COALESCE(e.subject, 'Unassigned')",
+        _ => "substr(e.created_at, 1, 10)",
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {key}, {TOOL_USAGE_TOTALS} FROM tool_usage_events e
+         WHERE {column} = ?1 AND e.created_at >= ?2 AND e.created_at < ?3
+         GROUP BY {key} ORDER BY {key}"
+    ))?;
+    let rows = stmt.query_map(params![id.to_string(), from, to], |row| {
+        Ok(ToolUsageBreakdown {
+            key: row.get(0)?,
+            totals: map_tool_usage_totals(row, 1)?,
+        })
+    })?;
+    let mut breakdown = Vec::new();
+    for row in rows {
+        breakdown.push(row?);
+    }
+    Ok(breakdown)
+}
+
+pub fn get_top_tools(conn: &Connection, user_id: Uuid, from: &str, to: &str, limit: usize) -> Result<Vec<ToolUsageRanking>> {
+    Ok(rank_tools(conn, user_id, false, from, to)?
+        .into_iter()
+        .map(|(_, ranking)| ranking)
+        .take(limit)
+        .collect())
+}
+
+pub fn get_top_tools_by_subject(conn: &Connection, user_id: Uuid, from: &str, to: &str, limit: usize) -> Result<Vec<ToolSubjectUsage>> {
+    let mut subjects: Vec<ToolSubjectUsage> = Vec::new();
+    for (subject, ranking) in rank_tools(conn, user_id, true, from, to)? {
+        match subjects.last_mut() {
+            Some(group) if group.subject == subject => {
+                if group.tools.len() < limit {
+                    group.tools.push(ranking);
+                }
+            }
+            _ => subjects.push(ToolSubjectUsage { subject, tools: vec![ranking] }),
+        }
+    }
+    Ok(subjects)
+}
+
+fn rank_tools(conn: &Connection, user_id: Uuid, by_subject: bool, from: &str, to: &str) -> Result<Vec<(String, ToolUsageRanking)>> {
+    let subject = if by_subject { "COALESCE(e.subject, 'Unassigned')" } else { "''" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {subject} AS subject_key, t.id, t.name, t.tool_type, {TOOL_USAGE_TOTALS}
+         FROM tool_usage_events e JOIN tools t ON t.id = e.tool_id
+         WHERE e.user_id = ?1 AND e.created_at >= ?2 AND e.created_at < ?3
+         GROUP BY subject_key, t.id
+         ORDER BY subject_key, 5 DESC, 6 DESC, 7 DESC, t.name"
+    ))?;
+    let rows = stmt.query_map(params![user_id.to_string(), from, to], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            ToolUsageRanking {
+                tool_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+                name: row.get(2)?,
+                tool_type: row.get(3)?,
+                totals: map_tool_usage_totals(row, 4)?,
+            },
+        ))
+    })?;
+    let mut ranking = Vec::new();
+    for row in rows {
+        ranking.push(row?);
+    }
+    Ok(ranking)
+}
+
+const TOOL_STATE_SELECT: &str = "SELECT tool_id, user_id, state_key, value_json, revision, updated_at FROM tool_state";
+
+fn map_tool_state(row: &rusqlite::Row<'_>) -> Result<ToolStateEntry> {
+    let value_json: String = row.get(3)?;
+    Ok(ToolStateEntry {
+        tool_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+        user_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+        key: row.get(2)?,
+        value: serde_json::from_str(&value_json).unwrap_or(serde_json::Value::Null),
+        revision: row.get::<_, i64>(4)?.max(0) as u64,
+        size_bytes: value_json.len(),
+        updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?).map(|dt| dt.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
+    })
+}
+
+pub fn get_tool_state(conn: &Connection, tool_id: Uuid, user_id: Uuid, key: &str) -> Result<Option<ToolStateEntry>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE tool_id = ?1 AND user_id = ?2 AND state_key = ?3", TOOL_STATE_SELECT))?;
+    stmt.query_row(params![tool_id.to_string(), user_id.to_string(), key], map_tool_state).optional()
+}
+
+pub fn list_tool_state(conn: &Connection, tool_id: Uuid, user_id: Uuid) -> Result<Vec<ToolStateEntry>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE tool_id = ?1 AND user_id = ?2 ORDER BY state_key", TOOL_STATE_SELECT))?;
+    let rows = stmt.query_map(params![tool_id.to_string(), user_id.to_string()], map_tool_state)?;
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+pub fn get_tool_state_size(conn: &Connection, tool_id: Uuid, user_id: Uuid, key: &str) -> Result<usize> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(length(CAST(value_json AS BLOB))), 0) FROM tool_state WHERE tool_id = ?1 AND user_id = ?2 AND state_key != ?3",
+        params![tool_id.to_string(), user_id.to_string(), key],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|bytes| bytes.max(0) as usize)
+}
+
+pub fn put_tool_state(conn: &Connection, tool_id: Uuid, user_id: Uuid, key: &str, value_json: &str, revision: u64) -> Result<bool> {
+    ensure_user_id(conn, user_id)?;
+    let now = Utc::now().to_rfc3339();
+    let rows = if revision == 1 {
+        conn.execute(
+            "INSERT OR IGNORE INTO tool_state (tool_id, user_id, state_key, value_json, revision, updated_at) VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+            params![tool_id.to_string(), user_id.to_string(), key, value_json, now],
+        )?
+    } else {
+        conn.execute(
+            "UPDATE tool_state SET value_json = ?4, revision = ?5, updated_at = ?6 WHERE tool_id = ?1 AND user_id = ?2 AND state_key = ?3 AND revision = ?5 - 1",
+            params![tool_id.to_string(), user_id.to_string(), key, value_json, revision as i64, now],
+        )?
+    };
+    Ok(rows > 0)
+}
+
+pub fn insert_tool_provenance(conn: &Connection, provenance: &ToolProvenance) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tool_provenance (tool_id, source_tool_id, source_version, content_hash, exported_at, signer_public_key, signature_trusted, imported_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            provenance.tool_id.to_string(),
+            provenance.source_tool_id.to_string(),
+            provenance.source_version,
+            provenance.content_hash,
+            provenance.exported_at.to_rfc3339(),
+            provenance.signer_public_key,
+            provenance.signature_trusted,
+            provenance.imported_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_tool_provenance(conn: &Connection, tool_id: Uuid) -> Result<Option<ToolProvenance>> {
+    let parse_time = |value: String| DateTime::parse_from_rfc3339(&value).map(|dt| dt.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now());
+    conn.query_row(
+        "SELECT tool_id, source_tool_id, source_version, content_hash, exported_at, signer_public_key, signature_trusted, imported_at
+         FROM tool_provenance WHERE tool_id = ?1",
+        [tool_id.to_string()],
+        |row| {
+            Ok(ToolProvenance {
+                tool_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+                source_tool_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+                source_version: row.get(2)?,
+                content_hash: row.get(3)?,
+                exported_at: parse_time(row.get(4)?),
+                signer_public_key: row.get(5)?,
+                signature_trusted: row.get(6)?,
+                imported_at: parse_time(row.get(7)?),
+            })
+        },
+    )
+    .optional()
+}
+
+pub fn publish_tool(conn: &Connection, tool_id: Uuid, subject: Option<&str>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tool_catalogue (tool_id, subject, published_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(tool_id) DO UPDATE SET subject = excluded.subject",
+        params![tool_id.to_string(), subject, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub fn unpublish_tool(conn: &Connection, tool_id: Uuid) -> Result<bool> {
+    let rows = conn.execute("DELETE FROM tool_catalogue WHERE tool_id = ?1", [tool_id.to_string()])?;
+    Ok(rows > 0)
+}
+
+const CATALOGUE_SELECT: &str = "SELECT t.id, t.user_id, u.name, t.name, t.tool_type, t.description, c.subject, t.version, c.published_at,
+        (SELECT AVG(rating) FROM tool_ratings r WHERE r.tool_id = t.id) AS rating_average,
+        (SELECT COUNT(*) FROM tool_ratings r WHERE r.tool_id = t.id) AS rating_count,
+        (SELECT COUNT(*) FROM tool_forks f WHERE f.upstream_tool_id = t.id) AS fork_count
+     FROM tool_catalogue c JOIN tools t ON t.id = c.tool_id LEFT JOIN users u ON u.id = t.user_id";
+
+fn map_catalogue_entry(row: &rusqlite::Row<'_>) -> Result<CatalogueEntry> {
+    Ok(CatalogueEntry {
+        tool_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+        owner_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+        owner_name: row.get(2)?,
+        name: row.get(3)?,
+        tool_type: row.get(4)?,
+        description: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        subject: row.get(6)?,
+        version: row.get(7)?,
+        published_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?).map(|dt| dt.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
+        rating_average: row.get::<_, Option<f64>>(9)?.map(|average| (average * 100.0).round() / 100.0),
+        rating_count: row.get(10)?,
+        fork_count: row.get(11)?,
+    })
+}
+
+pub fn get_catalogue_entry(conn: &Connection, tool_id: Uuid) -> Result<Option<CatalogueEntry>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE c.tool_id = ?1", CATALOGUE_SELECT))?;
+    stmt.query_row([tool_id.to_string()], map_catalogue_entry).optional()
+}
+
+pub fn search_catalogue(conn: &Connection, tool_type: Option<&str>, subject: Option<&str>, text: Option<&str>, sort: &str) -> Result<Vec<CatalogueEntry>> {
+    let order = match sort {
+        "rating" => "rating_average IS NULL, rating_average DESC, rating_count DESC, c.published_at DESC",
+        "forks" => "fork_count DESC, c.published_at DESC",
+        _ => "c.published_at DESC",
+    };
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR t.tool_type = ?1)
+           AND (?2 IS NULL OR lower(c.subject) = lower(?2))
+           AND (?3 IS NULL OR instr(lower(t.name), lower(?3)) > 0 OR instr(lower(COALESCE(t.description, '')), lower(?3)) > 0)
+         ORDER BY {}",
+        CATALOGUE_SELECT, order
+    ))?;
+    let rows = stmt.query_map(params![tool_type, subject, text], map_catalogue_entry)?;
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+pub fn upsert_tool_rating(conn: &Connection, tool_id: Uuid, user_id: Uuid, rating: u8) -> Result<()> {
+    ensure_user_id(conn, user_id)?;
+    conn.execute(
+        "INSERT INTO tool_ratings (tool_id, user_id, rating, rated_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(tool_id, user_id) DO UPDATE SET rating = excluded.rating, rated_at = excluded.rated_at",
+        params![tool_id.to_string(), user_id.to_string(), rating, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub fn insert_tool_fork(conn: &Connection, fork: &ToolFork) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tool_forks (tool_id, upstream_tool_id, synced_version, forked_at) VALUES (?1, ?2, ?3, ?4)",
+        params![fork.tool_id.to_string(), fork.upstream_tool_id.to_string(), fork.synced_version, fork.forked_at.to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub fn get_tool_fork(conn: &Connection, tool_id: Uuid) -> Result<Option<ToolFork>> {
+    conn.query_row(
+        "SELECT tool_id, upstream_tool_id, synced_version, forked_at FROM tool_forks WHERE tool_id = ?1",
+        [tool_id.to_string()],
+        |row| {
+            Ok(ToolFork {
+                tool_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+                upstream_tool_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+                synced_version: row.get(2)?,
+                forked_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?).map(|dt| dt.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
+            })
+        },
+    )
+    .optional()
+}
+
+pub fn update_fork_sync(conn: &Connection, tool_id: Uuid, synced_version: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE tool_forks SET synced_version = ?2 WHERE tool_id = ?1",
+        params![tool_id.to_string(), synced_version],
+    )?;
+    Ok(())
+}
+
+pub fn insert_conversation(conn: &Connection, request: &AiChatRequest, response: &str, prompt_version: Option<&str>) -> Result<Uuid> {
     let convo_id = Uuid::new_v4();
     ensure_user_id(conn, request.user_id)?;
     conn.execute(
-        "INSERT INTO ai_conversations (id, user_id, message, response, context_json) VALUES (?1, ?2, ?3, ?4, ?5)",
+        "INSERT INTO ai_conversations (id, user_id, message, response, context_json, prompt_version) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
             convo_id.to_string(),
             request.user_id.to_string(),
             request.message,
             response,
             request.context.as_ref().and_then(|ctx| serde_json::to_string(ctx).ok()),
+            prompt_version,
         ],
     )?;
+    index_document(
+        conn,
+        request.user_id,
+        "conversation",
+        &convo_id.to_string(),
+        &request.message.chars().take(80).collect::<String>(),
+        Some(&Utc::now().date_naive().to_string()),
+        &format!("{}\n{}", request.message, response),
+    )?;
     Ok(convo_id)
 }
 
@@ -403,7 +863,7 @@ pub fn get_user_stats(conn: &Connection, user_id: Uuid) -> Result<UserStatsRespo
         .query_row("SELECT COUNT(*) FROM tools WHERE user_id = ?1", [user_id.to_string()], |row| row.get(0))
         .unwrap_or(0);
     let ai_interactions: u32 = conn
-        .query_row("SELECT COUNT(*) FROM ai_conversations WHERE user_id = ?1", [user_id.to_string()], |row| row.get(0))
+        .query_row("SELECT COUNT(DISTINCT request_id) FROM ai_usage WHERE user_id = ?1", [user_id.to_string()], |row| row.get(0))
         .unwrap_or(0);
 
     let mut activity_stmt = conn.prepare(
@@ -431,6 +891,7 @@ pub fn get_user_stats(conn: &Connection, user_id: Uuid) -> Result<UserStatsRespo
         tools_created,
         ai_interactions,
         subjects_progress: get_subject_progress(conn, user_id).unwrap_or_else(|_| serde_json::json!({})),
+        quiz_scores: get_quiz_scores(conn, user_id).unwrap_or_else(|_| serde_json::json!({})),
         weekly_activity,
     })
 }
@@ -456,3 +917,805 @@ fn get_subject_progress(conn: &Connection, user_id: Uuid) -> Result<serde_json::
     }
     Ok(serde_json::Value::Object(map))
 }
+
+fn get_quiz_scores(conn: &Connection, user_id: Uuid) -> Result<serde_json::Value> {
+    let mut stmt = conn.prepare(
+        "SELECT q.subject, AVG(a.score)
+         FROM quiz_attempts a JOIN quizzes q ON a.quiz_id = q.id
+         WHERE a.user_id = ?1
+         GROUP BY q.subject",
+    )?;
+    let rows = stmt.query_map([user_id.to_string()], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+    let mut map = serde_json::Map::new();
+    for row in rows {
+        let (subject, score) = row?;
+        map.insert(subject, serde_json::json!((score * 100.0).round() as u32));
+    }
+    Ok(serde_json::Value::Object(map))
+}
+
+pub fn insert_ai_usage(conn: &Connection, call: &AiCall) -> Result<()> {
+    if call.usage.is_empty() {
+        return Ok(());
+    }
+    ensure_user_id(conn, call.user_id)?;
+    let now = Utc::now().to_rfc3339();
+    for usage in &call.usage {
+        conn.execute(
+            "INSERT INTO ai_usage (id, request_id, user_id, feature, model, prompt_tokens, completion_tokens, cost_usd, estimated, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                Uuid::new_v4().to_string(),
+                call.request_id.to_string(),
+                call.user_id.to_string(),
+                call.feature,
+                usage.model,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                usage.cost_usd,
+                usage.estimated,
+                now,
+            ],
+        )?;
+    }
+    conn.execute(
+        "INSERT INTO user_stats (user_id, ai_interactions, last_activity)
+         VALUES (?1, (SELECT COUNT(DISTINCT request_id) FROM ai_usage WHERE user_id = ?1), ?2)
+         ON CONFLICT(user_id) DO UPDATE SET ai_interactions = excluded.ai_interactions, last_activity = excluded.last_activity",
+        params![call.user_id.to_string(), now],
+    )?;
+    Ok(())
+}
+
+pub fn get_tokens_used_since(conn: &Connection, user_id: Uuid, since: DateTime<Utc>) -> Result<u64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(prompt_tokens + completion_tokens), 0) FROM ai_usage WHERE user_id = ?1 AND model != 'local' AND created_at >= ?2",
+        params![user_id.to_string(), since.to_rfc3339()],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|tokens| tokens.max(0) as u64)
+}
+
+pub fn get_usage_breakdown(conn: &Connection, user_id: Uuid, group: &str, from: &str, to: &str) -> Result<Vec<UsageBreakdown>> {
+    let key = match group {
+        "feature" => "feature",
+        "model" => "model",
+        _ => "substr(created_at, 1, 10)",
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {key}, COUNT(DISTINCT request_id), COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0), COALESCE(SUM(cost_usd), 0)
+         FROM ai_usage WHERE user_id = ?1 AND created_at >= ?2 AND created_at < ?3
+         GROUP BY {key} ORDER BY {key}"
+    ))?;
+    let rows = stmt.query_map(params![user_id.to_string(), from, to], |row| {
+        let prompt_tokens = row.get::<_, i64>(2)?.max(0) as u64;
+        let completion_tokens = row.get::<_, i64>(3)?.max(0) as u64;
+        Ok(UsageBreakdown {
+            key: row.get(0)?,
+            totals: UsageTotals {
+                requests: row.get(1)?,
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+                cost_usd: row.get(4)?,
+            },
+        })
+    })?;
+    let mut breakdown = Vec::new();
+    for row in rows {
+        breakdown.push(row?);
+    }
+    Ok(breakdown)
+}
+
+pub fn get_ai_budget(conn: &Connection, user_id: Uuid) -> Result<Option<AiBudget>> {
+    let mut stmt = conn.prepare("SELECT daily_token_limit, monthly_token_limit, on_exceed, downgrade_model FROM ai_budgets WHERE user_id = ?1")?;
+    stmt.query_row([user_id.to_string()], |row| {
+        Ok(AiBudget {
+            user_id,
+            daily_token_limit: row.get::<_, Option<i64>>(0)?.map(|limit| limit.max(0) as u64),
+            monthly_token_limit: row.get::<_, Option<i64>>(1)?.map(|limit| limit.max(0) as u64),
+            on_exceed: row.get(2)?,
+            downgrade_model: row.get(3)?,
+        })
+    })
+    .optional()
+}
+
+pub fn upsert_ai_budget(conn: &Connection, budget: &AiBudget) -> Result<()> {
+    ensure_user_id(conn, budget.user_id)?;
+    conn.execute(
+        "INSERT INTO ai_budgets (user_id, daily_token_limit, monthly_token_limit, on_exceed, downgrade_model, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, CURRENT_TIMESTAMP)
+         ON CONFLICT(user_id) DO UPDATE SET daily_token_limit = excluded.daily_token_limit, monthly_token_limit = excluded.monthly_token_limit,
+             on_exceed = excluded.on_exceed, downgrade_model = excluded.downgrade_model, updated_at = CURRENT_TIMESTAMP",
+        params![
+            budget.user_id.to_string(),
+            budget.daily_token_limit.map(|limit| limit.min(i64::MAX as u64) as i64),
+            budget.monthly_token_limit.map(|limit| limit.min(i64::MAX as u64) as i64),
+            budget.on_exceed,
+            budget.downgrade_model,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_cached_response(conn: &Connection, cache_key: &str, now: i64) -> Result<Option<CachedResponse>> {
+    let mut stmt = conn.prepare(
+        "SELECT provider, model, prompt_version, response, response_model, prompt_tokens, completion_tokens
+         FROM ai_response_cache WHERE cache_key = ?1 AND expires_at > ?2",
+    )?;
+    let entry = stmt
+        .query_row(params![cache_key, now], |row| {
+            Ok(CachedResponse {
+                cache_key: cache_key.to_string(),
+                provider: row.get(0)?,
+                model: row.get(1)?,
+                prompt_version: row.get(2)?,
+                content: row.get(3)?,
+                response_model: row.get(4)?,
+                prompt_tokens: row.get(5)?,
+                completion_tokens: row.get(6)?,
+            })
+        })
+        .optional()?;
+    if entry.is_some() {
+        conn.execute(
+            "UPDATE ai_response_cache SET hits = hits + 1, last_used_at = ?2 WHERE cache_key = ?1",
+            params![cache_key, now],
+        )?;
+    }
+    Ok(entry)
+}
+
+pub fn put_cached_response(conn: &Connection, entry: &CachedResponse, now: i64, expires_at: i64, max_entries: u64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO ai_response_cache (cache_key, provider, model, prompt_version, response, response_model, prompt_tokens, completion_tokens, hits, created_at, last_used_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0, ?9, ?9, ?10)
+         ON CONFLICT(cache_key) DO UPDATE SET response = excluded.response, response_model = excluded.response_model,
+             prompt_tokens = excluded.prompt_tokens, completion_tokens = excluded.completion_tokens, hits = 0,
+             created_at = excluded.created_at, last_used_at = excluded.last_used_at, expires_at = excluded.expires_at",
+        params![
+            entry.cache_key,
+            entry.provider,
+            entry.model,
+            entry.prompt_version,
+            entry.content,
+            entry.response_model,
+            entry.prompt_tokens,
+            entry.completion_tokens,
+            now,
+            expires_at,
+        ],
+    )?;
+    conn.execute("DELETE FROM ai_response_cache WHERE expires_at <= ?1", [now])?;
+    conn.execute(
+        "DELETE FROM ai_response_cache WHERE cache_key NOT IN (SELECT cache_key FROM ai_response_cache ORDER BY last_used_at DESC, created_at DESC LIMIT ?1)",
+        [max_entries.min(i64::MAX as u64) as i64],
+    )?;
+    Ok(())
+}
+
+pub fn index_document(conn: &Connection, user_id: Uuid, source_type: &str, source_id: &str, title: &str, date: Option<&str>, text: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM knowledge_index WHERE source_type = ?1 AND source_id = ?2",
+        params![source_type, source_id],
+    )?;
+    for (chunk_index, chunk) in chunk_text(text, 800).iter().enumerate() {
+        conn.execute(
+            "INSERT INTO knowledge_index (title, content, user_id, source_type, source_id, source_date, chunk_index) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![title, chunk, user_id.to_string(), source_type, source_id, date, chunk_index as i64],
+        )?;
+    }
+    Ok(())
+}
+
+fn index_task(conn: &Connection, user_id: Uuid, date: &str, task: &Task) -> Result<()> {
+    let title = format!("{}: {}", task.subject, task.topic);
+    let task_id = task.id.to_string();
+    index_document(conn, user_id, "task", &task_id, &title, Some(date), &title)?;
+    index_document(conn, user_id, "ai_note", &task_id, &title, Some(date), &task.ai_notes)
+}
+
+fn index_tool(conn: &Connection, user_id: Uuid, tool_id: &str, name: &str, date: &str, description: &str) -> Result<()> {
+    let body = if description.trim().is_empty() { name } else { description };
+    index_document(conn, user_id, "tool", tool_id, name, Some(date), body)
+}
+
+pub fn rebuild_knowledge_index(conn: &Connection) -> Result<()> {
+    let tools_indexed: i64 = conn.query_row("SELECT COUNT(*) FROM knowledge_index WHERE source_type = 'tool'", [], |row| row.get(0))?;
+    if tools_indexed == 0 {
+        let tools = {
+            let mut stmt = conn.prepare("SELECT id, user_id, name, substr(created_at, 1, 10), description FROM tools")?;
+            stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?
+        };
+        for (id, user_id, name, date, description) in tools {
+            if let Ok(user_id) = Uuid::parse_str(&user_id) {
+                index_tool(conn, user_id, &id, &name, &date, &description)?;
+            }
+        }
+    }
+    let indexed: i64 = conn.query_row("SELECT COUNT(*) FROM knowledge_index WHERE source_type != 'tool'", [], |row| row.get(0))?;
+    if indexed > 0 {
+        return Ok(());
+    }
+    let tasks = {
+        let mut stmt = conn.prepare(STORED_TASK_SELECT)?;
+        stmt.query_map([], map_stored_task)?.collect::<Result<Vec<_>>>()?
+    };
+    for stored in &tasks {
+        index_task(conn, stored.user_id, &stored.plan_date, &stored.task)?;
+        if let Some(notes) = &stored.notes {
+            let title = format!("{}: {}", stored.task.subject, stored.task.topic);
+            index_document(conn, stored.user_id, "user_note", &stored.task.id.to_string(), &title, Some(&stored.plan_date), notes)?;
+        }
+    }
+    let conversations = {
+        let mut stmt = conn.prepare("SELECT id, user_id, message, response, substr(created_at, 1, 10) FROM ai_conversations")?;
+        stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?
+    };
+    for (id, user_id, message, response, date) in conversations {
+        let Ok(user_id) = Uuid::parse_str(&user_id) else {
+            continue;
+        };
+        let title = message.chars().take(80).collect::<String>();
+        index_document(conn, user_id, "conversation", &id, &title, date.as_deref(), &format!("{}\n{}", message, response))?;
+    }
+    Ok(())
+}
+
+pub fn search_knowledge(conn: &Connection, user_id: Uuid, query: &str, from: Option<&str>, to: Option<&str>, limit: usize) -> Result<Vec<KnowledgeChunk>> {
+    let mut stmt = conn.prepare(
+        "SELECT source_type, source_id, title, source_date, content, snippet(knowledge_index, 1, '', '', '...', 24), bm25(knowledge_index, 2.0, 1.0)
+         FROM knowledge_index WHERE knowledge_index MATCH ?1 AND user_id = ?2 AND source_type != 'tool'
+           AND (?4 IS NULL OR source_date >= ?4) AND (?5 IS NULL OR source_date <= ?5)
+         ORDER BY bm25(knowledge_index, 2.0, 1.0) LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![query, user_id.to_string(), limit as i64, from, to], |row| {
+        Ok(KnowledgeChunk {
+            source_type: row.get(0)?,
+            source_id: row.get(1)?,
+            title: row.get(2)?,
+            date: row.get(3)?,
+            content: row.get(4)?,
+            snippet: row.get(5)?,
+            score: -row.get::<_, f64>(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn count_documents(conn: &Connection, search: &DocumentSearch) -> Result<usize> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(DISTINCT source_type || ':' || source_id)
+         FROM knowledge_index WHERE knowledge_index MATCH ?1 AND user_id = ?2
+           AND instr(?3, ',' || source_type || ',') > 0
+           AND (?4 IS NULL OR source_date >= ?4) AND (?5 IS NULL OR source_date <= ?5)",
+        params![search.query, search.user_id.to_string(), format!(",{},", search.source_types.join(",")), search.from, search.to],
+        |row| row.get(0),
+    )?;
+    Ok(count as usize)
+}
+
+pub fn search_documents(conn: &Connection, search: &DocumentSearch, match_markers: (char, char), limit: usize) -> Result<Vec<SearchResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT source_type, source_id, title, source_date, snippet(knowledge_index, -1, ?6, ?7, '...', 16), bm25(knowledge_index, 2.0, 1.0)
+         FROM knowledge_index WHERE knowledge_index MATCH ?1 AND user_id = ?2
+           AND instr(?3, ',' || source_type || ',') > 0
+           AND (?4 IS NULL OR source_date >= ?4) AND (?5 IS NULL OR source_date <= ?5)
+         ORDER BY CASE WHEN ?8 THEN source_date END DESC, bm25(knowledge_index, 2.0, 1.0)
+         LIMIT ?9",
+    )?;
+    let types = format!(",{},", search.source_types.join(","));
+    let rows = stmt.query_map(
+        params![
+            search.query,
+            search.user_id.to_string(),
+            types,
+            search.from,
+            search.to,
+            match_markers.0.to_string(),
+            match_markers.1.to_string(),
+            search.recent_first,
+            limit.min(i64::MAX as usize) as i64
+        ],
+        |row| {
+            Ok(SearchResult {
+                result_type: row.get(0)?,
+                id: row.get(1)?,
+                title: row.get(2)?,
+                date: row.get(3)?,
+                snippet: row.get(4)?,
+                score: -row.get::<_, f64>(5)?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for sentence in text.split_inclusive(['.', '!', '?', '\n']) {
+        if !current.is_empty() && current.chars().count() + sentence.chars().count() > max_chars {
+            chunks.push(current.trim().to_string());
+            current.clear();
+        }
+        for word in sentence.split_inclusive(' ') {
+            if !current.is_empty() && current.chars().count() + word.chars().count() > max_chars {
+                chunks.push(current.trim().to_string());
+                current.clear();
+            }
+            current.push_str(word);
+        }
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current.trim().to_string());
+    }
+    chunks.retain(|chunk| !chunk.is_empty());
+    chunks
+}
+
+pub fn insert_quiz(conn: &Connection, quiz: &Quiz) -> Result<()> {
+    ensure_user_id(conn, quiz.user_id)?;
+    conn.execute(
+        "INSERT INTO quizzes (id, user_id, task_id, subject, topic, questions_json, source, prompt_version, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            quiz.quiz_id.to_string(),
+            quiz.user_id.to_string(),
+            quiz.task_id.map(|id| id.to_string()),
+            quiz.subject,
+            quiz.topic,
+            serde_json::to_string(&quiz.questions).unwrap_or_default(),
+            quiz.source,
+            quiz.prompt_version,
+            quiz.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+const QUIZ_SELECT: &str = "SELECT id, user_id, task_id, subject, topic, questions_json, source, prompt_version, created_at FROM quizzes";
+
+fn map_quiz(row: &rusqlite::Row<'_>) -> Result<Quiz> {
+    let questions: String = row.get(5)?;
+    Ok(Quiz {
+        quiz_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+        user_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+        task_id: row.get::<_, Option<String>>(2)?.and_then(|id| Uuid::parse_str(&id).ok()),
+        subject: row.get(3)?,
+        topic: row.get(4)?,
+        questions: serde_json::from_str(&questions).unwrap_or_default(),
+        source: row.get(6)?,
+        prompt_version: row.get(7)?,
+        created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+    })
+}
+
+pub fn get_quiz(conn: &Connection, quiz_id: Uuid) -> Result<Option<Quiz>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE id = ?1", QUIZ_SELECT))?;
+    stmt.query_row([quiz_id.to_string()], map_quiz).optional()
+}
+
+pub fn list_quizzes(conn: &Connection, user_id: Uuid, subject: Option<&str>) -> Result<Vec<Quiz>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE user_id = ?1 AND (?2 IS NULL OR subject = ?2 COLLATE NOCASE) ORDER BY created_at DESC",
+        QUIZ_SELECT
+    ))?;
+    let rows = stmt.query_map(params![user_id.to_string(), subject], map_quiz)?;
+    rows.collect()
+}
+
+pub fn insert_quiz_attempt(conn: &Connection, attempt: &QuizAttempt) -> Result<()> {
+    conn.execute(
+        "INSERT INTO quiz_attempts (id, quiz_id, user_id, score, correct, total, results_json, submitted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            attempt.attempt_id.to_string(),
+            attempt.quiz_id.to_string(),
+            attempt.user_id.to_string(),
+            attempt.score,
+            attempt.correct,
+            attempt.total,
+            serde_json::to_string(&attempt.results).unwrap_or_default(),
+            attempt.submitted_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_quiz_attempts(conn: &Connection, quiz_id: Uuid) -> Result<Vec<QuizAttempt>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, quiz_id, user_id, score, correct, total, results_json, submitted_at FROM quiz_attempts WHERE quiz_id = ?1 ORDER BY submitted_at DESC",
+    )?;
+    let rows = stmt.query_map([quiz_id.to_string()], |row| {
+        let results: String = row.get(6)?;
+        Ok(QuizAttempt {
+            attempt_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+            quiz_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+            user_id: Uuid::parse_str(&row.get::<_, String>(2)?).unwrap_or_else(|_| Uuid::new_v4()),
+            score: row.get(3)?,
+            correct: row.get(4)?,
+            total: row.get(5)?,
+            results: serde_json::from_str(&results).unwrap_or_default(),
+            submitted_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn get_topic_mastery(conn: &Connection, user_id: Uuid) -> Result<Vec<TopicMastery>> {
+    let mut stmt = conn.prepare(
+        "SELECT q.subject, q.topic, AVG(a.score), COUNT(*)
+         FROM quiz_attempts a JOIN quizzes q ON a.quiz_id = q.id
+         WHERE a.user_id = ?1
+         GROUP BY q.subject, q.topic
+         ORDER BY AVG(a.score) ASC",
+    )?;
+    let rows = stmt.query_map([user_id.to_string()], |row| {
+        Ok(TopicMastery {
+            subject: row.get(0)?,
+            topic: row.get(1)?,
+            average_score: row.get(2)?,
+            attempts: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn insert_deck(conn: &Connection, deck: &Deck) -> Result<()> {
+    ensure_user_id(conn, deck.user_id)?;
+    conn.execute(
+        "INSERT INTO flashcard_decks (id, user_id, name, subject, task_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            deck.deck_id.to_string(),
+            deck.user_id.to_string(),
+            deck.name,
+            deck.subject,
+            deck.task_id.map(|id| id.to_string()),
+            deck.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+const DECK_SUMMARY_SELECT: &str = "SELECT d.id, d.user_id, d.name, d.subject, d.task_id, d.created_at,
+        (SELECT COUNT(*) FROM flashcard_cards c WHERE c.deck_id = d.id),
+        (SELECT COUNT(*) FROM flashcard_cards c WHERE c.deck_id = d.id AND c.due_date <= ?1)
+     FROM flashcard_decks d";
+
+fn map_deck_summary(row: &rusqlite::Row<'_>) -> Result<DeckSummary> {
+    Ok(DeckSummary {
+        deck: Deck {
+            deck_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+            user_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+            name: row.get(2)?,
+            subject: row.get(3)?,
+            task_id: row.get::<_, Option<String>>(4)?.and_then(|id| Uuid::parse_str(&id).ok()),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        },
+        card_count: row.get(6)?,
+        due_count: row.get(7)?,
+    })
+}
+
+pub fn get_deck(conn: &Connection, deck_id: Uuid, today: &str) -> Result<Option<DeckSummary>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE d.id = ?2", DECK_SUMMARY_SELECT))?;
+    stmt.query_row(params![today, deck_id.to_string()], map_deck_summary).optional()
+}
+
+pub fn list_decks(conn: &Connection, user_id: Uuid, subject: Option<&str>, today: &str) -> Result<Vec<DeckSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE d.user_id = ?2 AND (?3 IS NULL OR d.subject = ?3 COLLATE NOCASE) ORDER BY d.created_at DESC",
+        DECK_SUMMARY_SELECT
+    ))?;
+    let rows = stmt.query_map(params![today, user_id.to_string(), subject], map_deck_summary)?;
+    rows.collect()
+}
+
+pub fn insert_card(conn: &Connection, card: &Card) -> Result<()> {
+    conn.execute(
+        "INSERT INTO flashcard_cards (id, deck_id, front, back, tags_json, ease_factor, interval_days, repetitions, due_date, last_reviewed, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            card.card_id.to_string(),
+            card.deck_id.to_string(),
+            card.front,
+            card.back,
+            serde_json::to_string(&card.tags).unwrap_or_default(),
+            card.schedule.ease_factor,
+            card.schedule.interval_days,
+            card.schedule.repetitions,
+            card.schedule.due_date,
+            card.schedule.last_reviewed.map(|at| at.to_rfc3339()),
+            card.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+const CARD_SELECT: &str = "SELECT c.id, c.deck_id, c.front, c.back, c.tags_json, c.ease_factor, c.interval_days, c.repetitions, c.due_date, c.last_reviewed, c.created_at, d.name, d.subject, d.user_id
+     FROM flashcard_cards c JOIN flashcard_decks d ON c.deck_id = d.id";
+
+fn map_card(row: &rusqlite::Row<'_>) -> Result<Card> {
+    let tags: String = row.get(4)?;
+    Ok(Card {
+        card_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+        deck_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+        front: row.get(2)?,
+        back: row.get(3)?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        schedule: CardSchedule {
+            ease_factor: row.get(5)?,
+            interval_days: row.get(6)?,
+            repetitions: row.get(7)?,
+            due_date: row.get(8)?,
+            last_reviewed: row
+                .get::<_, Option<String>>(9)?
+                .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+                .map(|dt| dt.with_timezone(&Utc)),
+        },
+        created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+    })
+}
+
+fn map_due_card(row: &rusqlite::Row<'_>) -> Result<DueCard> {
+    Ok(DueCard {
+        card: map_card(row)?,
+        deck_name: row.get(11)?,
+        subject: row.get(12)?,
+    })
+}
+
+pub fn list_deck_cards(conn: &Connection, deck_id: Uuid) -> Result<Vec<Card>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE c.deck_id = ?1 ORDER BY c.created_at, c.id", CARD_SELECT))?;
+    let rows = stmt.query_map([deck_id.to_string()], map_card)?;
+    rows.collect()
+}
+
+pub fn get_card(conn: &Connection, card_id: Uuid) -> Result<Option<(Card, Uuid)>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE c.id = ?1", CARD_SELECT))?;
+    stmt.query_row([card_id.to_string()], |row| {
+        let owner = Uuid::parse_str(&row.get::<_, String>(13)?).unwrap_or_else(|_| Uuid::new_v4());
+        Ok((map_card(row)?, owner))
+    })
+    .optional()
+}
+
+pub fn list_due_cards(conn: &Connection, user_id: Uuid, date: &str, deck_id: Option<Uuid>, limit: usize) -> Result<Vec<DueCard>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE d.user_id = ?1 AND c.due_date <= ?2 AND (?3 IS NULL OR c.deck_id = ?3)
+         ORDER BY c.due_date, c.repetitions, c.created_at LIMIT ?4",
+        CARD_SELECT
+    ))?;
+    let rows = stmt.query_map(
+        params![user_id.to_string(), date, deck_id.map(|id| id.to_string()), limit as i64],
+        map_due_card,
+    )?;
+    rows.collect()
+}
+
+pub fn get_due_card_counts(conn: &Connection, user_id: Uuid, through: &str) -> Result<Vec<DeckDueCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT d.id, d.name, d.subject, c.due_date, COUNT(*)
+         FROM flashcard_cards c JOIN flashcard_decks d ON c.deck_id = d.id
+         WHERE d.user_id = ?1 AND c.due_date <= ?2
+         GROUP BY d.id, c.due_date
+         ORDER BY c.due_date, d.name",
+    )?;
+    let rows = stmt.query_map(params![user_id.to_string(), through], |row| {
+        Ok(DeckDueCount {
+            deck_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+            name: row.get(1)?,
+            subject: row.get(2)?,
+            due_date: row.get(3)?,
+            count: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn record_card_review(conn: &Connection, card_id: Uuid, schedule: &CardSchedule, review: &CardReview) -> Result<()> {
+    conn.execute(
+        "UPDATE flashcard_cards SET ease_factor = ?1, interval_days = ?2, repetitions = ?3, due_date = ?4, last_reviewed = ?5 WHERE id = ?6",
+        params![
+            schedule.ease_factor,
+            schedule.interval_days,
+            schedule.repetitions,
+            schedule.due_date,
+            schedule.last_reviewed.map(|at| at.to_rfc3339()),
+            card_id.to_string(),
+        ],
+    )?;
+    insert_card_review(conn, review)
+}
+
+pub fn insert_card_review(conn: &Connection, review: &CardReview) -> Result<()> {
+    conn.execute(
+        "INSERT INTO flashcard_reviews (id, card_id, user_id, quality, ease_factor, interval_days, reviewed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            review.review_id.to_string(),
+            review.card_id.to_string(),
+            review.user_id.to_string(),
+            review.quality,
+            review.ease_factor,
+            review.interval_days,
+            review.reviewed_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_deck_reviews(conn: &Connection, deck_id: Uuid) -> Result<Vec<CardReview>> {
+    let mut stmt = conn.prepare(
+        "SELECT r.id, r.card_id, r.user_id, r.quality, r.ease_factor, r.interval_days, r.reviewed_at
+         FROM flashcard_reviews r JOIN flashcard_cards c ON r.card_id = c.id
+         WHERE c.deck_id = ?1 ORDER BY r.reviewed_at",
+    )?;
+    let rows = stmt.query_map([deck_id.to_string()], |row| {
+        Ok(CardReview {
+            review_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+            card_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+            user_id: Uuid::parse_str(&row.get::<_, String>(2)?).unwrap_or_else(|_| Uuid::new_v4()),
+            quality: row.get(3)?,
+            ease_factor: row.get(4)?,
+            interval_days: row.get(5)?,
+            reviewed_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn insert_focus_session(conn: &Connection, session: &FocusSession) -> Result<()> {
+    conn.execute(
+        "INSERT INTO focus_sessions (id, task_id, user_id, status, started_at, ended_at, last_activity_at, segment_started_at, focused_seconds, interruptions_json)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            session.session_id.to_string(),
+            session.task_id.to_string(),
+            session.user_id.to_string(),
+            session.status,
+            session.started_at.to_rfc3339(),
+            session.ended_at.map(|at| at.to_rfc3339()),
+            session.last_activity_at.to_rfc3339(),
+            session.segment_started_at.map(|at| at.to_rfc3339()),
+            session.focused_seconds as i64,
+            serde_json::to_string(&session.interruptions).unwrap_or_else(|_| "[]".to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn update_focus_session(conn: &Connection, session: &FocusSession) -> Result<()> {
+    conn.execute(
+        "UPDATE focus_sessions SET status = ?1, ended_at = ?2, last_activity_at = ?3, segment_started_at = ?4, focused_seconds = ?5, interruptions_json = ?6
+         WHERE id = ?7",
+        params![
+            session.status,
+            session.ended_at.map(|at| at.to_rfc3339()),
+            session.last_activity_at.to_rfc3339(),
+            session.segment_started_at.map(|at| at.to_rfc3339()),
+            session.focused_seconds as i64,
+            serde_json::to_string(&session.interruptions).unwrap_or_else(|_| "[]".to_string()),
+            session.session_id.to_string(),
+        ],
+    )?;
+    Ok(())
+}
+
+const FOCUS_SESSION_SELECT: &str = "SELECT id, task_id, user_id, status, started_at, ended_at, last_activity_at, segment_started_at, focused_seconds, interruptions_json FROM focus_sessions";
+
+fn map_focus_session(row: &rusqlite::Row<'_>) -> Result<FocusSession> {
+    let timestamp = |value: Option<String>| {
+        value
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    let interruptions: String = row.get(9)?;
+    Ok(FocusSession {
+        session_id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap_or_else(|_| Uuid::new_v4()),
+        task_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap_or_else(|_| Uuid::new_v4()),
+        user_id: Uuid::parse_str(&row.get::<_, String>(2)?).unwrap_or_else(|_| Uuid::new_v4()),
+        status: row.get(3)?,
+        started_at: timestamp(row.get(4)?).unwrap_or_else(Utc::now),
+        ended_at: timestamp(row.get(5)?),
+        last_activity_at: timestamp(row.get(6)?).unwrap_or_else(Utc::now),
+        segment_started_at: timestamp(row.get(7)?),
+        focused_seconds: row.get::<_, i64>(8)?.max(0) as u64,
+        interruptions: serde_json::from_str(&interruptions).unwrap_or_default(),
+    })
+}
+
+pub fn get_focus_session(conn: &Connection, session_id: Uuid) -> Result<Option<FocusSession>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE id = ?1", FOCUS_SESSION_SELECT))?;
+    stmt.query_row([session_id.to_string()], map_focus_session).optional()
+}
+
+pub fn get_open_focus_session(conn: &Connection, user_id: Uuid) -> Result<Option<FocusSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE user_id = ?1 AND status IN ('active', 'paused') ORDER BY started_at DESC LIMIT 1",
+        FOCUS_SESSION_SELECT
+    ))?;
+    stmt.query_row([user_id.to_string()], map_focus_session).optional()
+}
+
+pub fn list_focus_sessions(conn: &Connection, user_id: Uuid, task_id: Option<Uuid>, status: Option<&str>) -> Result<Vec<FocusSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE user_id = ?1 AND (?2 IS NULL OR task_id = ?2) AND (?3 IS NULL OR status = ?3) ORDER BY started_at DESC",
+        FOCUS_SESSION_SELECT
+    ))?;
+    let rows = stmt.query_map(params![user_id.to_string(), task_id.map(|id| id.to_string()), status], map_focus_session)?;
+    rows.collect()
+}
+
+pub fn list_idle_focus_sessions(conn: &Connection, inactive_since: DateTime<Utc>) -> Result<Vec<FocusSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE status IN ('active', 'paused') AND last_activity_at <= ?1",
+        FOCUS_SESSION_SELECT
+    ))?;
+    let rows = stmt.query_map([inactive_since.to_rfc3339()], map_focus_session)?;
+    rows.collect()
+}
+
+pub fn credit_focus_minutes(conn: &Connection, task_id: Uuid, user_id: Uuid, minutes: u32, complete: bool) -> Result<Option<u32>> {
+    let completed_at = Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE tasks SET actual_duration = COALESCE(actual_duration, 0) + ?1,
+             status = CASE WHEN ?2 THEN 'completed' ELSE status END,
+             completed_at = CASE WHEN ?2 THEN ?3 ELSE completed_at END
+         WHERE id = ?4",
+        params![minutes, complete, completed_at, task_id.to_string()],
+    )?;
+    refresh_study_hours(conn, user_id, &completed_at)?;
+    get_task_actual_duration(conn, task_id)
+}
+
+fn refresh_study_hours(conn: &Connection, user_id: Uuid, at: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_stats (user_id, total_study_hours, last_activity)
+         VALUES (?1, (SELECT COALESCE(SUM(t.actual_duration), 0) / 60.0 FROM tasks t JOIN study_plans p ON t.plan_id = p.id WHERE p.user_id = ?1), ?2)
+         ON CONFLICT(user_id) DO UPDATE SET total_study_hours = excluded.total_study_hours, last_activity = excluded.last_activity",
+        params![user_id.to_string(), at],
+    )?;
+    Ok(())
+}
+
+pub fn get_task_actual_duration(conn: &Connection, task_id: Uuid) -> Result<Option<u32>> {
+    conn.query_row("SELECT actual_duration FROM tasks WHERE id = ?1", [task_id.to_string()], |row| row.get(0))
+        .optional()
+        .map(Option::flatten)
+}
+
+pub fn mark_task_in_progress(conn: &Connection, task_id: Uuid) -> Result<()> {
+    conn.execute("UPDATE tasks SET status = 'in_progress' WHERE id = ?1 AND status = 'pending'", [task_id.to_string()])?;
+    Ok(())
+}
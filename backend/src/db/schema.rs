@@ -1,6 +1,8 @@
 use rusqlite::{Connection, Result};
 
 pub fn apply(conn: &Connection) -> Result<()> {
+    drop_tool_type_check(conn)?;
+    allow_quiz_usage(conn)?;
     conn.execute_batch(
         r#"
         PRAGMA foreign_keys = ON;
@@ -20,6 +22,7 @@ pub fn apply(conn: &Connection) -> Result<()> {
             end_date TEXT NOT NULL,
             subjects_json TEXT,
             ai_rationale TEXT,
+            prompt_version TEXT,
             created_at TEXT DEFAULT CURRENT_TIMESTAMP,
             updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
@@ -49,11 +52,12 @@ pub fn apply(conn: &Connection) -> Result<()> {
             id TEXT PRIMARY KEY,
             user_id TEXT NOT NULL,
             name TEXT NOT NULL,
-            tool_type TEXT NOT NULL CHECK(tool_type IN ('calculator', 'timer', 'flashcard', 'custom')),
+            tool_type TEXT NOT NULL,
             description TEXT,
             component_code TEXT NOT NULL,
             metadata_json TEXT,
             version TEXT DEFAULT '1.0.0',
+            prompt_version TEXT,
             usage_count INTEGER DEFAULT 0,
             last_used TEXT,
             created_at TEXT DEFAULT CURRENT_TIMESTAMP,
@@ -61,12 +65,92 @@ pub fn apply(conn: &Connection) -> Result<()> {
             FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
         );
 
+        CREATE TABLE IF NOT EXISTS tool_versions (
+            id TEXT PRIMARY KEY,
+            tool_id TEXT NOT NULL,
+            version TEXT NOT NULL,
+            component_code TEXT NOT NULL,
+            instruction TEXT,
+            changes_summary TEXT NOT NULL,
+            change_type TEXT NOT NULL CHECK(change_type IN ('initial', 'patch', 'minor', 'major', 'rollback')),
+            ai_model TEXT,
+            prompt_version TEXT,
+            diagnostics_json TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE (tool_id, version),
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tool_provenance (
+            tool_id TEXT PRIMARY KEY,
+            source_tool_id TEXT NOT NULL,
+            source_version TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            exported_at TEXT NOT NULL,
+            signer_public_key TEXT,
+            signature_trusted INTEGER NOT NULL DEFAULT 0,
+            imported_at TEXT NOT NULL,
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tool_catalogue (
+            tool_id TEXT PRIMARY KEY,
+            subject TEXT,
+            published_at TEXT NOT NULL,
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tool_ratings (
+            tool_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            rating INTEGER NOT NULL CHECK(rating BETWEEN 1 AND 5),
+            rated_at TEXT NOT NULL,
+            PRIMARY KEY (tool_id, user_id),
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tool_forks (
+            tool_id TEXT PRIMARY KEY,
+            upstream_tool_id TEXT NOT NULL,
+            synced_version TEXT NOT NULL,
+            forked_at TEXT NOT NULL,
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tool_usage_events (
+            id TEXT PRIMARY KEY,
+            tool_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            event_type TEXT NOT NULL CHECK(event_type IN ('open', 'interact', 'close')),
+            task_id TEXT,
+            subject TEXT,
+            duration_seconds INTEGER,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS tool_state (
+            tool_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            state_key TEXT NOT NULL,
+            value_json TEXT NOT NULL,
+            revision INTEGER NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (tool_id, user_id, state_key),
+            FOREIGN KEY (tool_id) REFERENCES tools(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
         CREATE TABLE IF NOT EXISTS ai_conversations (
             id TEXT PRIMARY KEY,
             user_id TEXT NOT NULL,
             message TEXT NOT NULL,
             response TEXT NOT NULL,
             context_json TEXT,
+            prompt_version TEXT,
             created_at TEXT DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
         );
@@ -96,15 +180,219 @@ pub fn apply(conn: &Connection) -> Result<()> {
             FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
         );
 
+        CREATE TABLE IF NOT EXISTS ai_usage (
+            id TEXT PRIMARY KEY,
+            request_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            feature TEXT NOT NULL CHECK(feature IN ('chat', 'plan', 'tool', 'suggest', 'quiz')),
+            model TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL DEFAULT 0,
+            completion_tokens INTEGER NOT NULL DEFAULT 0,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            estimated INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS ai_budgets (
+            user_id TEXT PRIMARY KEY,
+            daily_token_limit INTEGER,
+            monthly_token_limit INTEGER,
+            on_exceed TEXT NOT NULL DEFAULT 'block' CHECK(on_exceed IN ('block', 'downgrade')),
+            downgrade_model TEXT,
+            updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS ai_response_cache (
+            cache_key TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            prompt_version TEXT NOT NULL,
+            response TEXT NOT NULL,
+            response_model TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL DEFAULT 0,
+            completion_tokens INTEGER NOT NULL DEFAULT 0,
+            hits INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            last_used_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL
+        );
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS knowledge_index USING fts5(
+            title,
+            content,
+            user_id UNINDEXED,
+            source_type UNINDEXED,
+            source_id UNINDEXED,
+            source_date UNINDEXED,
+            chunk_index UNINDEXED,
+            tokenize = 'porter unicode61'
+        );
+
+        CREATE TABLE IF NOT EXISTS quizzes (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            task_id TEXT,
+            subject TEXT NOT NULL,
+            topic TEXT NOT NULL,
+            questions_json TEXT NOT NULL,
+            source TEXT NOT NULL CHECK(source IN ('ai', 'local')),
+            prompt_version TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS quiz_attempts (
+            id TEXT PRIMARY KEY,
+            quiz_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            score REAL NOT NULL,
+            correct INTEGER NOT NULL,
+            total INTEGER NOT NULL,
+            results_json TEXT NOT NULL,
+            submitted_at TEXT NOT NULL,
+            FOREIGN KEY (quiz_id) REFERENCES quizzes(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS flashcard_decks (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            task_id TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS flashcard_cards (
+            id TEXT PRIMARY KEY,
+            deck_id TEXT NOT NULL,
+            front TEXT NOT NULL,
+            back TEXT NOT NULL,
+            tags_json TEXT NOT NULL DEFAULT '[]',
+            ease_factor REAL NOT NULL DEFAULT 2.5,
+            interval_days INTEGER NOT NULL DEFAULT 0,
+            repetitions INTEGER NOT NULL DEFAULT 0,
+            due_date TEXT NOT NULL,
+            last_reviewed TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (deck_id) REFERENCES flashcard_decks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS flashcard_reviews (
+            id TEXT PRIMARY KEY,
+            card_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            quality INTEGER NOT NULL CHECK(quality BETWEEN 0 AND 5),
+            ease_factor REAL NOT NULL,
+            interval_days INTEGER NOT NULL,
+            reviewed_at TEXT NOT NULL,
+            FOREIGN KEY (card_id) REFERENCES flashcard_cards(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS focus_sessions (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            status TEXT NOT NULL CHECK(status IN ('active', 'paused', 'completed', 'abandoned')),
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            last_activity_at TEXT NOT NULL,
+            segment_started_at TEXT,
+            focused_seconds INTEGER NOT NULL DEFAULT 0,
+            interruptions_json TEXT NOT NULL DEFAULT '[]',
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
         CREATE INDEX IF NOT EXISTS idx_tasks_plan_date ON tasks(plan_id, date);
         CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
         CREATE INDEX IF NOT EXISTS idx_tasks_due_date ON tasks(due_date);
         CREATE INDEX IF NOT EXISTS idx_tools_user_type ON tools(user_id, tool_type);
+        CREATE INDEX IF NOT EXISTS idx_tool_versions_tool ON tool_versions(tool_id, created_at);
+        CREATE INDEX IF NOT EXISTS idx_tool_usage_tool_time ON tool_usage_events(tool_id, created_at);
+        CREATE INDEX IF NOT EXISTS idx_tool_usage_user_time ON tool_usage_events(user_id, created_at);
         CREATE INDEX IF NOT EXISTS idx_plans_user_date ON study_plans(user_id, start_date);
         CREATE INDEX IF NOT EXISTS idx_ai_conversations_user ON ai_conversations(user_id, created_at);
         CREATE INDEX IF NOT EXISTS idx_reminders_user_time ON reminders(user_id, reminder_time);
         CREATE INDEX IF NOT EXISTS idx_reminders_status ON reminders(status);
+        CREATE INDEX IF NOT EXISTS idx_ai_usage_user_time ON ai_usage(user_id, created_at);
+        CREATE INDEX IF NOT EXISTS idx_ai_response_cache_expiry ON ai_response_cache(expires_at);
+        CREATE INDEX IF NOT EXISTS idx_quizzes_user_subject ON quizzes(user_id, subject, topic);
+        CREATE INDEX IF NOT EXISTS idx_quiz_attempts_quiz ON quiz_attempts(quiz_id, submitted_at);
+        CREATE INDEX IF NOT EXISTS idx_flashcard_decks_user ON flashcard_decks(user_id, subject);
+        CREATE INDEX IF NOT EXISTS idx_flashcard_cards_due ON flashcard_cards(deck_id, due_date);
+        CREATE INDEX IF NOT EXISTS idx_flashcard_reviews_card ON flashcard_reviews(card_id, reviewed_at);
+        CREATE INDEX IF NOT EXISTS idx_focus_sessions_user_status ON focus_sessions(user_id, status);
+        CREATE INDEX IF NOT EXISTS idx_focus_sessions_task ON focus_sessions(task_id, started_at);
+        CREATE INDEX IF NOT EXISTS idx_tool_forks_upstream ON tool_forks(upstream_tool_id);
         "#,
     )?;
+    ensure_column(conn, "study_plans", "prompt_version", "TEXT")?;
+    ensure_column(conn, "tools", "prompt_version", "TEXT")?;
+    ensure_column(conn, "ai_conversations", "prompt_version", "TEXT")?;
+    ensure_column(conn, "tool_versions", "diagnostics_json", "TEXT")?;
+    conn.execute(
+        "INSERT INTO tool_versions (id, tool_id, version, component_code, changes_summary, change_type, prompt_version, created_at)
+         SELECT lower(hex(randomblob(16))), id, COALESCE(version, '1.0.0'), component_code, 'Initial version', 'initial', prompt_version, strftime('%Y-%m-%dT%H:%M:%SZ', COALESCE(updated_at, created_at, CURRENT_TIMESTAMP))
+         FROM tools WHERE id NOT IN (SELECT tool_id FROM tool_versions)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn drop_tool_type_check(conn: &Connection) -> Result<()> {
+    rebuild_with(conn, "tools", " CHECK(tool_type IN ('calculator', 'timer', 'flashcard', 'custom'))", "")
+}
+
+fn allow_quiz_usage(conn: &Connection) -> Result<()> {
+    rebuild_with(
+        conn,
+        "ai_usage",
+        "CHECK(feature IN ('chat', 'plan', 'tool', 'suggest'))",
+        "CHECK(feature IN ('chat', 'plan', 'tool', 'suggest', 'quiz'))",
+    )
+}
+
+fn rebuild_with(conn: &Connection, table: &str, from: &str, to: &str) -> Result<()> {
+    let sql: Option<String> = conn
+        .query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1", [table], |row| row.get(0))
+        .ok();
+    let Some(sql) = sql.filter(|sql| sql.contains(from)) else {
+        return Ok(());
+    };
+    let columns = {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        stmt.query_map([], |row| row.get::<_, String>(1))?.collect::<Result<Vec<_>>>()?.join(", ")
+    };
+    let rebuilt = sql
+        .replacen(&format!("CREATE TABLE {}", table), &format!("CREATE TABLE {}_rebuild", table), 1)
+        .replace(from, to);
+    conn.execute_batch(&format!(
+        "PRAGMA foreign_keys = OFF;
+         BEGIN;
+         {rebuilt};
+         INSERT INTO {table}_rebuild ({columns}) SELECT {columns} FROM {table};
+         DROP TABLE {table};
+         ALTER TABLE {table}_rebuild RENAME TO {table};
+         COMMIT;
+         PRAGMA foreign_keys = ON;"
+    ))
+}
+
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
     Ok(())
 }
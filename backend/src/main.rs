@@ -14,6 +14,7 @@ async fn main() -> std::io::Result<()> {
             .app_data(state.clone())
             .wrap(Cors::permissive())
             .service(web::scope("/api").configure(api::configure))
+            .service(web::scope("/tools/preview").configure(api::tools::configure_preview))
     })
     .bind((config.host.as_str(), config.port))?
     .run()
@@ -1 +1,65 @@
-pub struct Auth;
+use std::future::{Ready, ready};
+
+use actix_web::{FromRequest, HttpRequest, dev::Payload, http::StatusCode, web};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::db::AppState;
+use crate::utils::errors::ApiError;
+
+pub const USER_HEADER: &str = "X-User-Id";
+pub const ADMIN_HEADER: &str = "X-Admin-Token";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Identity {
+    pub user_id: Uuid,
+}
+
+impl Identity {
+    pub fn ensure(&self, user_id: Uuid) -> Result<(), ApiError> {
+        if user_id == self.user_id {
+            Ok(())
+        } else {
+            Err(ApiError::new(StatusCode::FORBIDDEN, "IDENTITY_MISMATCH", "user_id does not match the requesting user"))
+        }
+    }
+}
+
+impl FromRequest for Identity {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user_id = req
+            .headers()
+            .get(USER_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| Uuid::parse_str(value.trim()).ok());
+        ready(
+            user_id
+                .map(|user_id| Identity { user_id })
+                .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", format!("A valid {} header is required", USER_HEADER))),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Admin;
+
+impl FromRequest for Admin {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let expected = req.app_data::<web::Data<AppState>>().and_then(|state| state.config.admin_token.clone());
+        let Some(expected) = expected else {
+            return ready(Err(ApiError::new(StatusCode::FORBIDDEN, "ADMIN_DISABLED", "Set ADMIN_TOKEN to enable administrative endpoints")));
+        };
+        let given = req.headers().get(ADMIN_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+        if Sha256::digest(given.trim().as_bytes()) == Sha256::digest(expected.as_bytes()) {
+            ready(Ok(Admin))
+        } else {
+            ready(Err(ApiError::new(StatusCode::FORBIDDEN, "ADMIN_REQUIRED", format!("A valid {} header is required", ADMIN_HEADER))))
+        }
+    }
+}
@@ -6,6 +6,8 @@ pub struct AiChatRequest {
     pub user_id: Uuid,
     pub message: String,
     pub context: Option<serde_json::Value>,
+    #[serde(default)]
+    pub cache: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +23,42 @@ pub struct AiChatResponse {
     pub response: String,
     pub suggested_actions: Vec<SuggestedAction>,
     pub conversation_id: Uuid,
+    pub provider: String,
+    pub model: Option<String>,
+    pub prompt_version: Option<String>,
+    pub cached: bool,
+    pub citations: Vec<Citation>,
+    #[serde(default)]
+    pub withheld_sources: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<AiFallback>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    pub index: usize,
+    pub source_type: String,
+    pub source_id: String,
+    pub title: String,
+    pub date: Option<String>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct KnowledgeChunk {
+    pub source_type: String,
+    pub source_id: String,
+    pub title: String,
+    pub date: Option<String>,
+    pub content: String,
+    pub snippet: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiFallback {
+    pub code: String,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,4 +72,131 @@ pub struct AiSuggestRequest {
 pub struct AiSuggestResponse {
     pub suggestions: Vec<String>,
     pub priority: String,
+    pub items: Vec<Suggestion>,
+    pub phrased_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    #[serde(rename = "type")]
+    pub suggestion_type: String,
+    pub message: String,
+    pub priority: String,
+    pub score: f64,
+    pub subject: Option<String>,
+    pub related_task_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenUsage {
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub cost_usd: f64,
+    pub estimated: bool,
+}
+
+impl TokenUsage {
+    pub fn estimate(model: &str, prompt: &str, completion: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            prompt_tokens: estimate_tokens(prompt),
+            completion_tokens: estimate_tokens(completion),
+            cost_usd: 0.0,
+            estimated: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AiCall {
+    pub request_id: Uuid,
+    pub user_id: Uuid,
+    pub feature: String,
+    pub model: Option<String>,
+    pub blocked: Option<String>,
+    pub cache: bool,
+    pub cache_hits: u32,
+    pub usage: Vec<TokenUsage>,
+}
+
+impl AiCall {
+    pub fn new(user_id: Uuid, feature: &str) -> Self {
+        Self {
+            request_id: Uuid::new_v4(),
+            user_id,
+            feature: feature.to_string(),
+            model: None,
+            blocked: None,
+            cache: true,
+            cache_hits: 0,
+            usage: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub cache_key: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_version: String,
+    pub content: String,
+    pub response_model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiUsageQuery {
+    pub user_id: Uuid,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageBreakdown {
+    pub key: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiBudget {
+    pub user_id: Uuid,
+    pub daily_token_limit: Option<u64>,
+    pub monthly_token_limit: Option<u64>,
+    pub on_exceed: String,
+    pub downgrade_model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiBudgetStatus {
+    #[serde(flatten)]
+    pub budget: AiBudget,
+    pub used_today: u64,
+    pub used_this_month: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiUsageResponse {
+    pub user_id: Uuid,
+    pub totals: UsageTotals,
+    pub by_feature: Vec<UsageBreakdown>,
+    pub by_model: Vec<UsageBreakdown>,
+    pub by_day: Vec<UsageBreakdown>,
+    pub budget: AiBudgetStatus,
+}
+
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
 }
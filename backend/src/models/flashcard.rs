@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckCreateRequest {
+    pub user_id: Uuid,
+    pub name: String,
+    pub subject: Option<String>,
+    pub task_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deck {
+    pub deck_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub subject: String,
+    pub task_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckSummary {
+    #[serde(flatten)]
+    pub deck: Deck,
+    pub card_count: u32,
+    pub due_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckListQuery {
+    pub user_id: Uuid,
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckListResponse {
+    pub decks: Vec<DeckSummary>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckDetailResponse {
+    #[serde(flatten)]
+    pub summary: DeckSummary,
+    pub cards: Vec<Card>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardSchedule {
+    pub ease_factor: f64,
+    pub interval_days: u32,
+    pub repetitions: u32,
+    pub due_date: String,
+    pub last_reviewed: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardCreateRequest {
+    pub user_id: Uuid,
+    pub front: String,
+    pub back: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Card {
+    pub card_id: Uuid,
+    pub deck_id: Uuid,
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub schedule: CardSchedule,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueCardsQuery {
+    pub user_id: Uuid,
+    pub date: Option<String>,
+    pub deck_id: Option<Uuid>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub deck_name: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueCardsResponse {
+    pub date: String,
+    pub cards: Vec<DueCard>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardReviewRequest {
+    pub user_id: Uuid,
+    pub quality: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardReview {
+    pub review_id: Uuid,
+    pub card_id: Uuid,
+    pub user_id: Uuid,
+    pub quality: u8,
+    pub ease_factor: f64,
+    pub interval_days: u32,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardReviewResponse {
+    pub card: Card,
+    pub review: CardReview,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckDueCount {
+    pub deck_id: Uuid,
+    pub name: String,
+    pub subject: String,
+    pub due_date: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApkgImportQuery {
+    pub user_id: Uuid,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApkgImportResponse {
+    pub decks: Vec<DeckSummary>,
+    pub imported_cards: usize,
+    pub imported_reviews: usize,
+    pub skipped_notes: usize,
+    pub media_skipped: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckQuery {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckExportQuery {
+    pub user_id: Uuid,
+}
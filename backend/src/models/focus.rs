@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStartRequest {
+    pub user_id: Uuid,
+    pub task_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusPauseRequest {
+    pub user_id: Uuid,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusActionRequest {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStopRequest {
+    pub user_id: Uuid,
+    #[serde(default)]
+    pub complete_task: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusInterruption {
+    pub paused_at: DateTime<Utc>,
+    pub resumed_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSession {
+    pub session_id: Uuid,
+    pub task_id: Uuid,
+    pub user_id: Uuid,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub last_activity_at: DateTime<Utc>,
+    pub segment_started_at: Option<DateTime<Utc>>,
+    pub focused_seconds: u64,
+    pub interruptions: Vec<FocusInterruption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSessionResponse {
+    #[serde(flatten)]
+    pub session: FocusSession,
+    pub elapsed_seconds: u64,
+    pub task_actual_duration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSessionListQuery {
+    pub user_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSessionListResponse {
+    pub sessions: Vec<FocusSessionResponse>,
+    pub total: usize,
+    pub focused_seconds: u64,
+}
@@ -1,5 +1,9 @@
 pub mod plan;
+pub mod quiz;
 pub mod ai;
+pub mod flashcard;
+pub mod focus;
 pub mod reminder;
+pub mod search;
 pub mod tool;
 pub mod user;
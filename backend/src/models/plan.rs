@@ -2,6 +2,8 @@ use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::ai::AiFallback;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanGenerateRequest {
     pub user_id: Uuid,
@@ -49,6 +51,10 @@ pub struct Plan {
     pub weekly_plan: WeeklyPlan,
     pub ai_rationale: String,
     pub generated_at: DateTime<Utc>,
+    pub prompt_version: Option<String>,
+    pub cached: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<AiFallback>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +63,10 @@ pub struct PlanResponse {
     pub weekly_plan: WeeklyPlan,
     pub ai_rationale: String,
     pub generated_at: DateTime<Utc>,
+    pub prompt_version: Option<String>,
+    pub cached: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<AiFallback>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,6 +120,7 @@ pub struct StoredTask {
     pub updated_at: DateTime<Utc>,
     pub user_id: Uuid,
     pub plan_date: String,
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
 pub fn parse_date(value: &str) -> Result<NaiveDate, chrono::ParseError> {
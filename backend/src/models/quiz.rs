@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizGenerateRequest {
+    pub user_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub subject: Option<String>,
+    pub topic: Option<String>,
+    pub question_count: Option<usize>,
+    pub difficulty_level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizQuestion {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub question_type: String,
+    pub prompt: String,
+    pub options: Vec<String>,
+    pub answer: String,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizQuestionView {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub question_type: String,
+    pub prompt: String,
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quiz {
+    pub quiz_id: Uuid,
+    pub user_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub subject: String,
+    pub topic: String,
+    pub questions: Vec<QuizQuestion>,
+    pub source: String,
+    pub prompt_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizResponse {
+    pub quiz_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub subject: String,
+    pub topic: String,
+    pub questions: Vec<QuizQuestionView>,
+    pub source: String,
+    pub prompt_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&Quiz> for QuizResponse {
+    fn from(quiz: &Quiz) -> Self {
+        Self {
+            quiz_id: quiz.quiz_id,
+            task_id: quiz.task_id,
+            subject: quiz.subject.clone(),
+            topic: quiz.topic.clone(),
+            questions: quiz
+                .questions
+                .iter()
+                .map(|question| QuizQuestionView {
+                    id: question.id,
+                    question_type: question.question_type.clone(),
+                    prompt: question.prompt.clone(),
+                    options: question.options.clone(),
+                })
+                .collect(),
+            source: quiz.source.clone(),
+            prompt_version: quiz.prompt_version.clone(),
+            created_at: quiz.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizListQuery {
+    pub user_id: Uuid,
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizQuery {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizListResponse {
+    pub quizzes: Vec<QuizResponse>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAnswer {
+    pub question_id: Uuid,
+    pub answer: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizSubmitRequest {
+    pub user_id: Uuid,
+    pub answers: Vec<QuizAnswer>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionResult {
+    pub question_id: Uuid,
+    pub correct: bool,
+    pub score: f64,
+    pub given: String,
+    pub expected: String,
+    pub explanation: String,
+    pub feedback: Option<String>,
+    pub graded_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAttempt {
+    pub attempt_id: Uuid,
+    pub quiz_id: Uuid,
+    pub user_id: Uuid,
+    pub score: f64,
+    pub correct: u32,
+    pub total: u32,
+    pub results: Vec<QuestionResult>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicMastery {
+    pub subject: String,
+    pub topic: String,
+    pub average_score: f64,
+    pub attempts: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAttemptResponse {
+    #[serde(flatten)]
+    pub attempt: QuizAttempt,
+    pub mastery: TopicMastery,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAttemptListResponse {
+    pub attempts: Vec<QuizAttempt>,
+}
@@ -0,0 +1,45 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub user_id: Uuid,
+    pub q: String,
+    pub types: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub sort: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentSearch<'a> {
+    pub user_id: Uuid,
+    pub query: &'a str,
+    pub source_types: &'a [&'a str],
+    pub from: Option<&'a str>,
+    pub to: Option<&'a str>,
+    pub recent_first: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(rename = "type")]
+    pub result_type: String,
+    pub id: String,
+    pub title: String,
+    pub snippet: String,
+    pub date: Option<String>,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
@@ -8,7 +8,13 @@ pub struct ToolGenerateRequest {
     pub tool_type: String,
     pub context: String,
     pub requirements: String,
+    #[serde(default)]
+    pub subject: Option<String>,
     pub ui_preferences: Option<UiPreferences>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub parameters: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +28,10 @@ pub struct ToolMetadata {
     pub version: String,
     pub created_at: DateTime<Utc>,
     pub ai_model: String,
+    #[serde(default)]
+    pub prompt_version: Option<String>,
+    #[serde(default)]
+    pub template: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +43,7 @@ pub struct ToolResponse {
     pub component_code: String,
     pub metadata: ToolMetadata,
     pub preview_url: String,
+    pub diagnostics: Vec<ToolDiagnostic>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,12 +81,22 @@ pub struct ToolDetailResponse {
     pub description: String,
     pub component_code: String,
     pub metadata: ToolMetadata,
+    pub provenance: Option<ToolProvenance>,
+    pub fork: Option<ToolFork>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolEditRequest {
     pub edit_instruction: String,
     pub current_state: Option<serde_json::Value>,
+    #[serde(default)]
+    pub state_ref: Option<ToolStateRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStateRef {
+    pub user_id: Uuid,
+    pub keys: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,7 +104,11 @@ pub struct ToolEditResponse {
     pub tool_id: Uuid,
     pub updated_component_code: String,
     pub changes_summary: String,
+    pub change_type: String,
+    pub previous_version: String,
     pub version: String,
+    pub prompt_version: String,
+    pub diagnostics: Vec<ToolDiagnostic>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,3 +129,402 @@ pub struct Tool {
     pub usage_count: u32,
     pub last_used: Option<DateTime<Utc>>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolVersion {
+    pub version_id: Uuid,
+    pub tool_id: Uuid,
+    pub version: String,
+    #[serde(skip_serializing)]
+    pub component_code: String,
+    pub instruction: Option<String>,
+    pub changes_summary: String,
+    pub change_type: String,
+    pub ai_model: Option<String>,
+    pub prompt_version: Option<String>,
+    pub diagnostics: Vec<ToolDiagnostic>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolVersionListResponse {
+    pub tool_id: Uuid,
+    pub current_version: String,
+    pub versions: Vec<ToolVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDiffQuery {
+    pub from: String,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDiffResponse {
+    pub tool_id: Uuid,
+    pub from: String,
+    pub to: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRollbackRequest {
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRollbackResponse {
+    pub tool_id: Uuid,
+    pub restored_from: String,
+    pub previous_version: String,
+    pub version: String,
+    pub component_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageEventRequest {
+    pub user_id: Uuid,
+    pub event_type: String,
+    pub task_id: Option<Uuid>,
+    pub duration_seconds: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageEvent {
+    pub event_id: Uuid,
+    pub tool_id: Uuid,
+    pub user_id: Uuid,
+    pub event_type: String,
+    pub task_id: Option<Uuid>,
+    pub subject: Option<String>,
+    pub duration_seconds: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageEventResponse {
+    pub event_id: Uuid,
+    pub tool_id: Uuid,
+    pub usage_count: u32,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageQuery {
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageAnalyticsQuery {
+    pub user_id: Uuid,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub top: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolUsageTotals {
+    pub opens: u32,
+    pub interactions: u32,
+    pub duration_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageBreakdown {
+    pub key: String,
+    #[serde(flatten)]
+    pub totals: ToolUsageTotals,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageRanking {
+    pub tool_id: Uuid,
+    pub name: String,
+    pub tool_type: String,
+    #[serde(flatten)]
+    pub totals: ToolUsageTotals,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolSubjectUsage {
+    pub subject: String,
+    pub tools: Vec<ToolUsageRanking>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUsageResponse {
+    pub tool_id: Uuid,
+    pub usage_count: u32,
+    pub last_used: Option<DateTime<Utc>>,
+    pub totals: ToolUsageTotals,
+    pub by_subject: Vec<ToolUsageBreakdown>,
+    pub by_day: Vec<ToolUsageBreakdown>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserToolUsageResponse {
+    pub user_id: Uuid,
+    pub totals: ToolUsageTotals,
+    pub top_tools: Vec<ToolUsageRanking>,
+    pub by_subject: Vec<ToolSubjectUsage>,
+    pub by_day: Vec<ToolUsageBreakdown>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStateEntry {
+    pub tool_id: Uuid,
+    pub user_id: Uuid,
+    pub key: String,
+    pub value: serde_json::Value,
+    pub revision: u64,
+    pub size_bytes: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStateQuery {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStatePutRequest {
+    pub user_id: Uuid,
+    pub value: serde_json::Value,
+    pub expected_revision: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStatePatchRequest {
+    pub user_id: Uuid,
+    pub patch: serde_json::Value,
+    pub expected_revision: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolStateListResponse {
+    pub tool_id: Uuid,
+    pub user_id: Uuid,
+    pub entries: Vec<ToolStateEntry>,
+    pub total_bytes: usize,
+    pub limit_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolExportQuery {
+    #[serde(default)]
+    pub include_state: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolImportQuery {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolBundle {
+    pub format: String,
+    pub content: ToolBundleContent,
+    pub content_hash: String,
+    pub signature: Option<BundleSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolBundleContent {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub source_tool_id: Uuid,
+    pub tool: BundledTool,
+    pub head: BundledVersion,
+    pub state: Option<Vec<BundledState>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledTool {
+    pub name: String,
+    pub tool_type: String,
+    pub description: String,
+    pub component_code: String,
+    pub metadata: ToolMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledVersion {
+    pub version: String,
+    pub changes_summary: String,
+    pub change_type: String,
+    pub ai_model: Option<String>,
+    pub prompt_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledState {
+    pub key: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSignature {
+    pub algorithm: String,
+    pub public_key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolProvenance {
+    pub tool_id: Uuid,
+    pub source_tool_id: Uuid,
+    pub source_version: String,
+    pub content_hash: String,
+    pub exported_at: DateTime<Utc>,
+    pub signer_public_key: Option<String>,
+    pub signature_trusted: bool,
+    pub imported_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolImportResponse {
+    #[serde(flatten)]
+    pub tool: ToolResponse,
+    pub provenance: ToolProvenance,
+    pub imported_state_keys: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolAccess {
+    Owner,
+    Shared,
+    Denied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOwnerQuery {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolPublishRequest {
+    pub user_id: Uuid,
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogueQuery {
+    #[serde(rename = "type")]
+    pub tool_type: Option<String>,
+    pub subject: Option<String>,
+    pub q: Option<String>,
+    pub sort: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogueEntry {
+    pub tool_id: Uuid,
+    pub owner_id: Uuid,
+    pub owner_name: Option<String>,
+    pub name: String,
+    pub tool_type: String,
+    pub description: String,
+    pub subject: Option<String>,
+    pub version: String,
+    pub published_at: DateTime<Utc>,
+    pub rating_average: Option<f64>,
+    pub rating_count: u32,
+    pub fork_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogueResponse {
+    pub tools: Vec<CatalogueEntry>,
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRatingRequest {
+    pub user_id: Uuid,
+    pub rating: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRatingResponse {
+    pub tool_id: Uuid,
+    pub rating: u8,
+    pub rating_average: Option<f64>,
+    pub rating_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolFork {
+    pub tool_id: Uuid,
+    pub upstream_tool_id: Uuid,
+    pub synced_version: String,
+    pub forked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolForkResponse {
+    #[serde(flatten)]
+    pub tool: ToolResponse,
+    pub fork: ToolFork,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUpstreamStatus {
+    pub tool_id: Uuid,
+    pub upstream_tool_id: Uuid,
+    pub synced_version: String,
+    pub upstream_version: String,
+    pub behind: bool,
+    pub mergeable: bool,
+    pub diff: Option<ToolDiffResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolMergeResponse {
+    pub tool_id: Uuid,
+    pub previous_version: String,
+    pub version: String,
+    pub upstream_version: String,
+    pub component_code: String,
+    pub diagnostics: Vec<ToolDiagnostic>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolDiagnostic {
+    pub severity: String,
+    pub code: String,
+    pub message: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl ToolDiagnostic {
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: "error".to_string(),
+            code: code.to_string(),
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    pub fn warning(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: "warning".to_string(),
+            ..Self::error(code, message)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
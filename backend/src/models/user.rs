@@ -32,6 +32,7 @@ pub struct UserStatsResponse {
     pub tools_created: u32,
     pub ai_interactions: u32,
     pub subjects_progress: serde_json::Value,
+    pub quiz_scores: serde_json::Value,
     pub weekly_activity: Vec<WeeklyActivity>,
 }
 
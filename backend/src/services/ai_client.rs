@@ -0,0 +1,398 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, StatusCode};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::models::ai::{AiCall, TokenUsage};
+use crate::services::policy_service::{PolicyService, PolicyViolation};
+use crate::utils::config::AppConfig;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AiError {
+    #[error("AI provider is not configured")]
+    Disabled,
+    #[error("AI provider is temporarily unavailable after repeated failures")]
+    CircuitOpen,
+    #[error("AI provider timed out")]
+    Timeout,
+    #[error("AI provider is rate limiting requests")]
+    RateLimited,
+    #[error("AI provider returned HTTP {status}: {message}")]
+    Upstream { status: u16, message: String },
+    #[error("AI provider could not be reached: {0}")]
+    Transport(String),
+    #[error("AI provider returned an unexpected response: {0}")]
+    InvalidResponse(String),
+    #[error("AI token budget exceeded for this {0}")]
+    BudgetExceeded(String),
+    #[error("The AI response was blocked by the content policy ({})", .0.join(", "))]
+    OutputBlocked(Vec<String>),
+}
+
+impl AiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Disabled => "AI_DISABLED",
+            Self::CircuitOpen => "AI_CIRCUIT_OPEN",
+            Self::Timeout => "AI_TIMEOUT",
+            Self::RateLimited => "AI_RATE_LIMITED",
+            Self::Upstream { .. } => "AI_UPSTREAM_ERROR",
+            Self::Transport(_) => "AI_UNREACHABLE",
+            Self::InvalidResponse(_) => "AI_INVALID_RESPONSE",
+            Self::BudgetExceeded(_) => "AI_BUDGET_EXCEEDED",
+            Self::OutputBlocked(_) => "POLICY_OUTPUT_BLOCKED",
+        }
+    }
+
+    fn is_transient(&self) -> bool {
+        matches!(self, Self::Timeout | Self::RateLimited | Self::Transport(_))
+            || matches!(self, Self::Upstream { status, .. } if *status >= 500)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub content: String,
+    pub model: String,
+    pub usage: TokenUsage,
+    pub cached: bool,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+pub struct AiClient {
+    http: Client,
+    enabled: bool,
+    provider: String,
+    base_url: String,
+    api_key: String,
+    model: String,
+    referer: Option<String>,
+    max_retries: u32,
+    retry_base: Duration,
+    retry_max_delay: Duration,
+    breaker_threshold: u32,
+    breaker_cooldown: Duration,
+    prompt_cost_per_mtok: f64,
+    completion_cost_per_mtok: f64,
+    embeddings_base_url: Option<String>,
+    embeddings_model: String,
+    redact_pii: bool,
+    blocked_terms: Vec<String>,
+    breaker: Mutex<BreakerState>,
+}
+
+impl AiClient {
+    pub fn new(config: &AppConfig) -> Self {
+        let http = Client::builder()
+            .connect_timeout(Duration::from_millis(config.ai_connect_timeout_ms))
+            .timeout(Duration::from_millis(config.ai_request_timeout_ms))
+            .build()
+            .unwrap_or_default();
+        let api_key = config.openrouter_api_key.clone().unwrap_or_default();
+        Self {
+            http,
+            enabled: config.ai_provider == "openrouter"
+                && !api_key.trim().is_empty()
+                && !config.openrouter_model.trim().is_empty(),
+            provider: config.ai_provider.clone(),
+            base_url: config.openrouter_base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model: config.openrouter_model.clone(),
+            referer: config.openrouter_referer.clone(),
+            max_retries: config.ai_max_retries,
+            retry_base: Duration::from_millis(config.ai_retry_base_ms),
+            retry_max_delay: Duration::from_millis(config.ai_retry_max_delay_ms),
+            breaker_threshold: config.ai_breaker_threshold.max(1),
+            breaker_cooldown: Duration::from_millis(config.ai_breaker_cooldown_ms),
+            prompt_cost_per_mtok: config.ai_prompt_cost_per_mtok,
+            completion_cost_per_mtok: config.ai_completion_cost_per_mtok,
+            embeddings_base_url: config.embeddings_base_url.as_ref().map(|url| url.trim_end_matches('/').to_string()),
+            embeddings_model: config.embeddings_model.clone(),
+            redact_pii: config.ai_redact_pii,
+            blocked_terms: config.ai_blocked_terms.clone(),
+            breaker: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn check_output(&self, text: &str) -> Result<(), AiError> {
+        PolicyService::scan_output(text, &self.blocked_terms).map_err(|violation| match violation {
+            PolicyViolation::OutputBlocked(reasons) => AiError::OutputBlocked(reasons),
+            violation => AiError::InvalidResponse(violation.to_string()),
+        })
+    }
+
+    pub async fn chat_completion(&self, messages: &[serde_json::Value], call: &mut AiCall) -> Result<Completion, AiError> {
+        if !self.enabled {
+            return Err(AiError::Disabled);
+        }
+        if let Some(period) = &call.blocked {
+            return Err(AiError::BudgetExceeded(period.clone()));
+        }
+        let probe = self.acquire()?;
+        let model = call.model.clone().unwrap_or_else(|| self.model.clone());
+        let result = self.send_with_retries(messages, &model).await;
+        self.record(probe, &result);
+        if let Ok(completion) = &result {
+            call.usage.push(completion.usage.clone());
+        }
+        result
+    }
+
+    pub async fn embeddings(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, AiError> {
+        let Some(base_url) = &self.embeddings_base_url else {
+            return Err(AiError::Disabled);
+        };
+        let inputs = if self.redact_pii { inputs.iter().map(|input| PolicyService::redact(input)).collect::<Vec<_>>() } else { inputs.to_vec() };
+        let payload = serde_json::json!({
+            "model": self.embeddings_model,
+            "input": inputs,
+        });
+        let response = self
+            .http
+            .post(format!("{}/embeddings", base_url))
+            .json(&payload)
+            .send()
+            .await
+            .map_err(transport_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(AiError::Upstream {
+                status: status.as_u16(),
+                message: upstream_message(&message),
+            });
+        }
+        let body: serde_json::Value = response.json().await.map_err(transport_error)?;
+        let vectors = body
+            .get("data")
+            .and_then(|data| data.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.get("embedding").and_then(|embedding| embedding.as_array()))
+                    .map(|embedding| embedding.iter().filter_map(|value| value.as_f64()).map(|value| value as f32).collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .ok_or_else(|| AiError::InvalidResponse("missing data[].embedding".to_string()))?;
+        if vectors.len() != inputs.len() {
+            return Err(AiError::InvalidResponse(format!("expected {} embeddings, got {}", inputs.len(), vectors.len())));
+        }
+        Ok(vectors)
+    }
+
+    async fn send_with_retries(&self, messages: &[serde_json::Value], model: &str) -> Result<Completion, AiError> {
+        let mut attempt = 0;
+        loop {
+            let (error, retry_after) = match self.send_once(messages, model).await {
+                Ok(completion) => return Ok(completion),
+                Err(failure) => failure,
+            };
+            if !error.is_transient() || attempt >= self.max_retries {
+                return Err(error);
+            }
+            let delay = match retry_after {
+                Some(delay) if delay > self.retry_max_delay => return Err(error),
+                Some(delay) => delay,
+                None => self.backoff(attempt),
+            };
+            actix_web::rt::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send_once(&self, messages: &[serde_json::Value], model: &str) -> Result<Completion, (AiError, Option<Duration>)> {
+        let messages = if self.redact_pii {
+            messages
+                .iter()
+                .map(|message| {
+                    let mut message = message.clone();
+                    if let Some(content) = message.get("content").and_then(|content| content.as_str()) {
+                        message["content"] = serde_json::Value::String(PolicyService::redact(content));
+                    }
+                    message
+                })
+                .collect::<Vec<_>>()
+        } else {
+            messages.to_vec()
+        };
+        let payload = serde_json::json!({
+            "model": model,
+            "messages": messages,
+            "usage": {"include": true},
+        });
+        let mut request_builder = self
+            .http
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .header("X-Title", "AI Study Planner");
+        if let Some(referer) = &self.referer {
+            request_builder = request_builder.header("HTTP-Referer", referer);
+        }
+        let response = request_builder.json(&payload).send().await.map_err(|err| (transport_error(err), None))?;
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = parse_retry_after(response.headers());
+            let message = response.text().await.unwrap_or_default();
+            let error = if status == StatusCode::TOO_MANY_REQUESTS {
+                AiError::RateLimited
+            } else {
+                AiError::Upstream {
+                    status: status.as_u16(),
+                    message: upstream_message(&message),
+                }
+            };
+            return Err((error, retry_after));
+        }
+        let body: serde_json::Value = response.json().await.map_err(|err| (transport_error(err), None))?;
+        let content = body
+            .get("choices")
+            .and_then(|choices| choices.get(0))
+            .and_then(|choice| choice.get("message"))
+            .and_then(|message| message.get("content"))
+            .and_then(|content| content.as_str())
+            .ok_or_else(|| (AiError::InvalidResponse("missing choices[0].message.content".to_string()), None))?;
+        let model = body
+            .get("model")
+            .and_then(|model| model.as_str())
+            .unwrap_or(model)
+            .to_string();
+        let usage = self.usage_from(body.get("usage"), &model, &messages, content);
+        Ok(Completion {
+            content: content.to_string(),
+            model,
+            usage,
+            cached: false,
+        })
+    }
+
+    fn usage_from(&self, usage: Option<&serde_json::Value>, model: &str, messages: &[serde_json::Value], content: &str) -> TokenUsage {
+        let count = |key: &str| {
+            usage
+                .and_then(|usage| usage.get(key))
+                .and_then(|value| value.as_u64())
+                .map(|value| value.min(u32::MAX as u64) as u32)
+        };
+        let mut usage_record = match (count("prompt_tokens"), count("completion_tokens")) {
+            (Some(prompt_tokens), Some(completion_tokens)) => TokenUsage {
+                model: model.to_string(),
+                prompt_tokens,
+                completion_tokens,
+                cost_usd: 0.0,
+                estimated: false,
+            },
+            _ => {
+                let prompt = messages
+                    .iter()
+                    .filter_map(|message| message.get("content").and_then(|content| content.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                TokenUsage::estimate(model, &prompt, content)
+            }
+        };
+        usage_record.cost_usd = usage
+            .and_then(|usage| usage.get("cost"))
+            .and_then(|cost| cost.as_f64())
+            .unwrap_or_else(|| {
+                (usage_record.prompt_tokens as f64 * self.prompt_cost_per_mtok
+                    + usage_record.completion_tokens as f64 * self.completion_cost_per_mtok)
+                    / 1_000_000.0
+            });
+        usage_record
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.retry_base.saturating_mul(2_u32.saturating_pow(attempt)).min(self.retry_max_delay);
+        let jitter = rand::random_range(0..=exponential.as_millis() as u64 / 2);
+        exponential.saturating_add(Duration::from_millis(jitter)).min(self.retry_max_delay)
+    }
+
+    fn acquire(&self) -> Result<Probe<'_>, AiError> {
+        let mut breaker = self.breaker.lock().expect("breaker lock");
+        let probing = match breaker.open_until {
+            Some(until) if Instant::now() < until => return Err(AiError::CircuitOpen),
+            Some(_) if breaker.probing => return Err(AiError::CircuitOpen),
+            Some(_) => true,
+            None => false,
+        };
+        breaker.probing = probing;
+        Ok(Probe { breaker: &self.breaker, active: probing })
+    }
+
+    fn record(&self, mut probe: Probe<'_>, result: &Result<Completion, AiError>) {
+        let mut breaker = self.breaker.lock().expect("breaker lock");
+        probe.active = false;
+        breaker.probing = false;
+        match result {
+            Err(error) if error.is_transient() => {
+                breaker.consecutive_failures += 1;
+                if breaker.open_until.is_some() || breaker.consecutive_failures >= self.breaker_threshold {
+                    breaker.open_until = Some(Instant::now() + self.breaker_cooldown);
+                }
+            }
+            _ => *breaker = BreakerState::default(),
+        }
+    }
+}
+
+struct Probe<'a> {
+    breaker: &'a Mutex<BreakerState>,
+    active: bool,
+}
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        if self.active {
+            self.breaker.lock().expect("breaker lock").probing = false;
+        }
+    }
+}
+
+fn transport_error(err: reqwest::Error) -> AiError {
+    if err.is_timeout() {
+        AiError::Timeout
+    } else if err.is_decode() {
+        AiError::InvalidResponse(err.to_string())
+    } else {
+        AiError::Transport(err.to_string())
+    }
+}
+
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok().or(Some(Duration::ZERO))
+}
+
+fn upstream_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| {
+            value
+                .pointer("/error/message")
+                .and_then(|message| message.as_str())
+                .map(|message| message.to_string())
+        })
+        .unwrap_or_else(|| body.chars().take(200).collect())
+}
@@ -1,76 +1,118 @@
-use reqwest::Client;
+use chrono::Utc;
 use uuid::Uuid;
 
-use crate::models::ai::{AiChatRequest, AiChatResponse, AiSuggestRequest, AiSuggestResponse};
+use crate::models::ai::{AiCall, AiChatRequest, AiChatResponse, AiFallback, Citation, AiSuggestRequest, AiSuggestResponse, KnowledgeChunk, Suggestion, TokenUsage};
+use crate::models::plan::StoredTask;
+use crate::services::ai_client::{AiClient, AiError, Completion};
+use crate::services::prompt_registry::PromptRegistry;
+use crate::services::response_cache::ResponseCache;
+use crate::services::retrieval_service::RetrievalService;
+use crate::services::suggestion_service::SuggestionService;
 use crate::utils::config::AppConfig;
 
 pub struct AiService;
 
 impl AiService {
-    pub async fn chat(request: &AiChatRequest, config: &AppConfig) -> AiChatResponse {
-        if config.ai_provider == "openrouter" {
-            if let Some(api_key) = &config.openrouter_api_key {
-                if !api_key.trim().is_empty() {
-                    if let Some(response) = Self::call_openrouter(request, config, api_key).await {
-                        return response;
-                    }
-                }
+    pub async fn chat(request: &AiChatRequest, passages: &[KnowledgeChunk], ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, config: &AppConfig, call: &mut AiCall) -> AiChatResponse {
+        let sources = RetrievalService::sources_block(passages);
+        let citations = RetrievalService::citations(passages);
+        let prompt = match prompts.render("chat", &[("sources", &sources), ("message", &request.message)]) {
+            Ok(prompt) => prompt,
+            Err(error) => return Self::local_chat(request, citations, Some(AiFallback { code: error.code, message: error.message }), call),
+        };
+        let fallback = match Self::complete(&prompt.id(), &prompt.messages(), ai, cache, call, |_| true).await {
+            Ok(completion) => {
+                return AiChatResponse {
+                    response: completion.content,
+                    suggested_actions: Vec::new(),
+                    conversation_id: Uuid::new_v4(),
+                    provider: "openrouter".to_string(),
+                    model: Some(completion.model),
+                    prompt_version: Some(prompt.id()),
+                    cached: completion.cached,
+                    citations,
+                    withheld_sources: 0,
+                    fallback: None,
+                };
+            }
+            Err(AiError::Disabled) if config.ai_provider != "openrouter" => None,
+            Err(error) => Some(AiFallback {
+                code: error.code().to_string(),
+                message: error.to_string(),
+            }),
+        };
+        Self::local_chat(request, citations, fallback, call)
+    }
+
+    fn local_chat(request: &AiChatRequest, citations: Vec<Citation>, fallback: Option<AiFallback>, call: &mut AiCall) -> AiChatResponse {
+        let mut response = format!("Based on your message: {}", request.message);
+        if !citations.is_empty() {
+            response.push_str("\n\nRelated notes:");
+            for citation in &citations {
+                response.push_str(&format!("\n[{}] {}: {}", citation.index, citation.title, citation.snippet));
             }
         }
+        call.usage.push(TokenUsage::estimate("local", &request.message, &response));
         AiChatResponse {
-            response: format!("Based on your message: {}", request.message),
+            response,
             suggested_actions: vec![crate::models::ai::SuggestedAction {
                 action_type: "generate_tool".to_string(),
                 description: "Create practice flashcards".to_string(),
                 action_data: Some(serde_json::json!({"tool_type": "flashcard"})),
             }],
             conversation_id: Uuid::new_v4(),
+            provider: "local".to_string(),
+            model: None,
+            prompt_version: None,
+            cached: false,
+            citations,
+            withheld_sources: 0,
+            fallback,
         }
     }
 
-    pub async fn suggest(_request: &AiSuggestRequest, _config: &AppConfig) -> AiSuggestResponse {
+    pub async fn suggest(request: &AiSuggestRequest, tasks: &[StoredTask], ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, config: &AppConfig, call: &mut AiCall) -> AiSuggestResponse {
+        let mut items = SuggestionService::analyze(tasks, request.data.as_ref(), Utc::now());
+        let mut phrased_by = "local".to_string();
+        if config.ai_phrase_suggestions
+            && let Some(messages) = Self::phrase_suggestions(request, &items, ai, prompts, cache, call).await
+        {
+            for (item, message) in items.iter_mut().zip(messages) {
+                item.message = message;
+            }
+            phrased_by = ai.model().to_string();
+        }
         AiSuggestResponse {
-            suggestions: vec![
-                "Consider a short break.".to_string(),
-                "Review key concepts.".to_string(),
-            ],
-            priority: "low".to_string(),
+            suggestions: items.iter().map(|item| item.message.clone()).collect(),
+            priority: items
+                .first()
+                .map(|item| item.priority.clone())
+                .unwrap_or_else(|| "low".to_string()),
+            items,
+            phrased_by,
         }
     }
 
-    async fn call_openrouter(request: &AiChatRequest, config: &AppConfig, api_key: &str) -> Option<AiChatResponse> {
-        if config.openrouter_model.trim().is_empty() {
-            return None;
-        }
-        let client = Client::new();
-        let payload = serde_json::json!({
-            "model": config.openrouter_model,
-            "messages": [
-                {"role": "system", "content": "You are a helpful study planner assistant."},
-                {"role": "user", "content": request.message}
-            ],
-        });
-        let mut request_builder = client
-            .post(format!("{}/chat/completions", config.openrouter_base_url.trim_end_matches('/')))
-            .bearer_auth(api_key)
-            .header("X-Title", "AI Study Planner");
-        if let Some(referer) = &config.openrouter_referer {
-            request_builder = request_builder.header("HTTP-Referer", referer);
-        }
-        let response = request_builder.json(&payload).send().await.ok()?;
-        let body: serde_json::Value = response.json().await.ok()?;
-        let reply = body
-            .get("choices")
-            .and_then(|choices| choices.get(0))
-            .and_then(|choice| choice.get("message"))
-            .and_then(|message| message.get("content"))
-            .and_then(|content| content.as_str())
-            .unwrap_or("I can help you with your study plan.")
-            .to_string();
-        Some(AiChatResponse {
-            response: reply,
-            suggested_actions: Vec::new(),
-            conversation_id: Uuid::new_v4(),
-        })
+    async fn phrase_suggestions(request: &AiSuggestRequest, items: &[Suggestion], ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, call: &mut AiCall) -> Option<Vec<String>> {
+        let drafts = serde_json::json!(items.iter().map(|item| item.message.as_str()).collect::<Vec<_>>()).to_string();
+        let prompt = prompts.render("suggestion_phrasing", &[("context", &request.context), ("suggestions", &drafts)]).ok()?;
+        let reply = Self::complete(&prompt.id(), &prompt.messages(), ai, cache, call, |reply| parse_phrasings(reply, items.len()).is_some())
+            .await
+            .ok()?
+            .content;
+        parse_phrasings(&reply, items.len())
+    }
+
+    pub async fn complete(prompt_version: &str, messages: &[serde_json::Value], ai: &AiClient, cache: &ResponseCache<'_>, call: &mut AiCall, accept: impl Fn(&str) -> bool) -> Result<Completion, AiError> {
+        let completion = cache.complete(ai, prompt_version, messages, call, |reply| ai.check_output(reply).is_ok() && accept(reply)).await?;
+        ai.check_output(&completion.content)?;
+        Ok(completion)
     }
 }
+
+fn parse_phrasings(reply: &str, count: usize) -> Option<Vec<String>> {
+    let start = reply.find('[')?;
+    let end = reply.rfind(']')?;
+    let phrased: Vec<String> = serde_json::from_str(reply.get(start..=end)?).ok()?;
+    (phrased.len() == count && phrased.iter().all(|message| !message.trim().is_empty())).then_some(phrased)
+}
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+
+use actix_web::http::StatusCode;
+use chrono::{DateTime, NaiveDate, TimeDelta, TimeZone, Utc};
+use regex::Regex;
+use rusqlite::{Connection, OpenFlags, params};
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+
+use crate::models::flashcard::{Card, CardReview, CardSchedule, Deck};
+use crate::services::flashcard_service::{DEFAULT_EASE, FlashcardService, MAX_INTERVAL_DAYS};
+use crate::utils::errors::ApiError;
+
+const FIELD_SEPARATOR: char = '\u{1f}';
+const MODEL_ID: i64 = 1_342_697_561_419;
+const MAX_IMPORTED_TAGS: usize = 10;
+const MAX_MEDIA_MANIFEST_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ApkgError {
+    #[error("The file is not a readable .apkg package: {0}")]
+    Invalid(String),
+    #[error("This package uses the Anki 2.1.50+ collection format; export it again with \"Support older Anki versions\" enabled")]
+    Unsupported,
+    #[error("Card {card_id} has a due date Anki cannot represent ({due})")]
+    BadSchedule { card_id: i64, due: i64 },
+    #[error("Failed to build the .apkg package: {0}")]
+    Export(String),
+}
+
+impl ApkgError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Invalid(_) => "INVALID_APKG",
+            Self::Unsupported => "UNSUPPORTED_APKG",
+            Self::BadSchedule { .. } => "INVALID_APKG_SCHEDULE",
+            Self::Export(_) => "INTERNAL_ERROR",
+        }
+    }
+}
+
+impl From<rusqlite::Error> for ApkgError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Invalid(error.to_string())
+    }
+}
+
+impl From<ApkgError> for ApiError {
+    fn from(error: ApkgError) -> Self {
+        let status = match &error {
+            ApkgError::Invalid(_) => StatusCode::BAD_REQUEST,
+            ApkgError::Unsupported | ApkgError::BadSchedule { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApkgError::Export(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError::new(status, error.code(), error.to_string())
+    }
+}
+
+pub struct ApkgCard {
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+    pub schedule: CardSchedule,
+    pub reviews: Vec<(u8, f64, u32, DateTime<Utc>)>,
+}
+
+pub struct ApkgDeck {
+    pub name: String,
+    pub cards: Vec<ApkgCard>,
+}
+
+pub struct ApkgPackage {
+    pub decks: Vec<ApkgDeck>,
+    pub skipped_notes: usize,
+    pub media_files: usize,
+}
+
+struct ScratchFile(PathBuf);
+
+impl ScratchFile {
+    fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("apkg-{}.sqlite", Uuid::new_v4())))
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+struct AnkiCardRow {
+    note_id: i64,
+    card_id: i64,
+    deck_id: i64,
+    card_type: i64,
+    queue: i64,
+    due: i64,
+    interval: i64,
+    factor: i64,
+    reps: i64,
+    lapses: i64,
+}
+
+pub struct ApkgService;
+
+impl ApkgService {
+    pub fn read(bytes: &[u8], today: NaiveDate, max_collection_bytes: u64) -> Result<ApkgPackage, ApkgError> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|error| ApkgError::Invalid(error.to_string()))?;
+        let entry_name = ["collection.anki21", "collection.anki2"]
+            .into_iter()
+            .find(|name| archive.index_for_name(name).is_some());
+        let Some(entry_name) = entry_name else {
+            return Err(if archive.index_for_name("collection.anki21b").is_some() {
+                ApkgError::Unsupported
+            } else {
+                ApkgError::Invalid("missing collection.anki2".to_string())
+            });
+        };
+        let mut collection = Vec::new();
+        archive
+            .by_name(entry_name)
+            .map_err(|error| ApkgError::Invalid(error.to_string()))?
+            .take(max_collection_bytes + 1)
+            .read_to_end(&mut collection)
+            .map_err(|error| ApkgError::Invalid(error.to_string()))?;
+        if collection.len() as u64 > max_collection_bytes {
+            return Err(ApkgError::Invalid("the embedded collection is too large".to_string()));
+        }
+        let media_files = match archive.by_name("media") {
+            Ok(media) => {
+                let mut raw = String::new();
+                media.take(MAX_MEDIA_MANIFEST_BYTES).read_to_string(&mut raw).ok();
+                serde_json::from_str::<HashMap<String, String>>(&raw).map(|map| map.len()).unwrap_or(0)
+            }
+            Err(_) => 0,
+        };
+
+        let scratch = ScratchFile::new();
+        std::fs::write(&scratch.0, &collection).map_err(|error| ApkgError::Invalid(error.to_string()))?;
+        let conn = Connection::open_with_flags(&scratch.0, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|error| ApkgError::Invalid(error.to_string()))?;
+        let mut package = Self::read_collection(&conn, today)?;
+        package.media_files = media_files;
+        Ok(package)
+    }
+
+    fn read_collection(conn: &Connection, today: NaiveDate) -> Result<ApkgPackage, ApkgError> {
+        let (crt, models, decks): (i64, String, String) =
+            conn.query_row("SELECT crt, models, decks FROM col LIMIT 1", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        let created = Utc.timestamp_opt(crt, 0).single().map(|at| at.date_naive()).unwrap_or(today);
+        let models: HashMap<String, serde_json::Value> = serde_json::from_str(&models).unwrap_or_default();
+        let deck_names: HashMap<String, String> = serde_json::from_str::<HashMap<String, serde_json::Value>>(&decks)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, deck)| (id, deck["name"].as_str().unwrap_or("Imported deck").to_string()))
+            .collect();
+
+        let mut stmt = conn.prepare(
+            "SELECT nid, id, CASE WHEN odid != 0 THEN odid ELSE did END, type, queue, CASE WHEN odid != 0 THEN odue ELSE due END, ivl, factor, reps, lapses
+             FROM cards ORDER BY nid, ord",
+        )?;
+        let mut first_cards: Vec<AnkiCardRow> = Vec::new();
+        for row in stmt.query_map([], |row| {
+            Ok(AnkiCardRow {
+                note_id: row.get(0)?,
+                card_id: row.get(1)?,
+                deck_id: row.get(2)?,
+                card_type: row.get(3)?,
+                queue: row.get(4)?,
+                due: row.get(5)?,
+                interval: row.get(6)?,
+                factor: row.get(7)?,
+                reps: row.get(8)?,
+                lapses: row.get(9)?,
+            })
+        })? {
+            let row = row?;
+            if first_cards.last().is_none_or(|last| last.note_id != row.note_id) {
+                first_cards.push(row);
+            }
+        }
+
+        let mut note_stmt = conn.prepare("SELECT mid, tags, flds FROM notes WHERE id = ?1")?;
+        let mut review_stmt = conn.prepare("SELECT id, ease, ivl, factor FROM revlog WHERE cid = ?1 ORDER BY id")?;
+        let mut package = ApkgPackage {
+            decks: Vec::new(),
+            skipped_notes: 0,
+            media_files: 0,
+        };
+        for anki_card in first_cards {
+            let Some((model_id, tags, fields)) = note_stmt
+                .query_map([anki_card.note_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+                .next()
+                .transpose()?
+            else {
+                package.skipped_notes += 1;
+                continue;
+            };
+            let cloze = models.get(&model_id.to_string()).and_then(|model| model["type"].as_i64()) == Some(1);
+            let fields = fields.split(FIELD_SEPARATOR).map(html_to_text).collect::<Vec<_>>();
+            let (front, back) = note_sides(&fields, cloze);
+            let tags = tags.split_whitespace().take(MAX_IMPORTED_TAGS).map(str::to_string).collect::<Vec<_>>();
+            let Ok(tags) = FlashcardService::validate_card(&front, &back, &tags) else {
+                package.skipped_notes += 1;
+                continue;
+            };
+
+            let reviews = review_stmt
+                .query_map([anki_card.card_id], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .into_iter()
+                .filter_map(|(id, ease, interval, factor)| {
+                    let reviewed_at = Utc.timestamp_millis_opt(id).single()?;
+                    let quality = match ease {
+                        1 => 1,
+                        2 => 3,
+                        3 => 4,
+                        _ => 5,
+                    };
+                    Some((quality, ease_from_factor(factor), clamp_interval(interval), reviewed_at))
+                })
+                .collect::<Vec<_>>();
+            let schedule = schedule_from_anki(&anki_card, created, today, reviews.last().map(|review| review.3))?;
+
+            let deck_name = deck_names.get(&anki_card.deck_id.to_string()).cloned().unwrap_or_else(|| "Imported deck".to_string());
+            let card = ApkgCard {
+                front,
+                back,
+                tags,
+                schedule,
+                reviews,
+            };
+            match package.decks.iter_mut().find(|deck| deck.name == deck_name) {
+                Some(deck) => deck.cards.push(card),
+                None => package.decks.push(ApkgDeck {
+                    name: deck_name,
+                    cards: vec![card],
+                }),
+            }
+        }
+        Ok(package)
+    }
+
+    pub fn write(deck: &Deck, cards: &[Card], reviews: &[CardReview]) -> Result<Vec<u8>, ApkgError> {
+        let scratch = ScratchFile::new();
+        let conn = Connection::open(&scratch.0).map_err(|error| ApkgError::Export(error.to_string()))?;
+        Self::write_collection(&conn, deck, cards, reviews).map_err(|error| ApkgError::Export(error.to_string()))?;
+        drop(conn);
+        let collection = std::fs::read(&scratch.0).map_err(|error| ApkgError::Export(error.to_string()))?;
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let mut add = |name: &str, data: &[u8]| -> zip::result::ZipResult<()> {
+            zip.start_file(name, options)?;
+            zip.write_all(data)?;
+            Ok(())
+        };
+        add("collection.anki2", &collection).map_err(|error| ApkgError::Export(error.to_string()))?;
+        add("media", b"{}").map_err(|error| ApkgError::Export(error.to_string()))?;
+        zip.finish()
+            .map(|cursor| cursor.into_inner())
+            .map_err(|error| ApkgError::Export(error.to_string()))
+    }
+
+    fn write_collection(conn: &Connection, deck: &Deck, cards: &[Card], reviews: &[CardReview]) -> rusqlite::Result<()> {
+        conn.execute_batch(ANKI_SCHEMA)?;
+        let now = Utc::now();
+        let created = cards.iter().map(|card| card.created_at).min().unwrap_or(deck.created_at).min(deck.created_at).date_naive();
+        let crt = created.and_hms_opt(0, 0, 0).map(|at| at.and_utc().timestamp()).unwrap_or_default();
+        let deck_id = deck.created_at.timestamp_millis();
+        let deck_json = |id: i64, name: &str| {
+            serde_json::json!({
+                "id": id, "name": name, "mod": now.timestamp(), "usn": -1, "desc": "", "dyn": 0, "conf": 1, "collapsed": false,
+                "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0], "extendNew": 10, "extendRev": 50
+            })
+        };
+        let decks = serde_json::json!({"1": deck_json(1, "Default"), deck_id.to_string(): deck_json(deck_id, &deck.name)});
+        let models = serde_json::json!({ MODEL_ID.to_string(): {
+            "id": MODEL_ID, "name": "Basic", "type": 0, "mod": now.timestamp(), "usn": -1, "sortf": 0, "did": deck_id,
+            "tmpls": [{"name": "Card 1", "ord": 0, "qfmt": "{{Front}}", "afmt": "{{FrontSide}}\n\n<hr id=answer>\n\n{{Back}}", "bqfmt": "", "bafmt": "", "did": null}],
+            "flds": [
+                {"name": "Front", "ord": 0, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []},
+                {"name": "Back", "ord": 1, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []}
+            ],
+            "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; background-color: white; }",
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}", "tags": [], "vers": [], "req": [[0, "any", [0]]]
+        }});
+        let dconf = serde_json::json!({"1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true, "timer": 0, "replayq": true, "dyn": false,
+            "new": {"delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "order": 1, "perDay": 20, "bury": true, "separate": true},
+            "rev": {"perDay": 200, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1, "maxIvl": 36500, "bury": true, "minSpace": 1},
+            "lapse": {"delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0}
+        }});
+        let conf = serde_json::json!({
+            "nextPos": cards.len() + 1, "estTimes": true, "activeDecks": [deck_id], "sortType": "noteFld", "timeLim": 0,
+            "sortBackwards": false, "addToCur": true, "curDeck": deck_id, "newBury": true, "newSpread": 0, "dueCounts": true,
+            "curModel": MODEL_ID.to_string(), "collapseTime": 1200
+        });
+        conn.execute(
+            "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags) VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+            params![crt, now.timestamp_millis(), conf.to_string(), models.to_string(), decks.to_string(), dconf.to_string()],
+        )?;
+
+        let base_id = now.timestamp_millis();
+        let mut card_ids = HashMap::new();
+        for (index, card) in cards.iter().enumerate() {
+            let id = base_id + index as i64;
+            card_ids.insert(card.card_id, id);
+            let front = text_to_html(&card.front);
+            let tags = if card.tags.is_empty() { String::new() } else { format!(" {} ", card.tags.join(" ")) };
+            conn.execute(
+                "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data) VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+                params![
+                    id,
+                    card.card_id.simple().to_string()[..10].to_string(),
+                    MODEL_ID,
+                    now.timestamp(),
+                    tags,
+                    format!("{}{}{}", front, FIELD_SEPARATOR, text_to_html(&card.back)),
+                    card.front,
+                    field_checksum(&card.front),
+                ],
+            )?;
+            let reviewed = card.schedule.repetitions > 0 || card.schedule.last_reviewed.is_some();
+            let due = if reviewed {
+                NaiveDate::parse_from_str(&card.schedule.due_date, "%Y-%m-%d")
+                    .map(|date| (date - created).num_days())
+                    .unwrap_or_default()
+            } else {
+                index as i64 + 1
+            };
+            let (card_type, factor) = if reviewed { (2, (card.schedule.ease_factor * 1000.0).round() as i64) } else { (0, 0) };
+            conn.execute(
+                "INSERT INTO cards (id, nid, did, ord, mod, usn, type, queue, due, ivl, factor, reps, lapses, left, odue, odid, flags, data)
+                 VALUES (?1, ?1, ?2, 0, ?3, -1, ?4, ?4, ?5, ?6, ?7, ?8, 0, 0, 0, 0, 0, '')",
+                params![id, deck_id, now.timestamp(), card_type, due, card.schedule.interval_days, factor, card.schedule.repetitions],
+            )?;
+        }
+
+        let mut last_id = 0;
+        for review in reviews {
+            let Some(card_id) = card_ids.get(&review.card_id) else {
+                continue;
+            };
+            let id = review.reviewed_at.timestamp_millis().max(last_id + 1);
+            last_id = id;
+            let ease = match review.quality {
+                0..=2 => 1,
+                3 => 2,
+                4 => 3,
+                _ => 4,
+            };
+            conn.execute(
+                "INSERT INTO revlog (id, cid, usn, ease, ivl, lastIvl, factor, time, type) VALUES (?1, ?2, -1, ?3, ?4, 0, ?5, 0, 1)",
+                params![id, card_id, ease, review.interval_days, (review.ease_factor * 1000.0).round() as i64],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn schedule_from_anki(card: &AnkiCardRow, created: NaiveDate, today: NaiveDate, last_reviewed: Option<DateTime<Utc>>) -> Result<CardSchedule, ApkgError> {
+    if card.card_type == 0 {
+        return Ok(FlashcardService::new_schedule(today));
+    }
+    let due = if card.card_type == 2 || card.queue == 3 || card.due < 1_000_000_000 {
+        TimeDelta::try_days(card.due).and_then(|offset| created.checked_add_signed(offset))
+    } else {
+        Utc.timestamp_opt(card.due, 0).single().map(|at| at.date_naive())
+    };
+    let due = due.ok_or(ApkgError::BadSchedule {
+        card_id: card.card_id,
+        due: card.due,
+    })?;
+    let interval_days = clamp_interval(card.interval);
+    let repetitions = match (card.card_type, interval_days) {
+        (2, 0..=1) => 1,
+        (2, _) => (card.reps - card.lapses).max(2) as u32,
+        _ => 0,
+    };
+    Ok(CardSchedule {
+        ease_factor: ease_from_factor(card.factor),
+        interval_days,
+        repetitions,
+        due_date: due.format("%Y-%m-%d").to_string(),
+        last_reviewed,
+    })
+}
+
+fn clamp_interval(interval: i64) -> u32 {
+    interval.clamp(0, i64::from(MAX_INTERVAL_DAYS)) as u32
+}
+
+fn ease_from_factor(factor: i64) -> f64 {
+    if factor > 0 { (factor as f64 / 1000.0).max(1.3) } else { DEFAULT_EASE }
+}
+
+fn note_sides(fields: &[String], cloze: bool) -> (String, String) {
+    let first = fields.first().cloned().unwrap_or_default();
+    let extra = fields.iter().skip(1).filter(|field| !field.is_empty()).cloned().collect::<Vec<_>>();
+    if cloze {
+        let pattern = Regex::new(r"\{\{c\d+::(.*?)(?:::(.*?))?\}\}").expect("cloze pattern");
+        let front = pattern
+            .replace_all(&first, |captures: &regex::Captures<'_>| format!("[{}]", captures.get(2).map_or("...", |hint| hint.as_str())))
+            .to_string();
+        let answer = pattern.replace_all(&first, "$1").to_string();
+        let back = std::iter::once(answer).chain(extra).collect::<Vec<_>>().join("\n");
+        return (front, back);
+    }
+    (first, extra.join("\n"))
+}
+
+fn html_to_text(html: &str) -> String {
+    let line_breaks = Regex::new(r"(?i)<br\s*/?>|</div>|</p>").expect("break pattern");
+    let images = Regex::new(r#"(?i)<img[^>]*src="([^"]*)"[^>]*>"#).expect("image pattern");
+    let sounds = Regex::new(r"\[sound:([^\]]*)\]").expect("sound pattern");
+    let tags = Regex::new(r"<[^>]*>").expect("tag pattern");
+    let text = line_breaks.replace_all(html, "\n");
+    let text = images.replace_all(&text, "[image: $1]");
+    let text = sounds.replace_all(&text, "[audio: $1]");
+    let text = tags.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+fn text_to_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('\n', "<br>")
+}
+
+fn field_checksum(text: &str) -> i64 {
+    let digest = Sha1::digest(text.as_bytes());
+    i64::from(u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]))
+}
+
+const ANKI_SCHEMA: &str = r#"
+CREATE TABLE col (
+    id INTEGER PRIMARY KEY, crt INTEGER NOT NULL, mod INTEGER NOT NULL, scm INTEGER NOT NULL, ver INTEGER NOT NULL, dty INTEGER NOT NULL,
+    usn INTEGER NOT NULL, ls INTEGER NOT NULL, conf TEXT NOT NULL, models TEXT NOT NULL, decks TEXT NOT NULL, dconf TEXT NOT NULL, tags TEXT NOT NULL
+);
+CREATE TABLE notes (
+    id INTEGER PRIMARY KEY, guid TEXT NOT NULL, mid INTEGER NOT NULL, mod INTEGER NOT NULL, usn INTEGER NOT NULL, tags TEXT NOT NULL,
+    flds TEXT NOT NULL, sfld INTEGER NOT NULL, csum INTEGER NOT NULL, flags INTEGER NOT NULL, data TEXT NOT NULL
+);
+CREATE TABLE cards (
+    id INTEGER PRIMARY KEY, nid INTEGER NOT NULL, did INTEGER NOT NULL, ord INTEGER NOT NULL, mod INTEGER NOT NULL, usn INTEGER NOT NULL,
+    type INTEGER NOT NULL, queue INTEGER NOT NULL, due INTEGER NOT NULL, ivl INTEGER NOT NULL, factor INTEGER NOT NULL, reps INTEGER NOT NULL,
+    lapses INTEGER NOT NULL, left INTEGER NOT NULL, odue INTEGER NOT NULL, odid INTEGER NOT NULL, flags INTEGER NOT NULL, data TEXT NOT NULL
+);
+CREATE TABLE revlog (
+    id INTEGER PRIMARY KEY, cid INTEGER NOT NULL, usn INTEGER NOT NULL, ease INTEGER NOT NULL, ivl INTEGER NOT NULL, lastIvl INTEGER NOT NULL,
+    factor INTEGER NOT NULL, time INTEGER NOT NULL, type INTEGER NOT NULL
+);
+CREATE TABLE graves (usn INTEGER NOT NULL, oid INTEGER NOT NULL, type INTEGER NOT NULL);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+"#;
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::plan::PlanGenerateRequest;
+use crate::services::plan_service::{PlanService, extract_json, parse_time};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub name: String,
+    pub request: PlanGenerateRequest,
+    #[serde(default)]
+    pub responses: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseReport {
+    pub name: String,
+    pub checks: Vec<CheckResult>,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseComparison {
+    pub name: String,
+    pub score: f64,
+    pub baseline: Option<f64>,
+    pub delta: Option<f64>,
+    pub regressed: bool,
+}
+
+pub struct EvalService;
+
+impl EvalService {
+    pub fn load_corpus(dir: &Path) -> Result<Vec<EvalCase>, String> {
+        let mut paths = fs::read_dir(dir)
+            .map_err(|err| format!("{}: {}", dir.display(), err))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+            .iter()
+            .map(|path| {
+                let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+                serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+            })
+            .collect()
+    }
+
+    pub fn load_baseline(path: &Path) -> BTreeMap<String, f64> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn score_plan_output(name: &str, request: &PlanGenerateRequest, raw: &str) -> CaseReport {
+        let root = extract_json(raw).and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok());
+        let days = root
+            .as_ref()
+            .map(|root| root.get("weekly_plan").unwrap_or(root))
+            .and_then(|plan| plan.get("daily_plans"))
+            .and_then(|days| days.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let budget = PlanService::daily_budget(request);
+
+        let mut checks = vec![
+            check("valid_json", root.is_some(), None),
+            match PlanService::parse_ai_plan(raw, request) {
+                Ok(_) => check("accepted", true, None),
+                Err(errors) => check("accepted", false, Some(errors.join("; "))),
+            },
+        ];
+
+        let over_budget = days
+            .iter()
+            .filter_map(|day| {
+                let minutes = tasks_of(day).iter().filter_map(|task| task.get("duration_minutes").and_then(|value| value.as_u64())).sum::<u64>();
+                (minutes > budget as u64).then(|| format!("{} has {} of {} minutes", date_of(day), minutes, budget))
+            })
+            .collect::<Vec<_>>();
+        checks.push(check("within_budget", root.is_some() && over_budget.is_empty(), Some(over_budget.join("; ")).filter(|detail| !detail.is_empty())));
+
+        let missing = request
+            .subjects
+            .iter()
+            .filter(|subject| {
+                !days.iter().flat_map(tasks_of).any(|task| {
+                    task.get("subject")
+                        .and_then(|value| value.as_str())
+                        .is_some_and(|value| value.trim().eq_ignore_ascii_case(subject.trim()))
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        checks.push(check("subjects_covered", root.is_some() && missing.is_empty(), Some(missing.join(", ")).filter(|detail| !detail.is_empty()).map(|detail| format!("missing {}", detail))));
+
+        let overlapping = days.iter().filter(|day| has_overlap(&tasks_of(day))).map(date_of).collect::<Vec<_>>();
+        checks.push(check("no_overlaps", root.is_some() && overlapping.is_empty(), Some(overlapping.join(", ")).filter(|detail| !detail.is_empty())));
+
+        let passed = checks.iter().filter(|check| check.passed).count();
+        CaseReport {
+            name: name.to_string(),
+            score: ((passed as f64 / checks.len() as f64) * 1000.0).round() / 1000.0,
+            checks,
+        }
+    }
+
+    pub fn compare(reports: &[CaseReport], baseline: &BTreeMap<String, f64>, tolerance: f64) -> Vec<CaseComparison> {
+        reports
+            .iter()
+            .map(|report| {
+                let previous = baseline.get(&report.name).copied();
+                let delta = previous.map(|previous| ((report.score - previous) * 1000.0).round() / 1000.0);
+                CaseComparison {
+                    name: report.name.clone(),
+                    score: report.score,
+                    baseline: previous,
+                    delta,
+                    regressed: delta.is_some_and(|delta| delta < -tolerance),
+                }
+            })
+            .collect()
+    }
+
+    pub fn render_report(reports: &[CaseReport], comparisons: &[CaseComparison]) -> String {
+        let mut output = String::new();
+        for (report, comparison) in reports.iter().zip(comparisons) {
+            let baseline = match (comparison.baseline, comparison.delta) {
+                (Some(baseline), Some(delta)) => format!("baseline {:.3}, delta {:+.3}{}", baseline, delta, if comparison.regressed { "  REGRESSED" } else { "" }),
+                _ => "no baseline".to_string(),
+            };
+            output.push_str(&format!("{:<32} {:.3}  ({})\n", report.name, report.score, baseline));
+            for check in report.checks.iter().filter(|check| !check.passed) {
+                output.push_str(&format!("    FAIL {}{}\n", check.name, check.detail.as_ref().map(|detail| format!(": {}", detail)).unwrap_or_default()));
+            }
+        }
+        let mean = if reports.is_empty() { 0.0 } else { reports.iter().map(|report| report.score).sum::<f64>() / reports.len() as f64 };
+        let baseline_scores = comparisons.iter().filter_map(|comparison| comparison.baseline).collect::<Vec<_>>();
+        output.push_str(&format!("\n{} cases, mean score {:.3}", reports.len(), mean));
+        if !baseline_scores.is_empty() {
+            output.push_str(&format!(", baseline mean {:.3}", baseline_scores.iter().sum::<f64>() / baseline_scores.len() as f64));
+        }
+        let regressions = comparisons.iter().filter(|comparison| comparison.regressed).count();
+        output.push_str(&format!(", {} regressed\n", regressions));
+        output
+    }
+}
+
+fn check(name: &str, passed: bool, detail: Option<String>) -> CheckResult {
+    CheckResult {
+        name: name.to_string(),
+        passed,
+        detail: if passed { None } else { detail },
+    }
+}
+
+fn tasks_of(day: &serde_json::Value) -> Vec<serde_json::Value> {
+    day.get("tasks").and_then(|tasks| tasks.as_array()).cloned().unwrap_or_default()
+}
+
+fn date_of(day: &serde_json::Value) -> String {
+    day.get("date").and_then(|date| date.as_str()).unwrap_or("undated").to_string()
+}
+
+fn has_overlap(tasks: &[serde_json::Value]) -> bool {
+    let mut slots = tasks
+        .iter()
+        .filter_map(|task| {
+            let (hour, minute) = parse_time(task.get("start_time")?.as_str()?)?;
+            let start = hour * 60 + minute;
+            Some((start, start + task.get("duration_minutes")?.as_u64()? as u32))
+        })
+        .collect::<Vec<_>>();
+    slots.sort();
+    slots.windows(2).any(|pair| pair[1].0 < pair[0].1)
+}
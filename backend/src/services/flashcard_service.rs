@@ -0,0 +1,73 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::models::flashcard::CardSchedule;
+use crate::utils::errors::ApiError;
+
+pub const DEFAULT_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+const PASSING_QUALITY: u8 = 3;
+const MAX_SIDE_LENGTH: usize = 2000;
+const MAX_TAGS: usize = 10;
+pub const MAX_INTERVAL_DAYS: u32 = 36500;
+
+pub struct FlashcardService;
+
+impl FlashcardService {
+    pub fn new_schedule(today: NaiveDate) -> CardSchedule {
+        CardSchedule {
+            ease_factor: DEFAULT_EASE,
+            interval_days: 0,
+            repetitions: 0,
+            due_date: today.format("%Y-%m-%d").to_string(),
+            last_reviewed: None,
+        }
+    }
+
+    pub fn schedule(current: &CardSchedule, quality: u8, reviewed_at: DateTime<Utc>) -> CardSchedule {
+        let quality = quality.min(5);
+        let (repetitions, interval_days) = if quality >= PASSING_QUALITY {
+            let interval = match current.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (current.interval_days.max(1) as f64 * current.ease_factor).round().min(f64::from(MAX_INTERVAL_DAYS)) as u32,
+            };
+            (current.repetitions + 1, interval)
+        } else {
+            (0, 1)
+        };
+        let miss = f64::from(5 - quality);
+        let ease_factor = (current.ease_factor + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        let interval_days = interval_days.min(MAX_INTERVAL_DAYS);
+        let today = reviewed_at.date_naive();
+        let due = today.checked_add_signed(Duration::days(i64::from(interval_days))).unwrap_or(today);
+        CardSchedule {
+            ease_factor: (ease_factor * 100.0).round() / 100.0,
+            interval_days,
+            repetitions,
+            due_date: due.format("%Y-%m-%d").to_string(),
+            last_reviewed: Some(reviewed_at),
+        }
+    }
+
+    pub fn validate_card(front: &str, back: &str, tags: &[String]) -> Result<Vec<String>, ApiError> {
+        for (field, text) in [("front", front), ("back", back)] {
+            if text.trim().is_empty() {
+                return Err(ApiError::validation(format!("{} must not be empty", field)));
+            }
+            if text.chars().count() > MAX_SIDE_LENGTH {
+                return Err(ApiError::validation(format!("{} must be at most {} characters", field, MAX_SIDE_LENGTH)));
+            }
+        }
+        let mut normalized = Vec::new();
+        for tag in tags {
+            let tag = tag.trim().to_lowercase().replace(char::is_whitespace, "-");
+            if !tag.is_empty() && !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        if normalized.len() > MAX_TAGS {
+            return Err(ApiError::validation(format!("A card can have at most {} tags", MAX_TAGS)));
+        }
+        Ok(normalized)
+    }
+}
@@ -0,0 +1,140 @@
+use actix_web::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::models::focus::{FocusInterruption, FocusSession};
+use crate::utils::errors::ApiError;
+
+const MAX_REASON_LENGTH: usize = 200;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum FocusError {
+    #[error("Session {session_id} is still open; stop it before starting another")]
+    AlreadyOpen { session_id: Uuid },
+    #[error("Cannot {action} a session that is {status}")]
+    InvalidTransition { action: &'static str, status: String },
+}
+
+impl FocusError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::AlreadyOpen { .. } => "FOCUS_SESSION_OPEN",
+            Self::InvalidTransition { .. } => "FOCUS_SESSION_STATE",
+        }
+    }
+}
+
+impl From<FocusError> for ApiError {
+    fn from(error: FocusError) -> Self {
+        let details = match &error {
+            FocusError::AlreadyOpen { session_id } => serde_json::json!({"session_id": session_id}),
+            FocusError::InvalidTransition { status, .. } => serde_json::json!({"status": status}),
+        };
+        ApiError::new(StatusCode::CONFLICT, error.code(), error.to_string()).with_details(details)
+    }
+}
+
+pub struct FocusService;
+
+impl FocusService {
+    pub fn start(task_id: Uuid, user_id: Uuid, now: DateTime<Utc>) -> FocusSession {
+        FocusSession {
+            session_id: Uuid::new_v4(),
+            task_id,
+            user_id,
+            status: "active".to_string(),
+            started_at: now,
+            ended_at: None,
+            last_activity_at: now,
+            segment_started_at: Some(now),
+            focused_seconds: 0,
+            interruptions: Vec::new(),
+        }
+    }
+
+    pub fn is_open(session: &FocusSession) -> bool {
+        matches!(session.status.as_str(), "active" | "paused")
+    }
+
+    pub fn pause(session: &mut FocusSession, reason: Option<String>, now: DateTime<Utc>) -> Result<(), FocusError> {
+        Self::require(session, "pause", &["active"])?;
+        Self::close_segment(session, now);
+        let reason = reason
+            .map(|reason| reason.trim().chars().take(MAX_REASON_LENGTH).collect::<String>())
+            .filter(|reason| !reason.is_empty());
+        session.interruptions.push(FocusInterruption {
+            paused_at: now,
+            resumed_at: None,
+            reason,
+        });
+        session.status = "paused".to_string();
+        session.last_activity_at = now;
+        Ok(())
+    }
+
+    pub fn resume(session: &mut FocusSession, now: DateTime<Utc>) -> Result<(), FocusError> {
+        Self::require(session, "resume", &["paused"])?;
+        if let Some(interruption) = session.interruptions.last_mut() {
+            interruption.resumed_at = Some(now);
+        }
+        session.segment_started_at = Some(now);
+        session.status = "active".to_string();
+        session.last_activity_at = now;
+        Ok(())
+    }
+
+    pub fn heartbeat(session: &mut FocusSession, now: DateTime<Utc>) -> Result<(), FocusError> {
+        Self::require(session, "record activity on", &["active", "paused"])?;
+        session.last_activity_at = now;
+        Ok(())
+    }
+
+    pub fn stop(session: &mut FocusSession, now: DateTime<Utc>) -> Result<(), FocusError> {
+        Self::require(session, "stop", &["active", "paused"])?;
+        Self::close_segment(session, now);
+        session.status = "completed".to_string();
+        session.ended_at = Some(now);
+        session.last_activity_at = now;
+        Ok(())
+    }
+
+    pub fn expire(session: &mut FocusSession, idle_timeout: Duration, now: DateTime<Utc>) -> bool {
+        let cutoff = session.last_activity_at + idle_timeout;
+        if !Self::is_open(session) || cutoff > now {
+            return false;
+        }
+        Self::close_segment(session, cutoff);
+        session.status = "abandoned".to_string();
+        session.ended_at = Some(cutoff);
+        true
+    }
+
+    pub fn elapsed_seconds(session: &FocusSession, now: DateTime<Utc>) -> u64 {
+        let running = session
+            .segment_started_at
+            .map(|started| (now - started).num_seconds().max(0) as u64)
+            .unwrap_or(0);
+        session.focused_seconds + running
+    }
+
+    pub fn credited_minutes(session: &FocusSession) -> u32 {
+        ((session.focused_seconds + 30) / 60).min(u64::from(u32::MAX)) as u32
+    }
+
+    fn close_segment(session: &mut FocusSession, at: DateTime<Utc>) {
+        if let Some(started) = session.segment_started_at.take() {
+            session.focused_seconds += (at - started).num_seconds().max(0) as u64;
+        }
+    }
+
+    fn require(session: &FocusSession, action: &'static str, allowed: &[&str]) -> Result<(), FocusError> {
+        if allowed.contains(&session.status.as_str()) {
+            Ok(())
+        } else {
+            Err(FocusError::InvalidTransition {
+                action,
+                status: session.status.clone(),
+            })
+        }
+    }
+}
@@ -1,4 +1,23 @@
+pub mod ai_client;
 pub mod ai_service;
+pub mod apkg_service;
+pub mod eval_service;
+pub mod flashcard_service;
+pub mod focus_service;
 pub mod plan_service;
+pub mod policy_service;
+pub mod prompt_registry;
+pub mod quiz_service;
 pub mod reminder_service;
+pub mod response_cache;
+pub mod search_service;
+pub mod retrieval_service;
+pub mod suggestion_service;
+pub mod template_registry;
+pub mod tool_bundle_service;
+pub mod tool_catalogue_service;
+pub mod tool_preview;
 pub mod tool_service;
+pub mod tool_state_service;
+pub mod tool_validator;
+pub mod usage_service;
@@ -1,7 +1,23 @@
 use chrono::{Datelike, Duration, NaiveDate, Utc};
 use uuid::Uuid;
 
+use crate::models::ai::{AiCall, AiFallback};
+use crate::models::flashcard::DeckDueCount;
 use crate::models::plan::{DailyPlan, Plan, PlanGenerateRequest, Task, WeeklyPlan};
+use crate::models::quiz::TopicMastery;
+use crate::services::ai_client::AiClient;
+use crate::services::ai_service::AiService;
+use crate::services::prompt_registry::{PromptRegistry, RenderedPrompt};
+use crate::services::response_cache::ResponseCache;
+use crate::utils::config::AppConfig;
+use crate::utils::errors::ApiError;
+
+const WEAK_TOPIC_SCORE: f64 = 0.7;
+const REVIEW_MINUTES: u32 = 30;
+const FLASHCARD_SECONDS: u32 = 30;
+const FLASHCARD_MIN_MINUTES: u32 = 10;
+const FLASHCARD_MAX_MINUTES: u32 = 45;
+const MIN_TASK_MINUTES: u32 = 10;
 
 pub struct PlanService;
 
@@ -72,6 +88,395 @@ impl PlanService {
             },
             ai_rationale: "Plan focuses on consistent daily progress.".to_string(),
             generated_at: Utc::now(),
+            prompt_version: None,
+            cached: false,
+            fallback: None,
+        }
+    }
+
+    pub async fn generate(request: &PlanGenerateRequest, ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, config: &AppConfig, call: &mut AiCall) -> Plan {
+        Self::generate_traced(request, ai, prompts, cache, config, call).await.0
+    }
+
+    pub async fn generate_traced(request: &PlanGenerateRequest, ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, config: &AppConfig, call: &mut AiCall) -> (Plan, Vec<String>) {
+        let mut replies = Vec::new();
+        if !config.ai_plan_generation || !ai.is_enabled() {
+            return (Self::generate_plan(request), replies);
+        }
+        match Self::generate_with_ai(request, ai, prompts, cache, config, call, &mut replies).await {
+            Ok(plan) => (plan, replies),
+            Err(fallback) => {
+                let mut plan = Self::generate_plan(request);
+                plan.fallback = Some(fallback);
+                (plan, replies)
+            }
+        }
+    }
+
+    async fn generate_with_ai(request: &PlanGenerateRequest, ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, config: &AppConfig, call: &mut AiCall, replies: &mut Vec<String>) -> Result<Plan, AiFallback> {
+        let prompt = Self::build_plan_prompt(request, prompts).map_err(|error| AiFallback { code: error.code, message: error.message })?;
+        let mut messages = prompt.messages();
+        let mut last_errors = Vec::new();
+        for _ in 0..config.ai_plan_max_attempts.max(1) {
+            let completion = AiService::complete(&prompt.id(), &messages, ai, cache, call, |reply| Self::parse_ai_plan(reply, request).is_ok()).await.map_err(|error| AiFallback {
+                code: error.code().to_string(),
+                message: error.to_string(),
+            })?;
+            let reply = completion.content;
+            replies.push(reply.clone());
+            match Self::parse_ai_plan(&reply, request) {
+                Ok((weekly_plan, ai_rationale)) => {
+                    return Ok(Plan {
+                        plan_id: Uuid::new_v4(),
+                        user_id: request.user_id,
+                        weekly_plan,
+                        ai_rationale,
+                        generated_at: Utc::now(),
+                        prompt_version: Some(prompt.id()),
+                        cached: completion.cached,
+                        fallback: None,
+                    });
+                }
+                Err(errors) => {
+                    messages.push(serde_json::json!({"role": "assistant", "content": reply}));
+                    messages.push(serde_json::json!({
+                        "role": "user",
+                        "content": format!(
+                            "The plan you returned is invalid:\n- {}\nReturn the corrected plan as JSON only.",
+                            errors.join("\n- ")
+                        ),
+                    }));
+                    last_errors = errors;
+                }
+            }
+        }
+        Err(AiFallback {
+            code: "AI_PLAN_INVALID".to_string(),
+            message: format!("The AI plan failed validation: {}", last_errors.join("; ")),
+        })
+    }
+
+    pub fn build_plan_prompt(request: &PlanGenerateRequest, prompts: &PromptRegistry) -> Result<RenderedPrompt, ApiError> {
+        let subjects = request.subjects.join(", ");
+        let hours = request.study_hours_per_day.to_string();
+        let minutes = Self::daily_budget(request).to_string();
+        prompts.render(
+            "plan_generation",
+            &[
+                ("subjects", &subjects),
+                ("goals", &request.goals),
+                ("hours", &hours),
+                ("level", &request.difficulty_level),
+                ("date", &request.start_date),
+                ("minutes", &minutes),
+            ],
+        )
+    }
+
+    pub fn parse_ai_plan(raw: &str, request: &PlanGenerateRequest) -> Result<(WeeklyPlan, String), Vec<String>> {
+        let json = extract_json(raw).ok_or_else(|| vec!["response does not contain a JSON object".to_string()])?;
+        let root: serde_json::Value =
+            serde_json::from_str(json).map_err(|err| vec![format!("response is not valid JSON: {}", err)])?;
+        let plan_value = root.get("weekly_plan").unwrap_or(&root);
+        let ai_rationale = ["rationale", "ai_rationale", "explanation"]
+            .iter()
+            .find_map(|key| root.get(*key).and_then(|value| value.as_str()))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "Plan focuses on consistent daily progress.".to_string());
+        let days = plan_value
+            .get("daily_plans")
+            .and_then(|value| value.as_array())
+            .ok_or_else(|| vec!["weekly_plan.daily_plans must be an array".to_string()])?;
+
+        let base_date = NaiveDate::parse_from_str(&request.start_date, "%Y-%m-%d")
+            .unwrap_or_else(|_| Utc::now().date_naive());
+        let week_end = base_date + Duration::days(6);
+        let budget = Self::daily_budget(request);
+        let mut errors = Vec::new();
+        let mut daily_plans: Vec<DailyPlan> = Vec::new();
+
+        for (index, day_value) in days.iter().enumerate() {
+            let date = match day_value.get("date").and_then(|value| value.as_str()) {
+                Some(value) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                    Ok(date) => date,
+                    Err(_) => {
+                        errors.push(format!("daily_plans[{}].date '{}' is not YYYY-MM-DD", index, value));
+                        continue;
+                    }
+                },
+                None => base_date + Duration::days(index as i64),
+            };
+            if date < base_date || date > week_end {
+                errors.push(format!("daily_plans[{}].date {} is outside the week {} to {}", index, date, base_date, week_end));
+                continue;
+            }
+            let date_str = date.format("%Y-%m-%d").to_string();
+            if daily_plans.iter().any(|plan| plan.date == date_str) {
+                errors.push(format!("daily_plans[{}].date {} appears more than once", index, date_str));
+                continue;
+            }
+            let task_values = day_value
+                .get("tasks")
+                .and_then(|value| value.as_array())
+                .cloned()
+                .unwrap_or_default();
+            let mut tasks = Vec::new();
+            for (task_index, task_value) in task_values.iter().enumerate() {
+                match Self::parse_ai_task(task_value, request, &date_str) {
+                    Ok(task) => tasks.push(task),
+                    Err(err) => errors.push(format!("daily_plans[{}].tasks[{}]: {}", index, task_index, err)),
+                }
+            }
+            let tasks = clip_to_budget(tasks, budget);
+            let breaks = day_value
+                .get("breaks")
+                .and_then(|value| value.as_array())
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|value| value.as_str())
+                        .filter(|value| parse_time(value).is_some())
+                        .map(|value| value.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .filter(|values| !values.is_empty())
+                .unwrap_or_else(|| vec!["11:00".to_string(), "15:00".to_string()]);
+            daily_plans.push(DailyPlan {
+                date: date_str,
+                day: date.weekday().to_string(),
+                total_study_time: tasks.iter().map(|task| task.duration_minutes).sum(),
+                tasks,
+                breaks,
+            });
+        }
+
+        if daily_plans.len() != 7 && errors.is_empty() {
+            errors.push(format!("expected 7 daily plans, got {}", daily_plans.len()));
+        }
+        if daily_plans.iter().all(|plan| plan.tasks.is_empty()) && errors.is_empty() {
+            errors.push("plan does not contain any tasks".to_string());
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        daily_plans.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok((
+            WeeklyPlan {
+                week_start: request.start_date.clone(),
+                week_end: week_end.format("%Y-%m-%d").to_string(),
+                subjects: request.subjects.clone(),
+                daily_plans,
+            },
+            ai_rationale,
+        ))
+    }
+
+    fn parse_ai_task(value: &serde_json::Value, request: &PlanGenerateRequest, date: &str) -> Result<Task, String> {
+        let raw_subject = value.get("subject").and_then(|value| value.as_str()).map(str::trim);
+        let subject = match (raw_subject, request.subjects.as_slice()) {
+            (Some(subject), []) if !subject.is_empty() => subject.to_string(),
+            (Some(subject), subjects) => subjects
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(subject))
+                .cloned()
+                .ok_or_else(|| format!("subject '{}' is not one of {}", subject, subjects.join(", ")))?,
+            (None, [only]) => only.clone(),
+            (None, _) => return Err("subject is required".to_string()),
+        };
+        let duration_minutes = value
+            .get("duration_minutes")
+            .and_then(|value| value.as_u64().or_else(|| value.as_f64().map(|minutes| minutes.round() as u64)))
+            .filter(|minutes| *minutes > 0)
+            .ok_or_else(|| "duration_minutes must be a positive integer".to_string())?;
+        let topic = value
+            .get("topic")
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|topic| !topic.is_empty())
+            .map(|topic| topic.to_string())
+            .unwrap_or_else(|| format!("{} review", subject));
+        let start_time = value
+            .get("start_time")
+            .and_then(|value| value.as_str())
+            .and_then(parse_time)
+            .map(|(hour, minute)| format!("{:02}:{:02}", hour, minute))
+            .unwrap_or_default();
+        let priority = value
+            .get("priority")
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_ascii_lowercase())
+            .filter(|value| matches!(value.as_str(), "low" | "medium" | "high"))
+            .unwrap_or_else(|| "medium".to_string());
+        let resources = value
+            .get("resources")
+            .and_then(|value| value.as_array())
+            .map(|values| values.iter().filter_map(|value| value.as_str()).map(|value| value.to_string()).collect())
+            .unwrap_or_default();
+        let ai_notes = value
+            .get("ai_notes")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string();
+        Ok(Task {
+            id: Uuid::new_v4(),
+            subject,
+            topic,
+            duration_minutes: duration_minutes.min(u32::MAX as u64) as u32,
+            start_time,
+            due_date: date.to_string(),
+            priority,
+            resources,
+            ai_notes,
+        })
+    }
+
+    pub fn reinforce_weak_topics(plan: &mut Plan, mastery: &[TopicMastery], budget: u32) {
+        let mut reinforced = Vec::new();
+        for weak in mastery.iter().filter(|topic| topic.average_score < WEAK_TOPIC_SCORE) {
+            let days = plan
+                .weekly_plan
+                .daily_plans
+                .iter()
+                .enumerate()
+                .filter(|(_, day)| day.tasks.iter().any(|task| task.subject.eq_ignore_ascii_case(&weak.subject)))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            let Some(&day_index) = days.get(reinforced.len() % days.len().max(1)) else {
+                continue;
+            };
+            let day = &mut plan.weekly_plan.daily_plans[day_index];
+            let percent = (weak.average_score * 100.0).round() as u32;
+            let task = Task {
+                id: Uuid::new_v4(),
+                subject: day.tasks.iter().find(|task| task.subject.eq_ignore_ascii_case(&weak.subject)).map(|task| task.subject.clone()).unwrap_or_else(|| weak.subject.clone()),
+                topic: format!("Review: {}", weak.topic),
+                duration_minutes: REVIEW_MINUTES,
+                start_time: String::new(),
+                due_date: day.date.clone(),
+                priority: "high".to_string(),
+                resources: vec!["Missed quiz questions".to_string(), format!("{} practice set", weak.topic)],
+                ai_notes: format!("Quiz average {}% on {}. Revisit the questions you missed, then retake the quiz.", percent, weak.topic),
+            };
+            if add_within_budget(day, task, budget) {
+                reinforced.push(format!("{} ({}%)", weak.topic, percent));
+            }
+        }
+        if !reinforced.is_empty() {
+            plan.ai_rationale = format!("{} Added review sessions for weak quiz topics: {}.", plan.ai_rationale, reinforced.join(", "));
+        }
+    }
+
+    pub fn schedule_flashcard_reviews(plan: &mut Plan, due: &[DeckDueCount], budget: u32) {
+        let Some(first_date) = plan.weekly_plan.daily_plans.first().map(|day| day.date.clone()) else {
+            return;
+        };
+        let mut per_day: Vec<(usize, &DeckDueCount, u32)> = Vec::new();
+        for entry in due {
+            let date = if entry.due_date < first_date { &first_date } else { &entry.due_date };
+            let Some(day_index) = plan.weekly_plan.daily_plans.iter().position(|day| &day.date == date) else {
+                continue;
+            };
+            match per_day.iter_mut().find(|(index, deck, _)| *index == day_index && deck.deck_id == entry.deck_id) {
+                Some((_, _, count)) => *count += entry.count,
+                None => per_day.push((day_index, entry, entry.count)),
+            }
+        }
+        let mut total_cards = 0;
+        for (day_index, deck, count) in per_day {
+            let minutes = (count * FLASHCARD_SECONDS).div_ceil(60).clamp(FLASHCARD_MIN_MINUTES, FLASHCARD_MAX_MINUTES);
+            let day = &mut plan.weekly_plan.daily_plans[day_index];
+            let task = Task {
+                id: Uuid::new_v4(),
+                subject: deck.subject.clone(),
+                topic: format!("Flashcards: {}", deck.name),
+                duration_minutes: minutes,
+                start_time: String::new(),
+                due_date: day.date.clone(),
+                priority: "medium".to_string(),
+                resources: vec![format!("{} due card{} in {}", count, if count == 1 { "" } else { "s" }, deck.name)],
+                ai_notes: format!("Spaced repetition review for deck {}. Grade each card honestly so the schedule stays accurate.", deck.deck_id),
+            };
+            if add_within_budget(day, task, budget) {
+                total_cards += count;
+            }
+        }
+        if total_cards > 0 {
+            plan.ai_rationale = format!("{} Scheduled flashcard reviews for {} due card{}.", plan.ai_rationale, total_cards, if total_cards == 1 { "" } else { "s" });
+        }
+    }
+
+    pub fn daily_budget(request: &PlanGenerateRequest) -> u32 {
+        request.study_hours_per_day.saturating_mul(60).max(60)
+    }
+}
+
+fn add_within_budget(day: &mut DailyPlan, mut task: Task, budget: u32) -> bool {
+    let mut tasks = day.tasks.clone();
+    let mut over = (tasks.iter().map(|task| task.duration_minutes).sum::<u32>() + task.duration_minutes).saturating_sub(budget);
+    while over > 0 {
+        let Some(longest) = tasks.iter_mut().filter(|task| task.duration_minutes > MIN_TASK_MINUTES).max_by_key(|task| task.duration_minutes) else {
+            break;
+        };
+        let trimmed = over.min(longest.duration_minutes - MIN_TASK_MINUTES);
+        longest.duration_minutes -= trimmed;
+        over -= trimmed;
+    }
+    task.duration_minutes = task.duration_minutes.saturating_sub(over);
+    let start = tasks
+        .iter()
+        .filter_map(|task| parse_time(&task.start_time).map(|(hour, minute)| hour * 60 + minute + task.duration_minutes))
+        .max()
+        .unwrap_or(9 * 60);
+    task.duration_minutes = task.duration_minutes.min((24 * 60_u32).saturating_sub(start));
+    if task.duration_minutes < MIN_TASK_MINUTES {
+        return false;
+    }
+    task.start_time = format!("{:02}:{:02}", start / 60, start % 60);
+    tasks.push(task);
+    day.total_study_time = tasks.iter().map(|task| task.duration_minutes).sum();
+    day.tasks = tasks;
+    true
+}
+
+pub(crate) fn extract_json(raw: &str) -> Option<&str> {
+    let start = raw.find('{')?;
+    let end = raw.rfind('}')?;
+    (end > start).then(|| &raw[start..=end])
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = value.trim().split_once(':')?;
+    let hour = hour.parse::<u32>().ok()?;
+    let minute = minute.parse::<u32>().ok()?;
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+fn clip_to_budget(mut tasks: Vec<Task>, budget: u32) -> Vec<Task> {
+    tasks.sort_by_key(|task| parse_time(&task.start_time).unwrap_or((24, 0)));
+    let mut clipped = Vec::new();
+    let mut used = 0_u32;
+    let mut next_free: Option<u32> = None;
+    for mut task in tasks {
+        if used >= budget {
+            break;
+        }
+        task.duration_minutes = task.duration_minutes.min(budget - used);
+        let requested = parse_time(&task.start_time).map(|(hour, minute)| hour * 60 + minute);
+        let start = match (requested, next_free) {
+            (Some(requested), Some(free)) => requested.max(free),
+            (Some(requested), None) => requested,
+            (None, Some(free)) => free,
+            (None, None) => 9 * 60,
+        };
+        if start + task.duration_minutes > 24 * 60 {
+            break;
         }
+        task.start_time = format!("{:02}:{:02}", start / 60, start % 60);
+        next_free = Some(start + task.duration_minutes);
+        used += task.duration_minutes;
+        clipped.push(task);
     }
+    clipped
 }
@@ -0,0 +1,123 @@
+use std::sync::LazyLock;
+
+use actix_web::http::StatusCode;
+use regex::{Regex, RegexSet};
+
+use crate::models::ai::KnowledgeChunk;
+use crate::utils::config::AppConfig;
+use crate::utils::errors::ApiError;
+
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").expect("email pattern"));
+static PHONE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)[\s.-]?|\b\d{2,4}[\s.-])\d{3,4}[\s.-]\d{3,4}\b").expect("phone pattern"));
+
+static INJECTION: LazyLock<RegexSet> = LazyLock::new(|| {
+    RegexSet::new([
+        r"(?i)\b(ignore|disregard|forget|override)\b.{0,30}\b(previous|prior|above|earlier|system)\b.{0,20}\b(instructions?|prompts?|rules|messages?)\b",
+        r"(?i)\byou are now\b",
+        r"(?i)\b(reveal|print|repeat|show)\b.{0,20}\b(system prompt|your instructions|hidden instructions)\b",
+        r"(?i)\bact as\b.{0,20}\b(unfiltered|jailbroken|dan|developer mode)\b",
+    ])
+    .expect("injection patterns")
+});
+
+static CONTROL_TOKENS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<\|im_(start|end)\|>|\[/?inst\]|\[/?system\]").expect("control token pattern"));
+
+static SECRET_OUTPUT: LazyLock<RegexSet> = LazyLock::new(|| {
+    RegexSet::new([
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----",
+        r"\bsk-(or-v1-)?[A-Za-z0-9]{20,}\b",
+        r"\bAKIA[0-9A-Z]{16}\b",
+    ])
+    .expect("secret patterns")
+});
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("{field} is {length} characters, above the limit of {limit}")]
+    InputTooLarge { field: String, length: usize, limit: usize },
+    #[error("The AI response was blocked by the content policy")]
+    OutputBlocked(Vec<String>),
+}
+
+impl PolicyViolation {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InputTooLarge { .. } => "POLICY_INPUT_TOO_LARGE",
+            Self::OutputBlocked(_) => "POLICY_OUTPUT_BLOCKED",
+        }
+    }
+}
+
+impl From<PolicyViolation> for ApiError {
+    fn from(violation: PolicyViolation) -> Self {
+        let (status, details) = match &violation {
+            PolicyViolation::InputTooLarge { field, length, limit } => {
+                (StatusCode::PAYLOAD_TOO_LARGE, serde_json::json!({"field": field, "length": length, "limit": limit}))
+            }
+            PolicyViolation::OutputBlocked(reasons) => (StatusCode::UNPROCESSABLE_ENTITY, serde_json::json!({"reasons": reasons})),
+        };
+        ApiError::new(status, violation.code(), violation.to_string()).with_details(details)
+    }
+}
+
+pub struct PolicyService;
+
+impl PolicyService {
+    // Fenced code blocks are sent as they are, since rewriting a string inside tool code would change the program.
+    pub fn redact(text: &str) -> String {
+        text.split("```")
+            .enumerate()
+            .map(|(index, part)| {
+                if index % 2 == 1 {
+                    return part.to_string();
+                }
+                let part = EMAIL.replace_all(part, "[email]");
+                PHONE.replace_all(&part, "[phone]").into_owned()
+            })
+            .collect::<Vec<_>>()
+            .join("```")
+    }
+
+    pub fn check_input(field: &str, text: &str, config: &AppConfig) -> Result<(), PolicyViolation> {
+        let length = text.chars().count();
+        if config.ai_max_input_chars > 0 && length > config.ai_max_input_chars {
+            return Err(PolicyViolation::InputTooLarge {
+                field: field.to_string(),
+                length,
+                limit: config.ai_max_input_chars,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn is_injection(text: &str) -> bool {
+        INJECTION.is_match(text)
+    }
+
+    // Stored text is quoted into prompts on a single line, so it cannot open a `system:` turn or
+    // smuggle chat-template tokens.
+    pub fn neutralize(text: &str) -> String {
+        CONTROL_TOKENS.replace_all(text, " ").split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    // Returns the passages that are safe to quote and the number that were withheld.
+    pub fn filter_passages(passages: Vec<KnowledgeChunk>) -> (Vec<KnowledgeChunk>, usize) {
+        let (withheld, kept): (Vec<_>, Vec<_>) = passages.into_iter().partition(|passage| Self::is_injection(&passage.content));
+        (kept, withheld.len())
+    }
+
+    pub fn scan_output(text: &str, blocked_terms: &[String]) -> Result<(), PolicyViolation> {
+        let mut reasons = Vec::new();
+        if SECRET_OUTPUT.is_match(text) {
+            reasons.push("credential".to_string());
+        }
+        let lowered = text.to_lowercase();
+        reasons.extend(
+            blocked_terms
+                .iter()
+                .filter(|term| lowered.contains(&term.to_lowercase()))
+                .map(|term| format!("blocked term '{}'", term)),
+        );
+        if reasons.is_empty() { Ok(()) } else { Err(PolicyViolation::OutputBlocked(reasons)) }
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use actix_web::http::StatusCode;
+use serde::Serialize;
+
+use crate::utils::errors::ApiError;
+
+const BUILTIN_PROMPTS: [(&str, &str); 7] = [
+    ("chat", include_str!("../../prompts/chat.txt")),
+    ("plan_generation", include_str!("../../prompts/plan_generation.txt")),
+    ("tool_generation", include_str!("../../prompts/tool_generation.txt")),
+    ("tool_editing", include_str!("../../prompts/tool_editing.txt")),
+    ("suggestion_phrasing", include_str!("../../prompts/suggestion_phrasing.txt")),
+    ("quiz_generation", include_str!("../../prompts/quiz_generation.txt")),
+    ("quiz_grading", include_str!("../../prompts/quiz_grading.txt")),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub system: String,
+    pub user: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedPrompt {
+    pub name: String,
+    pub version: String,
+    pub system: String,
+    pub user: String,
+}
+
+impl RenderedPrompt {
+    pub fn id(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+
+    pub fn messages(&self) -> Vec<serde_json::Value> {
+        vec![
+            serde_json::json!({"role": "system", "content": self.system}),
+            serde_json::json!({"role": "user", "content": self.user}),
+        ]
+    }
+}
+
+impl PromptTemplate {
+    pub fn parse(name: &str, source: &str, text: &str) -> Result<Self, String> {
+        let mut version = None;
+        let mut sections: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut current: Option<&str> = None;
+        for line in text.lines() {
+            match line.trim() {
+                "[system]" => current = Some("system"),
+                "[user]" => current = Some("user"),
+                _ => match current {
+                    Some(section) => sections.entry(section).or_default().push(line),
+                    None => {
+                        if let Some((key, value)) = line.split_once(':')
+                            && key.trim() == "version"
+                        {
+                            version = Some(value.trim().to_string());
+                        }
+                    }
+                },
+            }
+        }
+        let section = |key: &str| sections.get(key).map(|lines| lines.join("\n").trim().to_string()).unwrap_or_default();
+        let template = Self {
+            name: name.to_string(),
+            version: version.filter(|value| !value.is_empty()).ok_or_else(|| format!("prompt '{}' has no version header", name))?,
+            source: source.to_string(),
+            system: section("system"),
+            user: section("user"),
+        };
+        if template.user.is_empty() {
+            return Err(format!("prompt '{}' has an empty [user] section", name));
+        }
+        Ok(template)
+    }
+
+    pub fn render(&self, variables: &[(&str, &str)]) -> RenderedPrompt {
+        RenderedPrompt {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            system: substitute(&self.system, variables),
+            user: substitute(&self.user, variables),
+        }
+    }
+}
+
+pub struct PromptRegistry {
+    dir: PathBuf,
+    templates: RwLock<BTreeMap<String, PromptTemplate>>,
+}
+
+impl PromptRegistry {
+    pub fn load(data_dir: &str) -> Self {
+        let registry = Self {
+            dir: Path::new(data_dir).join("prompts"),
+            templates: RwLock::new(BTreeMap::new()),
+        };
+        registry.reload();
+        registry
+    }
+
+    pub fn reload(&self) -> Vec<String> {
+        let mut templates = BTreeMap::new();
+        for (name, text) in BUILTIN_PROMPTS {
+            if let Ok(template) = PromptTemplate::parse(name, "builtin", text) {
+                templates.insert(name.to_string(), template);
+            }
+        }
+        let mut errors = Vec::new();
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("txt") {
+                    continue;
+                }
+                let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                let parsed = fs::read_to_string(&path)
+                    .map_err(|err| format!("{}: {}", path.display(), err))
+                    .and_then(|text| PromptTemplate::parse(name, &path.to_string_lossy(), &text));
+                match parsed {
+                    Ok(template) => {
+                        templates.insert(name.to_string(), template);
+                    }
+                    Err(err) => errors.push(err),
+                }
+            }
+        }
+        *self.templates.write().expect("prompt lock") = templates;
+        errors
+    }
+
+    pub fn get(&self, name: &str) -> Option<PromptTemplate> {
+        self.templates.read().expect("prompt lock").get(name).cloned()
+    }
+
+    pub fn render(&self, name: &str, variables: &[(&str, &str)]) -> Result<RenderedPrompt, ApiError> {
+        self.get(name)
+            .map(|template| template.render(variables))
+            .ok_or_else(|| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "PROMPT_NOT_FOUND", format!("Prompt template '{}' is not loaded", name)))
+    }
+
+    pub fn list(&self) -> Vec<PromptTemplate> {
+        self.templates.read().expect("prompt lock").values().cloned().collect()
+    }
+}
+
+fn substitute(text: &str, variables: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let key = after[..end].trim();
+                match variables.iter().find(|(name, _)| *name == key) {
+                    Some((_, value)) => output.push_str(value),
+                    None => output.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                output.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+
+use crate::models::ai::AiCall;
+use crate::models::quiz::{QuestionResult, Quiz, QuizAnswer, QuizAttempt, QuizQuestion};
+use crate::services::ai_client::AiClient;
+use crate::services::ai_service::AiService;
+use crate::services::plan_service::extract_json;
+use crate::services::prompt_registry::PromptRegistry;
+use crate::services::response_cache::ResponseCache;
+
+const QUESTION_BANK: &str = include_str!("../../templates/question_bank.json");
+const PASS_SCORE: f64 = 0.6;
+const STOPWORDS: &[&str] = &["the", "and", "for", "are", "that", "this", "with", "its", "into", "from", "than", "then", "their", "which", "when", "what"];
+
+pub struct QuizService;
+
+pub struct QuizSpec<'a> {
+    pub user_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub subject: &'a str,
+    pub topic: &'a str,
+    pub question_count: usize,
+    pub difficulty_level: &'a str,
+}
+
+impl QuizService {
+    pub async fn generate(spec: &QuizSpec<'_>, ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, call: &mut AiCall) -> Quiz {
+        let mut quiz = Quiz {
+            quiz_id: Uuid::new_v4(),
+            user_id: spec.user_id,
+            task_id: spec.task_id,
+            subject: spec.subject.to_string(),
+            topic: spec.topic.to_string(),
+            questions: Vec::new(),
+            source: "local".to_string(),
+            prompt_version: None,
+            created_at: Utc::now(),
+        };
+        if ai.is_enabled() {
+            let count = spec.question_count.to_string();
+            if let Ok(prompt) = prompts.render(
+                "quiz_generation",
+                &[("count", &count), ("level", spec.difficulty_level), ("subject", spec.subject), ("topic", spec.topic)],
+            ) && let Ok(completion) = AiService::complete(&prompt.id(), &prompt.messages(), ai, cache, call, |reply| !Self::parse_questions(reply).is_empty()).await
+            {
+                let questions = Self::parse_questions(&completion.content);
+                if !questions.is_empty() {
+                    quiz.questions = questions.into_iter().take(spec.question_count).collect();
+                    quiz.source = "ai".to_string();
+                    quiz.prompt_version = Some(prompt.id());
+                    return quiz;
+                }
+            }
+        }
+        quiz.questions = Self::from_bank(spec.subject, spec.topic, spec.question_count);
+        quiz
+    }
+
+    pub fn parse_questions(raw: &str) -> Vec<QuizQuestion> {
+        let Some(root) = extract_json(raw).and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok()) else {
+            return Vec::new();
+        };
+        let text = |value: &serde_json::Value, key: &str| value.get(key).and_then(|field| field.as_str()).map(|field| field.trim().to_string()).unwrap_or_default();
+        root.get("questions")
+            .and_then(|questions| questions.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let prompt = text(item, "question");
+                        let mut answer = text(item, "answer");
+                        let options = item
+                            .get("options")
+                            .and_then(|options| options.as_array())
+                            .map(|options| options.iter().filter_map(|option| option.as_str()).map(|option| option.trim().to_string()).collect::<Vec<_>>())
+                            .unwrap_or_default();
+                        if prompt.is_empty() || answer.is_empty() {
+                            return None;
+                        }
+                        let question_type = if options.is_empty() { "short_answer" } else { "multiple_choice" };
+                        if question_type == "multiple_choice" {
+                            if !(2..=6).contains(&options.len()) {
+                                return None;
+                            }
+                            answer = match_option(&answer, &options)?.to_string();
+                        }
+                        Some(QuizQuestion {
+                            id: Uuid::new_v4(),
+                            question_type: question_type.to_string(),
+                            prompt,
+                            options,
+                            answer,
+                            explanation: text(item, "explanation"),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn from_bank(subject: &str, topic: &str, count: usize) -> Vec<QuizQuestion> {
+        let bank: BTreeMap<String, Vec<serde_json::Value>> = serde_json::from_str(QUESTION_BANK).unwrap_or_default();
+        let subject_key = subject.to_lowercase();
+        let mut entries = bank
+            .iter()
+            .filter(|(key, _)| key.as_str() != "general" && (subject_key.contains(key.as_str()) || key.contains(subject_key.as_str())))
+            .flat_map(|(_, entries)| entries.clone())
+            .collect::<Vec<_>>();
+        entries.shuffle(&mut rand::rng());
+        entries.extend(bank.get("general").cloned().unwrap_or_default());
+        entries
+            .iter()
+            .take(count)
+            .map(|entry| {
+                let fill = |key: &str| {
+                    entry
+                        .get(key)
+                        .and_then(|value| value.as_str())
+                        .unwrap_or_default()
+                        .replace("{topic}", topic)
+                        .replace("{subject}", subject)
+                };
+                QuizQuestion {
+                    id: Uuid::new_v4(),
+                    question_type: fill("type"),
+                    prompt: fill("prompt"),
+                    options: entry
+                        .get("options")
+                        .and_then(|options| options.as_array())
+                        .map(|options| options.iter().filter_map(|option| option.as_str()).map(|option| option.to_string()).collect())
+                        .unwrap_or_default(),
+                    answer: fill("answer"),
+                    explanation: fill("explanation"),
+                }
+            })
+            .collect()
+    }
+
+    pub async fn grade(quiz: &Quiz, answers: &[QuizAnswer], ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, call: &mut AiCall) -> QuizAttempt {
+        let mut results = quiz
+            .questions
+            .iter()
+            .map(|question| {
+                let given = answers
+                    .iter()
+                    .find(|answer| answer.question_id == question.id)
+                    .map(|answer| answer.answer.trim().to_string())
+                    .unwrap_or_default();
+                Self::grade_locally(question, &given)
+            })
+            .collect::<Vec<_>>();
+
+        let pending = results
+            .iter()
+            .filter(|result| result.graded_by == "keyword" && !result.given.is_empty())
+            .map(|result| result.question_id)
+            .collect::<Vec<_>>();
+        if !pending.is_empty() && ai.is_enabled() {
+            let items = quiz
+                .questions
+                .iter()
+                .filter(|question| pending.contains(&question.id))
+                .map(|question| {
+                    let given = results.iter().find(|result| result.question_id == question.id).map(|result| result.given.as_str()).unwrap_or_default();
+                    serde_json::json!({"question_id": question.id, "question": question.prompt, "model_answer": question.answer, "student_answer": given})
+                })
+                .collect::<Vec<_>>();
+            let items = serde_json::json!(items).to_string();
+            if let Ok(prompt) = prompts.render("quiz_grading", &[("subject", &quiz.subject), ("topic", &quiz.topic), ("answers", &items)])
+                && let Ok(completion) = AiService::complete(&prompt.id(), &prompt.messages(), ai, cache, call, |reply| !parse_grades(reply).is_empty()).await
+            {
+                for (question_id, score, feedback) in parse_grades(&completion.content) {
+                    if let Some(result) = results.iter_mut().find(|result| result.question_id == question_id && pending.contains(&question_id)) {
+                        result.score = score;
+                        result.correct = score >= PASS_SCORE;
+                        result.feedback = Some(feedback).filter(|feedback| !feedback.is_empty());
+                        result.graded_by = "model".to_string();
+                    }
+                }
+            }
+        }
+
+        let total = results.len() as u32;
+        let correct = results.iter().filter(|result| result.correct).count() as u32;
+        let score = if results.is_empty() { 0.0 } else { results.iter().map(|result| result.score).sum::<f64>() / results.len() as f64 };
+        QuizAttempt {
+            attempt_id: Uuid::new_v4(),
+            quiz_id: quiz.quiz_id,
+            user_id: quiz.user_id,
+            score: (score * 1000.0).round() / 1000.0,
+            correct,
+            total,
+            results,
+            submitted_at: Utc::now(),
+        }
+    }
+
+    fn grade_locally(question: &QuizQuestion, given: &str) -> QuestionResult {
+        let (score, graded_by) = if question.question_type == "multiple_choice" {
+            let chosen = match_option(given, &question.options);
+            (if chosen == Some(question.answer.as_str()) { 1.0 } else { 0.0 }, "exact")
+        } else if normalize(given) == normalize(&question.answer) {
+            (1.0, "exact")
+        } else {
+            (keyword_overlap(&question.answer, given), "keyword")
+        };
+        QuestionResult {
+            question_id: question.id,
+            correct: score >= PASS_SCORE,
+            score,
+            given: given.to_string(),
+            expected: question.answer.clone(),
+            explanation: question.explanation.clone(),
+            feedback: None,
+            graded_by: graded_by.to_string(),
+        }
+    }
+}
+
+fn match_option<'a>(answer: &str, options: &'a [String]) -> Option<&'a str> {
+    let wanted = normalize(answer);
+    if let Some(option) = options.iter().find(|option| normalize(option) == wanted) {
+        return Some(option);
+    }
+    let letter = answer.trim().trim_end_matches([')', '.', ':']).to_ascii_uppercase();
+    if letter.len() == 1 {
+        let index = letter.as_bytes()[0].checked_sub(b'A')? as usize;
+        return options.get(index).map(|option| option.as_str());
+    }
+    None
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn keywords(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for word in text.split(|ch: char| !ch.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        let meaningful = word.chars().count() >= 3 || (!word.is_empty() && word.chars().all(|ch| ch.is_ascii_digit()));
+        if meaningful && !STOPWORDS.contains(&word.as_str()) && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn keyword_overlap(expected: &str, given: &str) -> f64 {
+    let expected = keywords(expected);
+    if expected.is_empty() {
+        return 0.0;
+    }
+    let given = keywords(given);
+    let matched = expected
+        .iter()
+        .filter(|word| {
+            given.iter().any(|candidate| {
+                candidate == *word || (candidate.len() >= 4 && word.len() >= 4 && (candidate.starts_with(word.as_str()) || word.starts_with(candidate.as_str())))
+            })
+        })
+        .count();
+    ((matched as f64 / expected.len() as f64) * 100.0).round() / 100.0
+}
+
+fn parse_grades(raw: &str) -> Vec<(Uuid, f64, String)> {
+    let (Some(start), Some(end)) = (raw.find('['), raw.rfind(']')) else {
+        return Vec::new();
+    };
+    let Some(items) = raw.get(start..=end).and_then(|json| serde_json::from_str::<Vec<serde_json::Value>>(json).ok()) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let question_id = item.get("question_id").and_then(|id| id.as_str()).and_then(|id| Uuid::parse_str(id).ok())?;
+            let score = item.get("score").and_then(|score| score.as_f64())?.clamp(0.0, 1.0);
+            let feedback = item.get("feedback").and_then(|feedback| feedback.as_str()).unwrap_or_default().trim().to_string();
+            Some((question_id, score, feedback))
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+
+use chrono::Utc;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+
+use crate::db::repository;
+use crate::models::ai::{AiCall, CachedResponse, TokenUsage};
+use crate::services::ai_client::{AiClient, AiError, Completion};
+use crate::utils::config::AppConfig;
+
+pub struct ResponseCache<'a> {
+    db: &'a Mutex<Connection>,
+    ttl_secs: u64,
+    max_entries: u64,
+}
+
+impl<'a> ResponseCache<'a> {
+    pub fn new(db: &'a Mutex<Connection>, config: &AppConfig) -> Self {
+        Self {
+            db,
+            ttl_secs: config.ai_cache_ttl_secs,
+            max_entries: config.ai_cache_max_entries,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ttl_secs > 0 && self.max_entries > 0
+    }
+
+    pub fn key(provider: &str, model: &str, prompt_version: &str, messages: &[serde_json::Value]) -> String {
+        let normalized = messages
+            .iter()
+            .map(|message| {
+                let content = message.get("content").and_then(|content| content.as_str()).unwrap_or_default();
+                serde_json::json!([message.get("role"), content.split_whitespace().collect::<Vec<_>>().join(" ")])
+            })
+            .collect::<Vec<_>>();
+        let material = serde_json::json!([provider, model, prompt_version, normalized]).to_string();
+        format!("{:x}", Sha256::digest(material.as_bytes()))
+    }
+
+    pub async fn complete(&self, ai: &AiClient, prompt_version: &str, messages: &[serde_json::Value], call: &mut AiCall, accept: impl Fn(&str) -> bool) -> Result<Completion, AiError> {
+        if !call.cache || !self.is_enabled() || !ai.is_enabled() {
+            return ai.chat_completion(messages, call).await;
+        }
+        let model = call.model.clone().unwrap_or_else(|| ai.model().to_string());
+        let key = Self::key(ai.provider(), &model, prompt_version, messages);
+        if let Some(period) = &call.blocked {
+            return Err(AiError::BudgetExceeded(period.clone()));
+        }
+        if let Some(hit) = self.lookup(&key) {
+            call.cache_hits += 1;
+            return Ok(Completion {
+                content: hit.content,
+                model: hit.response_model,
+                usage: TokenUsage {
+                    model: model.clone(),
+                    prompt_tokens: hit.prompt_tokens,
+                    completion_tokens: hit.completion_tokens,
+                    cost_usd: 0.0,
+                    estimated: false,
+                },
+                cached: true,
+            });
+        }
+        let completion = ai.chat_completion(messages, call).await?;
+        if !accept(&completion.content) {
+            return Ok(completion);
+        }
+        self.store(CachedResponse {
+            cache_key: key,
+            provider: ai.provider().to_string(),
+            model,
+            prompt_version: prompt_version.to_string(),
+            content: completion.content.clone(),
+            response_model: completion.model.clone(),
+            prompt_tokens: completion.usage.prompt_tokens,
+            completion_tokens: completion.usage.completion_tokens,
+        });
+        Ok(completion)
+    }
+
+    fn lookup(&self, key: &str) -> Option<CachedResponse> {
+        let conn = self.db.lock().expect("db lock");
+        repository::get_cached_response(&conn, key, Utc::now().timestamp()).ok().flatten()
+    }
+
+    fn store(&self, entry: CachedResponse) {
+        let conn = self.db.lock().expect("db lock");
+        let now = Utc::now().timestamp();
+        let _ = repository::put_cached_response(&conn, &entry, now, now.saturating_add(self.ttl_secs as i64), self.max_entries);
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+
+use crate::models::search::SearchResult;
+use crate::utils::errors::ApiError;
+
+pub const SEARCH_TYPES: [&str; 5] = ["tool", "task", "user_note", "ai_note", "conversation"];
+pub const SEARCH_SORTS: [&str; 2] = ["relevance", "recent"];
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+const MAX_TERMS: usize = 12;
+
+pub struct SearchService;
+
+impl SearchService {
+    pub fn match_query(text: &str) -> Option<String> {
+        let mut terms: Vec<String> = Vec::new();
+        for word in text.split(|ch: char| !ch.is_alphanumeric()) {
+            let word = word.to_lowercase();
+            if word.is_empty() || terms.contains(&word) {
+                continue;
+            }
+            terms.push(word);
+        }
+        if terms.is_empty() {
+            return None;
+        }
+        Some(terms.iter().take(MAX_TERMS).map(|term| format!("\"{}\"*", term)).collect::<Vec<_>>().join(" AND "))
+    }
+
+    pub fn parse_types(types: Option<&str>) -> Result<Vec<&'static str>, ApiError> {
+        let Some(types) = types.map(str::trim).filter(|types| !types.is_empty()) else {
+            return Ok(SEARCH_TYPES.to_vec());
+        };
+        let mut selected = Vec::new();
+        for name in types.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let Some(known) = SEARCH_TYPES.iter().find(|known| **known == name) else {
+                return Err(ApiError::validation(format!("Unknown search type {}; expected one of {}", name, SEARCH_TYPES.join(", "))));
+            };
+            if !selected.contains(known) {
+                selected.push(*known);
+            }
+        }
+        Ok(selected)
+    }
+
+    pub fn highlight(snippet: &str) -> String {
+        let mut html = String::with_capacity(snippet.len());
+        for ch in snippet.chars() {
+            match ch {
+                MATCH_START => html.push_str("<mark>"),
+                MATCH_END => html.push_str("</mark>"),
+                '&' => html.push_str("&amp;"),
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                '"' => html.push_str("&quot;"),
+                '\'' => html.push_str("&#39;"),
+                _ => html.push(ch),
+            }
+        }
+        html
+    }
+
+    pub fn rank(mut hits: Vec<SearchResult>, sort: &str) -> Vec<SearchResult> {
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let mut seen = HashSet::new();
+        let mut results: Vec<SearchResult> = hits.into_iter().filter(|hit| seen.insert((hit.result_type.clone(), hit.id.clone()))).collect();
+        if sort == "recent" {
+            results.sort_by(|a, b| b.date.cmp(&a.date).then(b.score.total_cmp(&a.score)));
+        }
+        results
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::models::ai::Suggestion;
+use crate::models::plan::{StoredTask, parse_date};
+
+const BREAK_THRESHOLD_MINUTES: i64 = 50;
+const SESSION_GAP_MINUTES: i64 = 10;
+const OVERRUN_RATIO: f64 = 1.25;
+
+pub struct SuggestionService;
+
+impl SuggestionService {
+    pub fn analyze(tasks: &[StoredTask], data: Option<&serde_json::Value>, now: DateTime<Utc>) -> Vec<Suggestion> {
+        let today = now.date_naive();
+        let mut suggestions = Vec::new();
+        suggestions.extend(Self::overdue(tasks, today));
+        suggestions.extend(Self::skipped_streak(tasks, today));
+        suggestions.extend(Self::overruns(tasks));
+        suggestions.extend(Self::subjects_behind(tasks, today));
+        suggestions.extend(Self::break_needed(tasks, data, now));
+
+        if let Some(current) = data
+            .and_then(|data| data.get("current_task_id"))
+            .and_then(|value| value.as_str())
+            .and_then(|value| Uuid::parse_str(value).ok())
+        {
+            for suggestion in suggestions.iter_mut().filter(|suggestion| suggestion.related_task_ids.contains(&current)) {
+                suggestion.score = (suggestion.score + 10.0).min(100.0);
+                suggestion.priority = priority_for(suggestion.score);
+            }
+        }
+
+        if suggestions.is_empty() {
+            suggestions.push(suggestion("on_track", "You're on track. Keep going with today's plan.".to_string(), 10.0, None, Vec::new()));
+        }
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions
+    }
+
+    fn overdue(tasks: &[StoredTask], today: NaiveDate) -> Option<Suggestion> {
+        let mut overdue = tasks
+            .iter()
+            .filter(|task| task.status != "completed")
+            .filter_map(|task| parse_date(&task.task.due_date).ok().filter(|due| *due < today).map(|due| (due, task)))
+            .collect::<Vec<_>>();
+        if overdue.is_empty() {
+            return None;
+        }
+        overdue.sort_by_key(|(due, _)| *due);
+        let (oldest_due, oldest) = overdue[0];
+        let count = overdue.len();
+        let days = (today - oldest_due).num_days();
+        let score = 40.0 + 10.0 * count as f64 + 2.0 * days as f64;
+        Some(suggestion(
+            "overdue_tasks",
+            format!(
+                "You have {} overdue task{}. Start with {}: {} (due {} day{} ago).",
+                count,
+                plural(count as i64),
+                oldest.task.subject,
+                oldest.task.topic,
+                days,
+                plural(days)
+            ),
+            score,
+            Some(oldest.task.subject.clone()),
+            overdue.iter().map(|(_, task)| task.task.id).collect(),
+        ))
+    }
+
+    fn skipped_streak(tasks: &[StoredTask], today: NaiveDate) -> Option<Suggestion> {
+        let mut resolved = tasks
+            .iter()
+            .filter(|task| task.status == "completed" || task.status == "skipped")
+            .filter(|task| parse_date(&task.plan_date).map(|date| date <= today).unwrap_or(false))
+            .collect::<Vec<_>>();
+        resolved.sort_by(|a, b| (&a.plan_date, &a.task.start_time).cmp(&(&b.plan_date, &b.task.start_time)));
+        let streak = resolved
+            .iter()
+            .rev()
+            .take_while(|task| task.status == "skipped")
+            .collect::<Vec<_>>();
+        if streak.len() < 2 {
+            return None;
+        }
+        let score = 30.0 + 15.0 * streak.len() as f64;
+        Some(suggestion(
+            "skipped_streak",
+            format!(
+                "You've skipped your last {} tasks in a row. Try a shorter 20-minute session to rebuild momentum.",
+                streak.len()
+            ),
+            score,
+            streak.last().map(|task| task.task.subject.clone()),
+            streak.iter().map(|task| task.task.id).collect(),
+        ))
+    }
+
+    fn overruns(tasks: &[StoredTask]) -> Vec<Suggestion> {
+        let mut by_subject: BTreeMap<&str, Vec<&StoredTask>> = BTreeMap::new();
+        for task in tasks.iter().filter(|task| task.status == "completed" && task.task.duration_minutes > 0) {
+            if task.actual_duration.is_some() {
+                by_subject.entry(task.task.subject.as_str()).or_default().push(task);
+            }
+        }
+        by_subject
+            .into_iter()
+            .filter(|(_, tasks)| tasks.len() >= 2)
+            .filter_map(|(subject, tasks)| {
+                let planned: u32 = tasks.iter().map(|task| task.task.duration_minutes).sum();
+                let actual: u32 = tasks.iter().filter_map(|task| task.actual_duration).sum();
+                let ratio = actual as f64 / planned as f64;
+                if ratio < OVERRUN_RATIO {
+                    return None;
+                }
+                let percent = ((ratio - 1.0) * 100.0).round();
+                Some(suggestion(
+                    "duration_overrun",
+                    format!(
+                        "{} tasks are taking {}% longer than planned. Consider allocating more time to {} in your next plan.",
+                        subject, percent, subject
+                    ),
+                    (30.0 + (ratio - 1.0) * 50.0).min(90.0),
+                    Some(subject.to_string()),
+                    tasks
+                        .iter()
+                        .filter(|task| task.actual_duration.unwrap_or(0) > task.task.duration_minutes)
+                        .map(|task| task.task.id)
+                        .collect(),
+                ))
+            })
+            .collect()
+    }
+
+    fn subjects_behind(tasks: &[StoredTask], today: NaiveDate) -> Vec<Suggestion> {
+        let mut by_subject: BTreeMap<&str, (u32, u32, Vec<Uuid>)> = BTreeMap::new();
+        for task in tasks {
+            if !parse_date(&task.plan_date).map(|date| date <= today).unwrap_or(false) {
+                continue;
+            }
+            let entry = by_subject.entry(task.task.subject.as_str()).or_default();
+            entry.0 += 1;
+            if task.status == "completed" {
+                entry.1 += 1;
+            } else {
+                entry.2.push(task.task.id);
+            }
+        }
+        let (due, done) = by_subject
+            .values()
+            .fold((0, 0), |(due, done), (subject_due, subject_done, _)| (due + subject_due, done + subject_done));
+        if due == 0 || by_subject.len() < 2 {
+            return Vec::new();
+        }
+        let overall = done as f64 / due as f64;
+        by_subject
+            .into_iter()
+            .filter(|(_, (due, _, _))| *due >= 2)
+            .filter_map(|(subject, (due, done, pending))| {
+                let rate = done as f64 / due as f64;
+                if rate >= 0.5 || rate > overall - 0.2 {
+                    return None;
+                }
+                Some(suggestion(
+                    "subject_behind",
+                    format!(
+                        "{} is falling behind: {} of {} scheduled tasks done. Prioritise it in your next session.",
+                        subject, done, due
+                    ),
+                    35.0 + (1.0 - rate) * 40.0,
+                    Some(subject.to_string()),
+                    pending,
+                ))
+            })
+            .collect()
+    }
+
+    fn break_needed(tasks: &[StoredTask], data: Option<&serde_json::Value>, now: DateTime<Utc>) -> Option<Suggestion> {
+        let reported = data.and_then(|data| {
+            ["minutes_since_break", "time_spent"]
+                .iter()
+                .find_map(|key| data.get(*key).and_then(|value| value.as_i64()))
+        });
+        let minutes = reported.unwrap_or_else(|| Self::continuous_study_minutes(tasks, now));
+        if minutes < BREAK_THRESHOLD_MINUTES {
+            return None;
+        }
+        let score = 20.0 + (minutes - BREAK_THRESHOLD_MINUTES) as f64 * 0.5;
+        let rest = if minutes >= 90 { 15 } else { 10 };
+        Some(suggestion(
+            "take_break",
+            format!("You've been studying for {} minutes without a break. Take a {}-minute break.", minutes, rest),
+            score.min(80.0),
+            None,
+            Vec::new(),
+        ))
+    }
+
+    fn continuous_study_minutes(tasks: &[StoredTask], now: DateTime<Utc>) -> i64 {
+        let mut sessions = tasks
+            .iter()
+            .filter_map(|task| {
+                let end = task.completed_at?;
+                let minutes = task.actual_duration.unwrap_or(task.task.duration_minutes) as i64;
+                Some((end - Duration::minutes(minutes), end))
+            })
+            .filter(|(_, end)| *end <= now)
+            .collect::<Vec<_>>();
+        sessions.sort_by_key(|(_, end)| std::cmp::Reverse(*end));
+        let mut boundary = now;
+        let mut total = 0;
+        for (start, end) in sessions {
+            if (boundary - end).num_minutes() > SESSION_GAP_MINUTES {
+                break;
+            }
+            total += (end - start).num_minutes();
+            boundary = start;
+        }
+        total
+    }
+}
+
+pub fn priority_for(score: f64) -> String {
+    if score >= 70.0 {
+        "high"
+    } else if score >= 40.0 {
+        "medium"
+    } else {
+        "low"
+    }
+    .to_string()
+}
+
+fn suggestion(suggestion_type: &str, message: String, score: f64, subject: Option<String>, related_task_ids: Vec<Uuid>) -> Suggestion {
+    let score = score.clamp(0.0, 100.0);
+    Suggestion {
+        suggestion_type: suggestion_type.to_string(),
+        message,
+        priority: priority_for(score),
+        score,
+        subject,
+        related_task_ids,
+    }
+}
+
+fn plural(count: i64) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+use std::time::SystemTime;
+
+use actix_web::http::StatusCode;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::utils::errors::ApiError;
+
+const BUILTIN_TEMPLATES: [(&str, &str); 4] = [
+    (include_str!("../../templates/calculator.json"), include_str!("../../templates/calculator_template.tsx")),
+    (include_str!("../../templates/timer.json"), include_str!("../../templates/timer_template.tsx")),
+    (include_str!("../../templates/flashcard.json"), include_str!("../../templates/flashcard_template.tsx")),
+    (include_str!("../../templates/custom.json"), include_str!("../../templates/custom_template.tsx")),
+];
+
+static IDENTIFIER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-z][a-z0-9_]{0,39}$").expect("identifier pattern"));
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TemplateError {
+    #[error("tool_type must be one of {}", .0.join(", "))]
+    UnknownType(Vec<String>),
+    #[error("Template '{template}' does not exist for {tool_type} tools")]
+    UnknownTemplate { template: String, tool_type: String },
+    #[error("The tool parameters do not match the template schema: {}", .0.join("; "))]
+    InvalidParameters(Vec<String>),
+}
+
+impl TemplateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnknownType(_) | Self::UnknownTemplate { .. } => "VALIDATION_ERROR",
+            Self::InvalidParameters(_) => "INVALID_TOOL_PARAMETERS",
+        }
+    }
+}
+
+impl From<TemplateError> for ApiError {
+    fn from(error: TemplateError) -> Self {
+        let api = ApiError::new(StatusCode::BAD_REQUEST, error.code(), error.to_string());
+        match error {
+            TemplateError::InvalidParameters(errors) => api.with_details(serde_json::json!({"errors": errors})),
+            _ => api,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TemplateManifest {
+    name: String,
+    tool_type: String,
+    version: String,
+    #[serde(default)]
+    description: String,
+    template: Option<String>,
+    #[serde(default = "empty_schema")]
+    parameters: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolTemplate {
+    pub name: String,
+    pub tool_type: String,
+    pub version: String,
+    pub description: String,
+    pub source: String,
+    pub parameters: Value,
+    #[serde(skip)]
+    pub code: String,
+}
+
+impl ToolTemplate {
+    fn parse(manifest: &str, source: &str, load_code: impl FnOnce(Option<&str>) -> Result<String, String>) -> Result<Self, String> {
+        let manifest: TemplateManifest = serde_json::from_str(manifest).map_err(|err| format!("{}: {}", source, err))?;
+        for (field, value) in [("name", &manifest.name), ("tool_type", &manifest.tool_type)] {
+            if !IDENTIFIER.is_match(value) {
+                return Err(format!("{}: {} must be lowercase letters, digits and underscores", source, field));
+            }
+        }
+        if manifest.version.trim().is_empty() {
+            return Err(format!("{}: version must not be empty", source));
+        }
+        if manifest.parameters.get("type").and_then(Value::as_str) != Some("object") {
+            return Err(format!("{}: parameters must be an object schema", source));
+        }
+        let code = load_code(manifest.template.as_deref())?;
+        if code.trim().is_empty() {
+            return Err(format!("{}: template file is empty", source));
+        }
+        Ok(Self {
+            name: manifest.name,
+            tool_type: manifest.tool_type,
+            version: manifest.version.trim().to_string(),
+            description: manifest.description,
+            source: source.to_string(),
+            parameters: manifest.parameters,
+            code,
+        })
+    }
+
+    pub fn id(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+
+    pub fn resolve_parameters(&self, provided: Option<&Value>) -> Result<Map<String, Value>, TemplateError> {
+        let mut values = self
+            .parameters
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|properties| {
+                properties
+                    .iter()
+                    .filter_map(|(key, schema)| schema.get("default").map(|default| (key.clone(), default.clone())))
+                    .collect::<Map<_, _>>()
+            })
+            .unwrap_or_default();
+        match provided {
+            None | Some(Value::Null) => {}
+            Some(Value::Object(provided)) => values.extend(provided.clone()),
+            Some(_) => return Err(TemplateError::InvalidParameters(vec!["parameters must be an object".to_string()])),
+        }
+        let values = Value::Object(values);
+        let mut errors = Vec::new();
+        check_schema(&self.parameters, &values, "parameters", &mut errors);
+        if !errors.is_empty() {
+            return Err(TemplateError::InvalidParameters(errors));
+        }
+        let Value::Object(values) = values else { unreachable!() };
+        Ok(values)
+    }
+}
+
+pub struct TemplateRegistry {
+    dir: PathBuf,
+    templates: RwLock<BTreeMap<String, ToolTemplate>>,
+    fingerprint: RwLock<Vec<(PathBuf, Option<SystemTime>, u64)>>,
+}
+
+impl TemplateRegistry {
+    pub fn load(data_dir: &str) -> Self {
+        let registry = Self {
+            dir: Path::new(data_dir).join("templates"),
+            templates: RwLock::new(BTreeMap::new()),
+            fingerprint: RwLock::new(Vec::new()),
+        };
+        registry.reload();
+        registry
+    }
+
+    pub fn reload(&self) -> Vec<String> {
+        let fingerprint = self.scan();
+        let mut templates = BTreeMap::new();
+        for (manifest, code) in BUILTIN_TEMPLATES {
+            if let Ok(template) = ToolTemplate::parse(manifest, "builtin", |_| Ok(code.to_string())) {
+                templates.insert(template.name.clone(), template);
+            }
+        }
+        let mut errors = Vec::new();
+        for (path, _, _) in &fingerprint {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let source = path.to_string_lossy();
+            let parsed = fs::read_to_string(path)
+                .map_err(|err| format!("{}: {}", source, err))
+                .and_then(|manifest| {
+                    ToolTemplate::parse(&manifest, &source, |file| {
+                        let code_path = match file {
+                            Some(file) => self.dir.join(file),
+                            None => path.with_extension("tsx"),
+                        };
+                        if !code_path.starts_with(&self.dir) || code_path.components().any(|part| part == std::path::Component::ParentDir) {
+                            return Err(format!("{}: template must be inside {}", source, self.dir.display()));
+                        }
+                        fs::read_to_string(&code_path).map_err(|err| format!("{}: {}", code_path.display(), err))
+                    })
+                });
+            match parsed {
+                Ok(template) => {
+                    templates.insert(template.name.clone(), template);
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+        *self.templates.write().expect("template lock") = templates;
+        *self.fingerprint.write().expect("template lock") = fingerprint;
+        errors
+    }
+
+    pub fn refresh(&self) {
+        let current = self.scan();
+        if *self.fingerprint.read().expect("template lock") != current {
+            self.reload();
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<ToolTemplate> {
+        self.refresh();
+        self.templates.read().expect("template lock").get(name).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ToolTemplate> {
+        self.refresh();
+        self.templates.read().expect("template lock").values().cloned().collect()
+    }
+
+    pub fn tool_types(&self) -> Vec<String> {
+        let mut types = self.list().into_iter().map(|template| template.tool_type).collect::<Vec<_>>();
+        types.sort();
+        types.dedup();
+        types
+    }
+
+    pub fn select(&self, tool_type: &str, name: Option<&str>) -> Result<ToolTemplate, TemplateError> {
+        let templates = self.list();
+        if let Some(name) = name {
+            return templates
+                .into_iter()
+                .find(|template| template.name == name && template.tool_type == tool_type)
+                .ok_or_else(|| TemplateError::UnknownTemplate {
+                    template: name.to_string(),
+                    tool_type: tool_type.to_string(),
+                });
+        }
+        let mut candidates = templates.iter().filter(|template| template.tool_type == tool_type).peekable();
+        if candidates.peek().is_none() {
+            return Err(TemplateError::UnknownType(self.tool_types()));
+        }
+        let candidates = candidates.collect::<Vec<_>>();
+        let chosen = candidates.iter().find(|template| template.name == tool_type).unwrap_or(&candidates[0]);
+        Ok((*chosen).clone())
+    }
+
+    fn scan(&self) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+        let mut files = fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+                        Some((entry.path(), metadata.modified().ok(), metadata.len()))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+}
+
+fn empty_schema() -> Value {
+    serde_json::json!({"type": "object", "properties": {}})
+}
+
+fn check_schema(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(options) = schema.get("enum").and_then(Value::as_array)
+        && !options.contains(value)
+    {
+        errors.push(format!("{} must be one of {}", path, Value::Array(options.clone())));
+        return;
+    }
+    let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+    match schema.get("type").and_then(Value::as_str) {
+        Some("object") => {
+            let Some(object) = value.as_object() else {
+                errors.push(format!("{} must be an object", path));
+                return;
+            };
+            for key in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    errors.push(format!("{}.{} is required", path, key));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in object {
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property) => check_schema(property, item, &format!("{}.{}", path, key), errors),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => errors.push(format!("{}.{} is not allowed", path, key)),
+                    None => {}
+                }
+            }
+        }
+        Some("array") => {
+            let Some(items) = value.as_array() else {
+                errors.push(format!("{} must be an array", path));
+                return;
+            };
+            let count = items.len() as f64;
+            if bound("minItems").is_some_and(|min| count < min) || bound("maxItems").is_some_and(|max| count > max) {
+                errors.push(format!("{} has {} items, outside the allowed range", path, items.len()));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check_schema(item_schema, item, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        Some("string") => {
+            let Some(text) = value.as_str() else {
+                errors.push(format!("{} must be a string", path));
+                return;
+            };
+            let length = text.chars().count() as f64;
+            if bound("minLength").is_some_and(|min| length < min) || bound("maxLength").is_some_and(|max| length > max) {
+                errors.push(format!("{} has {} characters, outside the allowed range", path, text.chars().count()));
+            }
+        }
+        Some(kind @ ("integer" | "number")) => {
+            let Some(number) = value.as_f64().filter(|number| kind == "number" || number.fract() == 0.0) else {
+                errors.push(format!("{} must be {}", path, if kind == "integer" { "an integer" } else { "a number" }));
+                return;
+            };
+            if bound("minimum").is_some_and(|min| number < min) || bound("maximum").is_some_and(|max| number > max) {
+                errors.push(format!("{} is {}, outside the allowed range", path, number));
+            }
+        }
+        Some("boolean") if !value.is_boolean() => errors.push(format!("{} must be a boolean", path)),
+        _ => {}
+    }
+}
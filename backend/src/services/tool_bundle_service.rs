@@ -0,0 +1,200 @@
+use actix_web::http::StatusCode;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+use crate::models::tool::{BundleSignature, BundledState, BundledTool, BundledVersion, Tool, ToolBundle, ToolBundleContent, ToolStateEntry, ToolVersion};
+use crate::utils::errors::ApiError;
+
+pub const BUNDLE_FORMAT: &str = "studyplanner.tool-bundle";
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+const SIGNATURE_ALGORITHM: &str = "ed25519";
+const MAX_NAME_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BundleError {
+    #[error("Not a tool bundle: {0}")]
+    Invalid(String),
+    #[error("The bundle content does not match its content_hash")]
+    HashMismatch { expected: String, actual: String },
+    #[error("The bundle signature does not verify against its public key")]
+    BadSignature,
+    #[error("TOOL_SIGNING_KEY must be a base64-encoded 32-byte Ed25519 seed")]
+    SigningKey,
+}
+
+impl BundleError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Invalid(_) => "INVALID_TOOL_BUNDLE",
+            Self::HashMismatch { .. } => "BUNDLE_HASH_MISMATCH",
+            Self::BadSignature => "BUNDLE_SIGNATURE_INVALID",
+            Self::SigningKey => "SIGNING_KEY_INVALID",
+        }
+    }
+}
+
+impl From<BundleError> for ApiError {
+    fn from(error: BundleError) -> Self {
+        let status = match error {
+            BundleError::SigningKey => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let api = ApiError::new(status, error.code(), error.to_string());
+        match error {
+            BundleError::HashMismatch { expected, actual } => api.with_details(serde_json::json!({"expected": expected, "actual": actual})),
+            _ => api,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleVerification {
+    pub content_hash: String,
+    pub signer_public_key: Option<String>,
+    pub trusted: bool,
+}
+
+pub struct ToolBundleService;
+
+impl ToolBundleService {
+    pub fn export(tool: &Tool, head: &ToolVersion, state: Option<&[ToolStateEntry]>, signing_key: Option<&str>, now: DateTime<Utc>) -> Result<ToolBundle, BundleError> {
+        let content = ToolBundleContent {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: now,
+            source_tool_id: tool.tool_id,
+            tool: BundledTool {
+                name: tool.name.clone(),
+                tool_type: tool.tool_type.clone(),
+                description: tool.description.clone(),
+                component_code: tool.component_code.clone(),
+                metadata: tool.metadata.clone(),
+            },
+            head: BundledVersion {
+                version: head.version.clone(),
+                changes_summary: head.changes_summary.clone(),
+                change_type: head.change_type.clone(),
+                ai_model: head.ai_model.clone(),
+                prompt_version: head.prompt_version.clone(),
+                created_at: head.created_at,
+            },
+            state: state.map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| BundledState {
+                        key: entry.key.clone(),
+                        value: entry.value.clone(),
+                    })
+                    .collect()
+            }),
+        };
+        let content_hash = Self::content_hash(&serde_json::to_value(&content).map_err(|error| BundleError::Invalid(error.to_string()))?);
+        let signature = signing_key
+            .map(|key| {
+                let key = Self::signing_key(key)?;
+                Ok::<_, BundleError>(BundleSignature {
+                    algorithm: SIGNATURE_ALGORITHM.to_string(),
+                    public_key: STANDARD.encode(key.verifying_key().as_bytes()),
+                    value: STANDARD.encode(key.sign(content_hash.as_bytes()).to_bytes()),
+                })
+            })
+            .transpose()?;
+        Ok(ToolBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            content,
+            content_hash,
+            signature,
+        })
+    }
+
+    pub fn content_hash(content: &serde_json::Value) -> String {
+        let json = serde_json::to_vec(&canonical(content)).unwrap_or_default();
+        format!("{:x}", Sha256::digest(&json))
+    }
+
+    pub fn verify(received: &serde_json::Value, trusted_keys: &[String]) -> Result<(ToolBundle, BundleVerification), BundleError> {
+        let bundle: ToolBundle = serde_json::from_value(received.clone()).map_err(|error| BundleError::Invalid(error.to_string()))?;
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(BundleError::Invalid(format!("format must be \"{}\"", BUNDLE_FORMAT)));
+        }
+        if bundle.content.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::Invalid(format!("format_version {} is not supported", bundle.content.format_version)));
+        }
+        let content = &bundle.content;
+        if content.tool.name.trim().is_empty() || content.tool.name.chars().count() > MAX_NAME_LENGTH {
+            return Err(BundleError::Invalid(format!("tool name must be 1 to {} characters", MAX_NAME_LENGTH)));
+        }
+        let tool_type = &content.tool.tool_type;
+        if tool_type.is_empty() || tool_type.len() > 40 || !tool_type.chars().all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_') {
+            return Err(BundleError::Invalid("tool_type must be lowercase letters, digits and underscores".to_string()));
+        }
+        if content.head.version.trim().is_empty() || content.head.version.len() > 32 {
+            return Err(BundleError::Invalid("head.version must be 1 to 32 characters".to_string()));
+        }
+        let actual = Self::content_hash(&received["content"]);
+        if !actual.eq_ignore_ascii_case(&bundle.content_hash) {
+            return Err(BundleError::HashMismatch {
+                expected: bundle.content_hash.clone(),
+                actual,
+            });
+        }
+        let Some(signature) = &bundle.signature else {
+            let verification = BundleVerification {
+                content_hash: actual,
+                signer_public_key: None,
+                trusted: false,
+            };
+            return Ok((bundle, verification));
+        };
+        if signature.algorithm != SIGNATURE_ALGORITHM {
+            return Err(BundleError::Invalid(format!("signature algorithm must be \"{}\"", SIGNATURE_ALGORITHM)));
+        }
+        let public_key = STANDARD
+            .decode(signature.public_key.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+            .ok_or(BundleError::BadSignature)?;
+        let value = STANDARD
+            .decode(signature.value.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .map(|bytes| Signature::from_bytes(&bytes))
+            .ok_or(BundleError::BadSignature)?;
+        public_key.verify(actual.as_bytes(), &value).map_err(|_| BundleError::BadSignature)?;
+        let signer = STANDARD.encode(public_key.as_bytes());
+        let verification = BundleVerification {
+            trusted: trusted_keys.iter().any(|key| key.trim() == signer),
+            content_hash: actual,
+            signer_public_key: Some(signer),
+        };
+        Ok((bundle, verification))
+    }
+
+    pub fn public_key(signing_key: &str) -> Result<String, BundleError> {
+        Ok(STANDARD.encode(Self::signing_key(signing_key)?.verifying_key().as_bytes()))
+    }
+
+    fn signing_key(seed: &str) -> Result<SigningKey, BundleError> {
+        STANDARD
+            .decode(seed.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(|bytes| SigningKey::from_bytes(&bytes))
+            .ok_or(BundleError::SigningKey)
+    }
+}
+
+fn canonical(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            serde_json::Value::Object(entries.into_iter().map(|(key, value)| (key.clone(), canonical(value))).collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
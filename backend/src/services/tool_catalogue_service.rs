@@ -0,0 +1,75 @@
+use actix_web::http::StatusCode;
+use uuid::Uuid;
+
+use crate::utils::errors::ApiError;
+
+pub const CATALOGUE_SORTS: [&str; 3] = ["recent", "rating", "forks"];
+const MAX_SUBJECT_LENGTH: usize = 100;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ForkError {
+    #[error("Tool {0} is not in the catalogue")]
+    NotPublished(Uuid),
+    #[error("Tool {0} is not a fork")]
+    NotAFork(Uuid),
+    #[error("The upstream tool is no longer available")]
+    UpstreamGone,
+    #[error("Already up to date with upstream version {0}")]
+    UpToDate(String),
+    #[error("Upstream version {upstream_version} conflicts with changes made in the fork")]
+    Conflict { upstream_version: String, merged: String },
+}
+
+impl ForkError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotPublished(_) => "TOOL_NOT_PUBLISHED",
+            Self::NotAFork(_) => "NOT_A_FORK",
+            Self::UpstreamGone => "UPSTREAM_UNAVAILABLE",
+            Self::UpToDate(_) => "FORK_UP_TO_DATE",
+            Self::Conflict { .. } => "MERGE_CONFLICT",
+        }
+    }
+}
+
+impl From<ForkError> for ApiError {
+    fn from(error: ForkError) -> Self {
+        let status = match error {
+            ForkError::NotPublished(_) | ForkError::NotAFork(_) => StatusCode::NOT_FOUND,
+            ForkError::UpstreamGone => StatusCode::GONE,
+            ForkError::UpToDate(_) | ForkError::Conflict { .. } => StatusCode::CONFLICT,
+        };
+        let api = ApiError::new(status, error.code(), error.to_string());
+        match error {
+            ForkError::Conflict { upstream_version, merged } => api.with_details(serde_json::json!({"upstream_version": upstream_version, "conflicted_code": merged})),
+            _ => api,
+        }
+    }
+}
+
+pub struct ToolCatalogueService;
+
+impl ToolCatalogueService {
+    pub fn normalize_subject(subject: Option<&str>) -> Result<Option<String>, ApiError> {
+        let subject = subject.map(str::trim).filter(|subject| !subject.is_empty());
+        if subject.is_some_and(|subject| subject.chars().count() > MAX_SUBJECT_LENGTH) {
+            return Err(ApiError::validation(format!("subject must be at most {} characters", MAX_SUBJECT_LENGTH)));
+        }
+        Ok(subject.map(str::to_string))
+    }
+
+    pub fn validate_rating(rating: u8) -> Result<(), ApiError> {
+        if (1..=5).contains(&rating) {
+            Ok(())
+        } else {
+            Err(ApiError::validation("rating must be between 1 and 5"))
+        }
+    }
+
+    pub fn merge_upstream(base: &str, fork: &str, upstream: &str, upstream_version: &str) -> Result<String, ForkError> {
+        diffy::merge(base, fork, upstream).map_err(|merged| ForkError::Conflict {
+            upstream_version: upstream_version.to_string(),
+            merged,
+        })
+    }
+}
@@ -0,0 +1,232 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use actix_web::web::Bytes;
+use oxc_allocator::Allocator;
+use oxc_ast::AstBuilder;
+use oxc_ast::NONE;
+use oxc_ast::ast::{
+    ClassType, ExportDefaultDeclarationKind, Expression, FunctionType, ImportDeclaration, ImportDeclarationSpecifier, Statement,
+    VariableDeclarationKind,
+};
+use oxc_codegen::Codegen;
+use oxc_parser::Parser;
+use oxc_semantic::SemanticBuilder;
+use oxc_span::{SPAN, SourceType};
+use oxc_transformer::{JsxOptions, JsxRuntime, TransformOptions, Transformer};
+use sha2::{Digest, Sha256};
+
+use crate::models::tool::{Tool, ToolDiagnostic};
+
+const BOOTSTRAP_PRELUDE: &str = r#"const __modules = (window.StudyToolRuntime && window.StudyToolRuntime.modules) || {};
+const __require = (source) => {
+  if (source in __modules) return __modules[source];
+  const slash = source.lastIndexOf("/");
+  const parent = slash > 0 ? __modules[source.slice(0, slash)] : undefined;
+  const name = source.slice(slash + 1);
+  if (parent && name in parent) return { default: parent[name] };
+  throw new Error("Module is not available in the preview runtime: " + source);
+};
+const __default = (mod) => (mod && mod.default !== undefined ? mod.default : mod);
+const __h = __require("react").createElement;
+const __Fragment = __require("react").Fragment;"#;
+
+pub struct ToolPreview;
+
+impl ToolPreview {
+    pub fn transpile(code: &str) -> Result<String, Vec<ToolDiagnostic>> {
+        let allocator = Allocator::default();
+        let parsed = Parser::new(&allocator, code, SourceType::tsx()).parse();
+        if parsed.panicked || !parsed.errors.is_empty() {
+            return Err(parsed
+                .errors
+                .iter()
+                .map(|error| ToolDiagnostic::error("syntax_error", error.to_string()))
+                .collect());
+        }
+        let mut program = parsed.program;
+        if !program.body.iter().any(|statement| matches!(statement, Statement::ExportDefaultDeclaration(_))) {
+            return Err(vec![ToolDiagnostic::error("missing_default_export", "The component has no default export to render")]);
+        }
+
+        let scoping = SemanticBuilder::new().build(&program).semantic.into_scoping();
+        let options = TransformOptions {
+            jsx: JsxOptions {
+                runtime: JsxRuntime::Classic,
+                pragma: Some("__h".to_string()),
+                pragma_frag: Some("__Fragment".to_string()),
+                display_name_plugin: false,
+                pure: false,
+                ..JsxOptions::default()
+            },
+            ..TransformOptions::default()
+        };
+        let transformed = Transformer::new(&allocator, Path::new("tool.tsx"), &options).build_with_scoping(scoping, &mut program);
+        if !transformed.errors.is_empty() {
+            return Err(transformed
+                .errors
+                .iter()
+                .map(|error| ToolDiagnostic::error("unsupported_syntax", error.to_string()))
+                .collect());
+        }
+
+        let ast = AstBuilder::new(&allocator);
+        let mut imports = Vec::new();
+        let mut tool = None;
+        let statements = std::mem::replace(&mut program.body, ast.vec());
+        for statement in statements {
+            match statement {
+                Statement::ImportDeclaration(import) => imports.push(require_import(&import)),
+                Statement::ExportNamedDeclaration(export) => {
+                    if let Some(declaration) = export.unbox().declaration {
+                        program.body.push(Statement::from(declaration));
+                    }
+                }
+                Statement::ExportDefaultDeclaration(export) => {
+                    let (statement, name) = default_export(&ast, export.unbox().declaration);
+                    program.body.extend(statement);
+                    tool = name;
+                }
+                Statement::ExportAllDeclaration(_) => {}
+                statement => program.body.push(statement),
+            }
+        }
+        let script = Codegen::new().build(&program).code;
+        let tool = tool.map(|name| format!("const __tool = {};\n", name)).unwrap_or_default();
+        Ok(format!("{}\n{}{}", imports.join("\n"), script, tool))
+    }
+
+    pub fn render_page(tool: &Tool, script: &str, runtime_digest: &str, nonce: &str) -> String {
+        let bootstrap = format!(
+            "(function () {{\n\"use strict\";\nconst __root = document.getElementById(\"root\");\ntry {{\n{}\n{}\n__require(\"react-dom/client\").createRoot(__root).render(__h(__tool));\n}} catch (error) {{\n__root.textContent = \"Preview failed: \" + (error && error.message ? error.message : error);\n}}\n}})();",
+            BOOTSTRAP_PRELUDE, script
+        );
+        format!(
+            "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<meta name=\"referrer\" content=\"no-referrer\">\n<title>{}</title>\n<style>html, body {{ margin: 0; }} body {{ font-family: system-ui, sans-serif; }}</style>\n</head>\n<body>\n<div id=\"root\"></div>\n<script nonce=\"{}\" src=\"{}?v={}\"></script>\n<script nonce=\"{}\">{}</script>\n</body>\n</html>\n",
+            escape_html(&tool.name),
+            nonce,
+            RUNTIME_URL,
+            runtime_digest,
+            nonce,
+            inline_script(&bootstrap)
+        )
+    }
+
+    pub fn content_security_policy(nonce: &str) -> String {
+        format!(
+            "default-src 'none'; script-src 'nonce-{}'; style-src 'unsafe-inline'; img-src data: blob:; font-src data:; media-src data: blob:; connect-src 'none'; base-uri 'none'; form-action 'none'; sandbox allow-scripts",
+            nonce
+        )
+    }
+}
+
+pub const RUNTIME_URL: &str = "/tools/preview/runtime.js";
+
+pub struct RuntimeBundle {
+    pub source: Bytes,
+    pub digest: String,
+    fingerprint: (Option<SystemTime>, u64),
+}
+
+pub struct PreviewRuntime {
+    path: PathBuf,
+    loaded: RwLock<Option<Arc<RuntimeBundle>>>,
+}
+
+impl PreviewRuntime {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            loaded: RwLock::new(None),
+        }
+    }
+
+    pub fn current(&self) -> Option<Arc<RuntimeBundle>> {
+        let metadata = std::fs::metadata(&self.path).ok()?;
+        let fingerprint = (metadata.modified().ok(), metadata.len());
+        if let Some(bundle) = self.loaded.read().expect("runtime lock").as_ref()
+            && bundle.fingerprint == fingerprint
+        {
+            return Some(bundle.clone());
+        }
+        let source = std::fs::read(&self.path).ok()?;
+        let digest = Sha256::digest(&source).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let bundle = Arc::new(RuntimeBundle {
+            source: Bytes::from(source),
+            digest,
+            fingerprint,
+        });
+        *self.loaded.write().expect("runtime lock") = Some(bundle.clone());
+        Some(bundle)
+    }
+}
+
+fn inline_script(code: &str) -> String {
+    code.replace("</", "<\\/")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn require_import(import: &ImportDeclaration<'_>) -> String {
+    let source = json_string(&import.source.value);
+    let mut lines = Vec::new();
+    let mut named = Vec::new();
+    for specifier in import.specifiers.iter().flatten() {
+        match specifier {
+            ImportDeclarationSpecifier::ImportDefaultSpecifier(default) => {
+                lines.push(format!("const {} = __default(__require({}));", default.local.name, source));
+            }
+            ImportDeclarationSpecifier::ImportNamespaceSpecifier(namespace) => {
+                lines.push(format!("const {} = __require({});", namespace.local.name, source));
+            }
+            ImportDeclarationSpecifier::ImportSpecifier(specifier) => {
+                let imported = specifier.imported.name();
+                if imported == specifier.local.name {
+                    named.push(imported.to_string());
+                } else {
+                    named.push(format!("{}: {}", json_string(&imported), specifier.local.name));
+                }
+            }
+        }
+    }
+    if !named.is_empty() {
+        lines.push(format!("const {{ {} }} = __require({});", named.join(", "), source));
+    }
+    lines.join(" ")
+}
+
+// Named functions and classes keep their declaration so the rest of the module can still refer to
+// them; anything else is bound to `__tool` directly.
+fn default_export<'a>(ast: &AstBuilder<'a>, declaration: ExportDefaultDeclarationKind<'a>) -> (Option<Statement<'a>>, Option<String>) {
+    let init = match declaration {
+        ExportDefaultDeclarationKind::FunctionDeclaration(function) if function.id.is_some() => {
+            let name = function.id.as_ref().map(|id| id.name.to_string());
+            return (Some(Statement::FunctionDeclaration(function)), name);
+        }
+        ExportDefaultDeclarationKind::ClassDeclaration(class) if class.id.is_some() => {
+            let name = class.id.as_ref().map(|id| id.name.to_string());
+            return (Some(Statement::ClassDeclaration(class)), name);
+        }
+        ExportDefaultDeclarationKind::FunctionDeclaration(mut function) => {
+            function.r#type = FunctionType::FunctionExpression;
+            Expression::FunctionExpression(function)
+        }
+        ExportDefaultDeclarationKind::ClassDeclaration(mut class) => {
+            class.r#type = ClassType::ClassExpression;
+            Expression::ClassExpression(class)
+        }
+        ExportDefaultDeclarationKind::TSInterfaceDeclaration(_) => return (None, None),
+        declaration => declaration.into_expression(),
+    };
+    let binding = ast.binding_pattern_binding_identifier(SPAN, "__tool");
+    let declarator = ast.variable_declarator(SPAN, VariableDeclarationKind::Const, binding, NONE, Some(init), false);
+    let declaration = ast.declaration_variable(SPAN, VariableDeclarationKind::Const, ast.vec1(declarator), false);
+    (Some(Statement::from(declaration)), None)
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
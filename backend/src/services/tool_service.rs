@@ -1,33 +1,475 @@
+use std::sync::LazyLock;
+
+use actix_web::http::StatusCode;
 use chrono::Utc;
+use regex::Regex;
 use uuid::Uuid;
 
-use crate::models::tool::{Tool, ToolGenerateRequest, ToolMetadata};
-use crate::utils::config::AppConfig;
+use crate::models::ai::AiCall;
+use crate::models::tool::{Tool, ToolDiagnostic, ToolDiffResponse, ToolEditRequest, ToolGenerateRequest, ToolMetadata, ToolVersion, UiPreferences};
+use crate::services::ai_client::{AiClient, AiError};
+use crate::services::ai_service::AiService;
+use crate::services::plan_service::extract_json;
+use crate::services::prompt_registry::PromptRegistry;
+use crate::services::quiz_service::QuizService;
+use crate::services::response_cache::ResponseCache;
+use crate::services::template_registry::{TemplateError, ToolTemplate};
+use crate::services::tool_validator::ToolValidator;
+use crate::utils::errors::ApiError;
+
+const DEFAULT_CARDS: usize = 5;
+const DEFAULT_TIMER_SECS: u32 = 25 * 60;
+
+static DURATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(\d+(?:\.\d+)?)\s*(hours?|hrs?|h|minutes?|mins?|m|seconds?|secs?|s)\b").expect("duration pattern"));
+static CARD_COUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(\d+)\s*(?:flash\s*)?(?:cards|questions|terms)\b").expect("card count pattern"));
+static PRECISION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(\d+)\s*decimal").expect("precision pattern"));
+static QA_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(?:[-*•]\s*)?(?i:q|question)\s*:\s*(.+?)\s+(?i:a|answer)\s*:\s*(.+?)\s*$").expect("card pattern"));
+static QUESTION_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(?:[-*•]\s*)?(?i:q|question)\s*:\s*(.+?)\s*$").expect("question pattern"));
+static ANSWER_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(?:[-*•]\s*)?(?i:a|answer)\s*:\s*(.+?)\s*$").expect("answer pattern"));
+static ARROW_LINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(?:[-*•]\s*)?(.+?)\s*(?:->|=>)\s*(.+?)\s*$").expect("card pattern"));
+const FORMULAS: [(&str, &str, &str); 5] = [
+    ("quadratic", r"(?i)quadratic|discriminant|\broots\b|x\^2|x²", "Solves ax² + bx + c = 0"),
+    ("pythagoras", r"(?i)pythagor|hypotenuse|right[- ]angled? triangle", "Finds the hypotenuse of a right triangle"),
+    ("linear", r"(?i)linear|ax\s*\+\s*b|solve for x", "Solves ax + b = c"),
+    ("percentage", r"(?i)percent|%", "Works out percentages"),
+    ("average", r"(?i)average|\bmean\b|median", "Averages a list of numbers"),
+];
+static FORMULA_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| FORMULAS.iter().map(|(_, pattern, _)| Regex::new(pattern).expect("formula pattern")).collect());
+
+static FEATURE_MARKERS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\buse(State|Effect|Reducer|Memo|Callback)\s*\(|\bconst\s+\w+\s*=\s*(async\s*)?\([^)]*\)\s*=>|<(Button|TextField|Select|Slider|Switch|Checkbox|Dialog|Tabs)\b").expect("feature pattern")
+});
 
 pub struct ToolService;
 
+#[derive(Debug, Clone)]
+pub struct ToolEdit {
+    pub component_code: String,
+    pub changes_summary: String,
+    pub change_type: String,
+    pub version: String,
+    pub ai_model: String,
+    pub prompt_version: String,
+    pub diagnostics: Vec<ToolDiagnostic>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ToolEditError {
+    #[error(transparent)]
+    Ai(#[from] AiError),
+    #[error("{}", .0.message)]
+    Prompt(ApiError),
+    #[error("The edited component failed validation: {}", .0.iter().filter(|diagnostic| diagnostic.is_error()).map(|diagnostic| diagnostic.message.as_str()).collect::<Vec<_>>().join("; "))]
+    Invalid(Vec<ToolDiagnostic>),
+}
+
+impl From<ToolEditError> for ApiError {
+    fn from(error: ToolEditError) -> Self {
+        match error {
+            ToolEditError::Ai(error @ AiError::OutputBlocked(_)) => ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, error.code(), error.to_string()),
+            ToolEditError::Ai(error) => ApiError::new(StatusCode::SERVICE_UNAVAILABLE, error.code(), format!("Tool editing needs the AI provider: {}", error)),
+            ToolEditError::Prompt(error) => error,
+            ToolEditError::Invalid(diagnostics) => {
+                let message = ToolEditError::Invalid(diagnostics.clone()).to_string();
+                ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "TOOL_EDIT_INVALID", message).with_details(serde_json::json!({"diagnostics": diagnostics}))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolLayout {
+    pub theme: &'static str,
+    pub size: &'static str,
+    pub max_width: u32,
+}
+
+impl ToolLayout {
+    pub fn from_preferences(preferences: Option<&UiPreferences>) -> Self {
+        let theme = match preferences.and_then(|prefs| prefs.theme.as_deref()) {
+            Some(theme) if theme.eq_ignore_ascii_case("dark") => "dark",
+            _ => "light",
+        };
+        let (size, max_width) = match preferences.and_then(|prefs| prefs.size.as_deref()).map(str::to_lowercase).as_deref() {
+            Some("small") => ("small", 360),
+            Some("large") => ("large", 720),
+            _ => ("medium", 520),
+        };
+        Self { theme, size, max_width }
+    }
+}
+
 impl ToolService {
-    pub fn generate_tool(request: &ToolGenerateRequest, config: &AppConfig) -> Tool {
-        let template = match request.tool_type.as_str() {
-            "calculator" => include_str!("../../templates/calculator_template.tsx"),
-            "timer" => include_str!("../../templates/timer_template.tsx"),
-            "flashcard" => include_str!("../../templates/flashcard_template.tsx"),
-            _ => include_str!("../../templates/custom_template.tsx"),
+    pub async fn generate_tool(
+        request: &ToolGenerateRequest,
+        template: &ToolTemplate,
+        ai: &AiClient,
+        prompts: &PromptRegistry,
+        cache: &ResponseCache<'_>,
+        call: &mut AiCall,
+    ) -> Result<Tool, TemplateError> {
+        let layout = ToolLayout::from_preferences(request.ui_preferences.as_ref());
+        let mut tool = Self::from_template(request, template, &layout)?;
+        if ai.is_enabled() {
+            let max_width = layout.max_width.to_string();
+            if let Ok(prompt) = prompts.render(
+                "tool_generation",
+                &[
+                    ("tool_type", &request.tool_type),
+                    ("context", &request.context),
+                    ("requirements", &request.requirements),
+                    ("theme", layout.theme),
+                    ("size", layout.size),
+                    ("max_width", &max_width),
+                ],
+            ) && let Ok(completion) = AiService::complete(&prompt.id(), &prompt.messages(), ai, cache, call, |reply| Self::parse_generated(reply).is_some()).await
+                && let Some((name, description, code)) = Self::parse_generated(&completion.content)
+            {
+                if let Some(name) = name {
+                    tool.name = name;
+                }
+                if let Some(description) = description {
+                    tool.description = description;
+                }
+                tool.component_code = code;
+                tool.metadata.ai_model = completion.model;
+                tool.metadata.prompt_version = Some(prompt.id());
+            }
+        }
+        Ok(tool)
+    }
+
+    pub fn parse_generated(raw: &str) -> Option<(Option<String>, Option<String>, String)> {
+        let (root, code) = split_component(raw)?;
+        if ToolValidator::has_errors(&ToolValidator::validate(&code)) {
+            return None;
+        }
+        Some((json_text(&root, "name").map(|name| truncate_words(&name, 60)), json_text(&root, "description"), code))
+    }
+
+    pub async fn edit_tool(tool: &Tool, request: &ToolEditRequest, ai: &AiClient, prompts: &PromptRegistry, cache: &ResponseCache<'_>, call: &mut AiCall) -> Result<ToolEdit, ToolEditError> {
+        let current_state = request
+            .current_state
+            .as_ref()
+            .and_then(|state| serde_json::to_string_pretty(state).ok())
+            .unwrap_or_else(|| "None".to_string());
+        let prompt = prompts
+            .render(
+                "tool_editing",
+                &[("current_code", &tool.component_code), ("edit_instruction", &request.edit_instruction), ("current_state", &current_state)],
+            )
+            .map_err(ToolEditError::Prompt)?;
+        let completion = AiService::complete(&prompt.id(), &prompt.messages(), ai, cache, call, |reply| Self::check_edit(tool, reply).is_ok()).await?;
+        let (root, code, diagnostics) = Self::check_edit(tool, &completion.content).map_err(ToolEditError::Invalid)?;
+
+        let change_type = json_text(&root, "change_type")
+            .map(|value| value.to_lowercase())
+            .filter(|value| matches!(value.as_str(), "patch" | "minor" | "major"))
+            .unwrap_or_else(|| Self::infer_change_type(&tool.component_code, &code).to_string());
+        Ok(ToolEdit {
+            version: Self::bump_version(&tool.metadata.version, &change_type),
+            changes_summary: json_text(&root, "changes_summary").unwrap_or_else(|| format!("Updated the component: {}", request.edit_instruction.trim())),
+            change_type,
+            component_code: code,
+            ai_model: completion.model,
+            prompt_version: prompt.id(),
+            diagnostics,
+        })
+    }
+
+    fn check_edit(tool: &Tool, raw: &str) -> Result<(serde_json::Value, String, Vec<ToolDiagnostic>), Vec<ToolDiagnostic>> {
+        let Some((root, code)) = split_component(raw) else {
+            return Err(vec![ToolDiagnostic::error("no_code", "The AI response did not contain component code")]);
         };
-        Tool {
+        let mut diagnostics = ToolValidator::validate(&code);
+        if code.trim() == tool.component_code.trim() {
+            diagnostics.push(ToolDiagnostic::error("unchanged", "The edit returned the current code unchanged"));
+        }
+        if ToolValidator::has_errors(&diagnostics) {
+            return Err(diagnostics);
+        }
+        Ok((root, code, diagnostics))
+    }
+
+    pub fn infer_change_type(before: &str, after: &str) -> &'static str {
+        if FEATURE_MARKERS.find_iter(after).count() > FEATURE_MARKERS.find_iter(before).count() {
+            "minor"
+        } else {
+            "patch"
+        }
+    }
+
+    pub fn diff(from: &ToolVersion, to: &ToolVersion) -> ToolDiffResponse {
+        let mut options = diffy::DiffOptions::new();
+        options
+            .set_original_filename(format!("v{}", from.version))
+            .set_modified_filename(format!("v{}", to.version));
+        let patch = options.create_patch(&from.component_code, &to.component_code);
+        let (mut additions, mut deletions) = (0, 0);
+        for line in patch.hunks().iter().flat_map(|hunk| hunk.lines()) {
+            match line {
+                diffy::Line::Insert(_) => additions += 1,
+                diffy::Line::Delete(_) => deletions += 1,
+                diffy::Line::Context(_) => {}
+            }
+        }
+        ToolDiffResponse {
+            tool_id: to.tool_id,
+            from: from.version.clone(),
+            to: to.version.clone(),
+            additions,
+            deletions,
+            diff: patch.to_string(),
+        }
+    }
+
+    pub fn bump_version(previous: &str, change_type: &str) -> String {
+        let mut parts = previous
+            .trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+            .filter(|parts| parts.len() == 3)
+            .unwrap_or_else(|| vec![1, 0, 0]);
+        match change_type {
+            "major" => parts = vec![parts[0] + 1, 0, 0],
+            "minor" => parts = vec![parts[0], parts[1] + 1, 0],
+            _ => parts[2] += 1,
+        }
+        format!("{}.{}.{}", parts[0], parts[1], parts[2])
+    }
+
+    pub fn from_template(request: &ToolGenerateRequest, template: &ToolTemplate, layout: &ToolLayout) -> Result<Tool, TemplateError> {
+        let parameters = template.resolve_parameters(request.parameters.as_ref())?;
+        let explicit = |key: &str| request.parameters.as_ref().and_then(|values| values.get(key)).filter(|value| !value.is_null());
+        let subject = truncate_words(&request.context, 40);
+        let subject = if subject.is_empty() { title_case(&template.tool_type.replace('_', " ")) } else { subject };
+        let hints = format!("{}\n{}", request.requirements, request.context);
+        let (name, description, mut variables) = match template.tool_type.as_str() {
+            "calculator" => {
+                let formula = explicit("formula").and_then(|value| value.as_str()).map(str::to_string).unwrap_or_else(|| detect_formula(&hints).to_string());
+                let summary = FORMULAS
+                    .iter()
+                    .find(|(name, _, _)| *name == formula)
+                    .map(|(_, _, summary)| *summary)
+                    .unwrap_or("Adds, subtracts, multiplies and divides two numbers");
+                let precision = explicit("precision").and_then(|value| value.as_u64()).map(|value| value as u32).unwrap_or_else(|| {
+                    PRECISION
+                        .captures(&hints)
+                        .and_then(|caps| caps[1].parse::<u32>().ok())
+                        .unwrap_or(2)
+                        .min(10)
+                });
+                (
+                    format!("{} Calculator", subject),
+                    format!("{} for {}, rounded to {} decimal places.", summary, subject, precision),
+                    vec![("formula".to_string(), json_string(&formula)), ("precision".to_string(), precision.to_string())],
+                )
+            }
+            "timer" => {
+                let seconds = explicit("duration_seconds")
+                    .and_then(|value| value.as_u64())
+                    .map(|value| value as u32)
+                    .or_else(|| parse_duration(&request.requirements))
+                    .or_else(|| parse_duration(&request.context))
+                    .unwrap_or(DEFAULT_TIMER_SECS);
+                let label = format_duration(seconds);
+                (
+                    format!("{} {} Timer", label, subject),
+                    format!("A {} countdown for {} with start, pause and reset.", label, subject),
+                    vec![("duration_seconds".to_string(), seconds.to_string())],
+                )
+            }
+            "flashcard" => {
+                let cards = match explicit("cards").and_then(|value| value.as_array()) {
+                    Some(cards) => cards.clone(),
+                    None => {
+                        let count = CARD_COUNT
+                            .captures(&hints)
+                            .and_then(|caps| caps[1].parse::<usize>().ok())
+                            .unwrap_or(DEFAULT_CARDS)
+                            .clamp(1, 20);
+                        let mut cards = parse_cards(&request.requirements);
+                        if cards.is_empty() {
+                            let topic = request.context.trim();
+                            cards = QuizService::from_bank(request.subject.as_deref().unwrap_or(topic), topic, count)
+                                .into_iter()
+                                .map(|question| (question.prompt, question.answer))
+                                .collect();
+                        }
+                        cards
+                            .into_iter()
+                            .map(|(question, answer)| serde_json::json!({"question": question, "answer": answer}))
+                            .collect::<Vec<_>>()
+                    }
+                };
+                (
+                    format!("{} Flashcards", subject),
+                    format!("{} flashcards on {}.", cards.len(), subject),
+                    vec![("cards".to_string(), serde_json::to_string_pretty(&cards).unwrap_or_else(|_| "[]".to_string()))],
+                )
+            }
+            "custom" => {
+                let mut steps = match explicit("steps").and_then(|value| value.as_array()) {
+                    Some(steps) => steps.iter().filter_map(|step| step.as_str()).map(str::to_string).collect(),
+                    None => request
+                        .requirements
+                        .split(['\n', ';'])
+                        .flat_map(|line| line.split(". "))
+                        .map(|step| step.trim().trim_start_matches(['-', '*', '•']).trim().trim_end_matches('.').to_string())
+                        .filter(|step| !step.is_empty())
+                        .take(10)
+                        .collect::<Vec<_>>(),
+                };
+                if steps.is_empty() {
+                    steps = vec!["Read through the material".to_string(), "Work through examples".to_string(), "Summarise the key points".to_string()];
+                }
+                let description = explicit("description")
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("A checklist for {}.", subject));
+                (
+                    format!("{} Checklist", subject),
+                    description.clone(),
+                    vec![("description".to_string(), json_string(&description)), ("steps".to_string(), serde_json::to_string(&steps).unwrap_or_else(|_| "[]".to_string()))],
+                )
+            }
+            kind => (
+                format!("{} {}", subject, title_case(&kind.replace('_', " "))),
+                if template.description.is_empty() { format!("A {} for {}.", kind.replace('_', " "), subject) } else { template.description.clone() },
+                parameters
+                    .iter()
+                    .map(|(key, value)| (key.clone(), serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())))
+                    .collect(),
+            ),
+        };
+        variables.extend([
+            ("component_name".to_string(), component_name(&name)),
+            ("title".to_string(), json_string(&name)),
+            ("theme_mode".to_string(), json_string(layout.theme)),
+            ("max_width".to_string(), layout.max_width.to_string()),
+        ]);
+        let component_code = variables
+            .iter()
+            .fold(template.code.clone(), |code, (key, value)| code.replace(&format!("{{{{{}}}}}", key), value));
+
+        Ok(Tool {
             tool_id: Uuid::new_v4(),
             user_id: request.user_id,
             tool_type: request.tool_type.clone(),
-            name: format!("{} Tool", request.tool_type),
-            description: request.context.clone(),
-            component_code: template.to_string(),
+            name,
+            description,
+            component_code,
             metadata: ToolMetadata {
                 version: "1.0.0".to_string(),
                 created_at: Utc::now(),
-                ai_model: config.ai_provider.clone(),
+                ai_model: "local".to_string(),
+                prompt_version: None,
+                template: Some(template.id()),
             },
             usage_count: 0,
             last_used: None,
+        })
+    }
+}
+
+fn split_component(raw: &str) -> Option<(serde_json::Value, String)> {
+    let (root, code) = match extract_json(raw).and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok()) {
+        Some(root) if json_text(&root, "component_code").is_some() => {
+            let code = json_text(&root, "component_code")?;
+            (root, code)
+        }
+        _ => (serde_json::Value::Null, fenced_code(raw)?),
+    };
+    let code = fenced_code(&code).unwrap_or(code);
+    Some((root, code))
+}
+
+fn json_text(value: &serde_json::Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|field| field.as_str()).map(|field| field.trim().to_string()).filter(|field| !field.is_empty())
+}
+
+fn fenced_code(raw: &str) -> Option<String> {
+    let start = raw.find("```")?;
+    let body = &raw[start + 3..];
+    let body = &body[body.find('\n')? + 1..];
+    let end = body.find("```")?;
+    Some(body[..end].trim().to_string())
+}
+
+fn parse_duration(text: &str) -> Option<u32> {
+    let caps = DURATION.captures(text)?;
+    let value = caps[1].parse::<f64>().ok()?;
+    let unit = caps[2].to_lowercase();
+    let seconds = match unit.chars().next()? {
+        'h' => value * 3_600.0,
+        'm' => value * 60.0,
+        _ => value,
+    };
+    Some((seconds.round() as u32).clamp(1, 4 * 3_600))
+}
+
+fn format_duration(seconds: u32) -> String {
+    match seconds {
+        seconds if seconds % 3_600 == 0 => format!("{}-Hour", seconds / 3_600),
+        seconds if seconds % 60 == 0 => format!("{}-Minute", seconds / 60),
+        seconds => format!("{}-Second", seconds),
+    }
+}
+
+fn parse_cards(text: &str) -> Vec<(String, String)> {
+    let mut cards = Vec::new();
+    let mut question: Option<String> = None;
+    for line in text.split(['\n', ';']) {
+        let pending = question.take();
+        if let Some(caps) = QA_LINE.captures(line).or_else(|| ARROW_LINE.captures(line).filter(|_| !QUESTION_LINE.is_match(line))) {
+            cards.push((caps[1].to_string(), caps[2].to_string()));
+        } else if let Some(caps) = QUESTION_LINE.captures(line) {
+            question = Some(caps[1].to_string());
+        } else if let (Some(asked), Some(caps)) = (pending, ANSWER_LINE.captures(line)) {
+            cards.push((asked, caps[1].to_string()));
+        }
+    }
+    cards.truncate(20);
+    cards
+}
+
+fn detect_formula(text: &str) -> &'static str {
+    FORMULAS.iter().zip(FORMULA_PATTERNS.iter()).find(|(_, pattern)| pattern.is_match(text)).map(|((name, _, _), _)| *name).unwrap_or("basic")
+}
+
+fn truncate_words(text: &str, limit: usize) -> String {
+    let mut output = String::new();
+    for word in text.split_whitespace() {
+        if output.len() + word.len() + 1 > limit && !output.is_empty() {
+            break;
+        }
+        if !output.is_empty() {
+            output.push(' ');
         }
+        output.push_str(word);
     }
+    output.chars().take(limit).collect()
+}
+
+fn title_case(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn component_name(name: &str) -> String {
+    let pascal = name
+        .split(|ch: char| !ch.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(title_case)
+        .collect::<String>();
+    match pascal.chars().next() {
+        Some(first) if first.is_ascii_alphabetic() => pascal,
+        _ => format!("Study{}", pascal),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_else(|_| "\"\"".to_string())
 }
@@ -0,0 +1,97 @@
+use actix_web::http::StatusCode;
+
+use crate::models::tool::ToolStateEntry;
+use crate::utils::config::AppConfig;
+use crate::utils::errors::ApiError;
+
+const MAX_KEY_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ToolStateError {
+    #[error("State keys must be 1-64 characters of letters, digits, '.', '_' or '-'")]
+    InvalidKey,
+    #[error("The state document is {size} bytes, above the limit of {limit}")]
+    TooLarge { size: usize, limit: usize },
+    #[error("The tool would store {total} bytes for this user, above the limit of {limit}")]
+    QuotaExceeded { total: usize, limit: usize },
+    #[error("The state was changed by another writer; expected revision {expected}, found {current}")]
+    Conflict { expected: u64, current: u64 },
+}
+
+impl ToolStateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidKey => "VALIDATION_ERROR",
+            Self::TooLarge { .. } => "STATE_TOO_LARGE",
+            Self::QuotaExceeded { .. } => "STATE_QUOTA_EXCEEDED",
+            Self::Conflict { .. } => "STATE_CONFLICT",
+        }
+    }
+}
+
+impl From<ToolStateError> for ApiError {
+    fn from(error: ToolStateError) -> Self {
+        let (status, details) = match &error {
+            ToolStateError::InvalidKey => (StatusCode::BAD_REQUEST, None),
+            ToolStateError::TooLarge { size, limit } => (StatusCode::PAYLOAD_TOO_LARGE, Some(serde_json::json!({"size": size, "limit": limit}))),
+            ToolStateError::QuotaExceeded { total, limit } => (StatusCode::PAYLOAD_TOO_LARGE, Some(serde_json::json!({"total": total, "limit": limit}))),
+            ToolStateError::Conflict { expected, current } => (StatusCode::CONFLICT, Some(serde_json::json!({"expected_revision": expected, "current_revision": current}))),
+        };
+        let api_error = ApiError::new(status, error.code(), error.to_string());
+        match details {
+            Some(details) => api_error.with_details(details),
+            None => api_error,
+        }
+    }
+}
+
+pub struct ToolStateService;
+
+impl ToolStateService {
+    pub fn validate_key(key: &str) -> Result<(), ToolStateError> {
+        let valid = !key.is_empty()
+            && key.len() <= MAX_KEY_LENGTH
+            && key.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+        if valid { Ok(()) } else { Err(ToolStateError::InvalidKey) }
+    }
+
+    pub fn check_revision(current: Option<&ToolStateEntry>, expected: Option<u64>) -> Result<u64, ToolStateError> {
+        let current = current.map(|entry| entry.revision).unwrap_or(0);
+        match expected {
+            Some(expected) if expected != current => Err(ToolStateError::Conflict { expected, current }),
+            _ => Ok(current + 1),
+        }
+    }
+
+    pub fn encode(value: &serde_json::Value, other_keys_bytes: usize, config: &AppConfig) -> Result<String, ToolStateError> {
+        let json = value.to_string();
+        if json.len() > config.tool_state_max_bytes {
+            return Err(ToolStateError::TooLarge { size: json.len(), limit: config.tool_state_max_bytes });
+        }
+        let total = other_keys_bytes + json.len();
+        if total > config.tool_state_max_total_bytes {
+            return Err(ToolStateError::QuotaExceeded { total, limit: config.tool_state_max_total_bytes });
+        }
+        Ok(json)
+    }
+
+    pub fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+        let serde_json::Value::Object(patch) = patch else {
+            *target = patch.clone();
+            return;
+        };
+        if !target.is_object() {
+            *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        let serde_json::Value::Object(target) = target else {
+            return;
+        };
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                Self::merge_patch(target.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+        }
+    }
+}
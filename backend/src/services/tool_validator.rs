@@ -0,0 +1,297 @@
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    Argument, CallExpression, ComputedMemberExpression, Declaration, ExportDefaultDeclarationKind, Expression, IdentifierReference,
+    ImportDeclaration, ImportExpression, JSXAttribute, JSXElementName, JSXOpeningElement, NewExpression, Program, Statement, StaticMemberExpression, UnaryExpression,
+    UnaryOperator,
+};
+use oxc_ast_visit::{Visit, walk};
+use oxc_parser::Parser;
+use oxc_semantic::{Scoping, SemanticBuilder};
+use oxc_span::{SourceType, Span};
+
+use crate::models::tool::ToolDiagnostic;
+
+const ALLOWED_IMPORTS: [&str; 3] = ["react", "@mui/material", "@mui/icons-material"];
+const STORAGE_OBJECTS: [&str; 2] = ["localStorage", "sessionStorage"];
+const STORAGE_ABUSE: [&str; 3] = ["clear", "key", "length"];
+const GLOBAL_OBJECTS: [&str; 8] = ["window", "globalThis", "self", "top", "parent", "frames", "opener", "defaultView"];
+const NETWORK_GLOBALS: [&str; 4] = ["XMLHttpRequest", "WebSocket", "EventSource", "Image"];
+const HOST_GLOBALS: [&str; 2] = ["process", "ipcRenderer"];
+
+pub struct ToolValidator;
+
+impl ToolValidator {
+    pub fn validate(code: &str) -> Vec<ToolDiagnostic> {
+        if code.trim().is_empty() {
+            return vec![ToolDiagnostic::error("empty_code", "Component code is empty")];
+        }
+        let allocator = Allocator::default();
+        let parsed = Parser::new(&allocator, code, SourceType::tsx()).parse();
+        if parsed.panicked || !parsed.errors.is_empty() {
+            return parsed
+                .errors
+                .iter()
+                .map(|error| {
+                    let offset = error.labels.as_ref().and_then(|labels| labels.first()).map(|label| label.offset());
+                    locate(ToolDiagnostic::error("syntax_error", error.to_string()), code, offset)
+                })
+                .collect();
+        }
+
+        let scoping = SemanticBuilder::new().build(&parsed.program).semantic.into_scoping();
+        let mut scanner = Scanner { code, scoping: &scoping, diagnostics: Vec::new() };
+        if let Some(diagnostic) = check_default_export(&parsed.program, code) {
+            scanner.diagnostics.push(diagnostic);
+        }
+        scanner.visit_program(&parsed.program);
+        scanner.diagnostics
+    }
+
+    pub fn has_errors(diagnostics: &[ToolDiagnostic]) -> bool {
+        diagnostics.iter().any(ToolDiagnostic::is_error)
+    }
+}
+
+fn locate(mut diagnostic: ToolDiagnostic, code: &str, offset: Option<usize>) -> ToolDiagnostic {
+    if let Some(offset) = offset.filter(|offset| *offset <= code.len() && code.is_char_boundary(*offset)) {
+        let before = &code[..offset];
+        diagnostic.line = Some(before.matches('\n').count() as u32 + 1);
+        diagnostic.column = Some(before.rsplit('\n').next().map(|line| line.chars().count()).unwrap_or(0) as u32 + 1);
+    }
+    diagnostic
+}
+
+fn is_component_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|first| first.is_ascii_uppercase())
+}
+
+fn is_component_expression(expression: &Expression<'_>) -> bool {
+    match expression.get_inner_expression() {
+        Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_) | Expression::ClassExpression(_) => true,
+        // React.memo(Component), forwardRef(...) and similar wrappers.
+        Expression::CallExpression(call) => call.arguments.first().is_some_and(|argument| match argument {
+            Argument::Identifier(ident) => is_component_name(&ident.name),
+            argument => argument.as_expression().is_some_and(is_component_expression),
+        }),
+        _ => false,
+    }
+}
+
+fn top_level_binding<'a>(program: &'a Program<'a>, name: &str) -> Option<bool> {
+    let declaration_matches = |declaration: &Declaration<'a>| match declaration {
+        Declaration::FunctionDeclaration(function) => function.id.as_ref().is_some_and(|id| id.name == name).then_some(true),
+        Declaration::ClassDeclaration(class) => class.id.as_ref().is_some_and(|id| id.name == name).then_some(true),
+        Declaration::VariableDeclaration(variables) => variables
+            .declarations
+            .iter()
+            .find(|declarator| declarator.id.get_identifier_name().is_some_and(|id| id == name))
+            .map(|declarator| declarator.init.as_ref().is_some_and(is_component_expression)),
+        _ => None,
+    };
+    program.body.iter().find_map(|statement| match statement {
+        Statement::ExportNamedDeclaration(export) => export.declaration.as_ref().and_then(declaration_matches),
+        statement => statement.as_declaration().and_then(declaration_matches),
+    })
+}
+
+fn is_unresolved(scoping: &Scoping, ident: &IdentifierReference<'_>) -> bool {
+    scoping.get_reference(ident.reference_id()).symbol_id().is_none()
+}
+
+// `name`, `window.name`, `globalThis["name"]` and chains such as `window.self.name` all name the global `name`.
+// Identifiers bound in the tool itself are not globals, whatever they are called.
+fn global_name<'a>(scoping: &Scoping, expression: &Expression<'a>) -> Option<&'a str> {
+    match expression.get_inner_expression() {
+        Expression::Identifier(ident) => is_unresolved(scoping, ident).then(|| ident.name.as_str()),
+        expression => expression
+            .as_member_expression()
+            .filter(|member| {
+                is_global_object(scoping, member.object())
+                    || (member.static_property_name() == Some("defaultView") && global_name(scoping, member.object()) == Some("document"))
+            })
+            .and_then(|member| member.static_property_name()),
+    }
+}
+
+fn is_global_object(scoping: &Scoping, expression: &Expression<'_>) -> bool {
+    global_name(scoping, expression).is_some_and(|name| GLOBAL_OBJECTS.contains(&name))
+}
+
+fn check_default_export(program: &Program<'_>, code: &str) -> Option<ToolDiagnostic> {
+    let Some(export) = program.body.iter().find_map(|statement| match statement {
+        Statement::ExportDefaultDeclaration(export) => Some(export),
+        _ => None,
+    }) else {
+        return Some(ToolDiagnostic::error("missing_default_export", "The component must be the module's default export"));
+    };
+    let is_component = match &export.declaration {
+        ExportDefaultDeclarationKind::FunctionDeclaration(_) | ExportDefaultDeclarationKind::ClassDeclaration(_) => true,
+        ExportDefaultDeclarationKind::Identifier(ident) => is_component_name(&ident.name) && top_level_binding(program, &ident.name).unwrap_or(false),
+        declaration => declaration.as_expression().is_some_and(is_component_expression),
+    };
+    (!is_component).then(|| {
+        locate(
+            ToolDiagnostic::error("default_export_not_component", "The default export must be a React component (a function or class with a capitalised name)"),
+            code,
+            Some(export.span.start as usize),
+        )
+    })
+}
+
+struct Scanner<'s> {
+    code: &'s str,
+    scoping: &'s Scoping,
+    diagnostics: Vec<ToolDiagnostic>,
+}
+
+impl Scanner<'_> {
+    fn report(&mut self, diagnostic: ToolDiagnostic, span: Span) {
+        self.diagnostics.push(locate(diagnostic, self.code, Some(span.start as usize)));
+    }
+
+    fn check_global(&mut self, name: &str, span: Span) {
+        if name == "eval" {
+            self.report(ToolDiagnostic::error("eval", "eval is not allowed"), span);
+        } else if name == "fetch" {
+            self.report(ToolDiagnostic::error("external_fetch", "fetch must be called directly with a URL"), span);
+        } else if NETWORK_GLOBALS.contains(&name) {
+            self.report(ToolDiagnostic::error("network_access", format!("{} is not allowed", name)), span);
+        } else if name == "location" {
+            self.report(ToolDiagnostic::error("navigation", "location is not available to tools"), span);
+        } else if HOST_GLOBALS.contains(&name) {
+            self.report(ToolDiagnostic::error("host_access", format!("{} is not available to tools", name)), span);
+        } else if GLOBAL_OBJECTS.contains(&name) {
+            self.report(ToolDiagnostic::error("dynamic_global", format!("{} can only be used for direct property access", name)), span);
+        }
+    }
+
+    // `object.property`, where `property` is `None` for a computed key that is not a literal.
+    fn check_member(&mut self, object: &Expression<'_>, property: Option<&str>, span: Span) {
+        if is_global_object(self.scoping, object) {
+            match property {
+                Some("open") => self.report(ToolDiagnostic::error("window_open", "window.open is not allowed"), span),
+                Some(name) => self.check_global(name, span),
+                None => self.report(ToolDiagnostic::error("dynamic_global", "Computed access on the global object or another frame is not allowed"), span),
+            }
+            return;
+        }
+        let Some(object_name) = global_name(self.scoping, object) else {
+            return;
+        };
+        if object_name == "document" && property == Some("defaultView") {
+            self.check_global("defaultView", span);
+        } else if object_name == "document" && matches!(property, Some("createElement" | "createElementNS")) {
+            self.report(ToolDiagnostic::error("document_access", "document.createElement is not allowed; render elements with JSX"), span);
+        } else if object_name == "document" && matches!(property, Some("cookie" | "location") | None) {
+            self.report(ToolDiagnostic::error("document_access", format!("document.{} is not allowed", property.unwrap_or("[key]"))), span);
+        } else if object_name == "navigator" && property.is_none_or(|name| name == "sendBeacon") {
+            self.report(ToolDiagnostic::error("network_access", "navigator.sendBeacon is not allowed"), span);
+        } else if let Some(storage) = STORAGE_OBJECTS.iter().find(|storage| **storage == object_name) {
+            match property {
+                Some(name) if !STORAGE_ABUSE.contains(&name) => {
+                    self.report(ToolDiagnostic::warning("storage_access", format!("{}.{} persists outside the tool; prefer component state", storage, name)), span);
+                }
+                name => self.report(
+                    ToolDiagnostic::error("storage_abuse", format!("{}.{} touches data owned by other tools", storage, name.unwrap_or("[key]"))),
+                    span,
+                ),
+            }
+        }
+    }
+}
+
+impl<'a> Visit<'a> for Scanner<'_> {
+    fn visit_import_declaration(&mut self, it: &ImportDeclaration<'a>) {
+        let source = it.source.value.as_str();
+        if !ALLOWED_IMPORTS.iter().any(|allowed| source == *allowed || source.starts_with(&format!("{}/", allowed))) {
+            self.report(ToolDiagnostic::error("unsupported_import", format!("Import from '{}' is not available to tools; use react and @mui only", source)), it.span);
+        }
+        walk::walk_import_declaration(self, it);
+    }
+
+    fn visit_import_expression(&mut self, it: &ImportExpression<'a>) {
+        self.report(ToolDiagnostic::error("dynamic_import", "Dynamic import() is not allowed"), it.span);
+        walk::walk_import_expression(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        let callee = global_name(self.scoping, &it.callee);
+        if callee == Some("require") {
+            self.report(ToolDiagnostic::error("require", "require() is not allowed; use static imports from react or @mui"), it.span);
+        } else if callee == Some("Function") {
+            self.report(ToolDiagnostic::error("eval", "Function() is not allowed"), it.span);
+        } else if callee == Some("fetch") {
+            let url = match it.arguments.first() {
+                Some(Argument::StringLiteral(literal)) => Some(literal.value.to_string()),
+                Some(Argument::TemplateLiteral(template)) => template.quasis.first().map(|quasi| quasi.value.raw.to_string()),
+                _ => None,
+            };
+            match url {
+                Some(url) if !(url.starts_with("http:") || url.starts_with("https:") || url.starts_with("//")) => {
+                    self.report(ToolDiagnostic::warning("fetch", format!("fetch('{}') only works inside the app; tools have no backend of their own", url)), it.span);
+                }
+                Some(url) => self.report(ToolDiagnostic::error("external_fetch", format!("fetch to external host '{}' is not allowed", url)), it.span),
+                None => self.report(ToolDiagnostic::error("external_fetch", "fetch with a computed URL is not allowed"), it.span),
+            }
+            // The callee was handled above; only the arguments can hide further calls.
+            for argument in &it.arguments {
+                self.visit_argument(argument);
+            }
+            return;
+        } else if callee.is_some_and(|name| name == "setTimeout" || name == "setInterval") && matches!(it.arguments.first(), Some(Argument::StringLiteral(_))) {
+            self.report(ToolDiagnostic::error("eval", "Passing a string to setTimeout/setInterval evaluates code"), it.span);
+        }
+        walk::walk_call_expression(self, it);
+    }
+
+    fn visit_new_expression(&mut self, it: &NewExpression<'a>) {
+        if global_name(self.scoping, &it.callee) == Some("Function") {
+            self.report(ToolDiagnostic::error("eval", "new Function() is not allowed"), it.span);
+        }
+        walk::walk_new_expression(self, it);
+    }
+
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        if is_unresolved(self.scoping, it) {
+            self.check_global(it.name.as_str(), it.span);
+        }
+    }
+
+    fn visit_jsx_opening_element(&mut self, it: &JSXOpeningElement<'a>) {
+        if matches!(&it.name, JSXElementName::Identifier(ident) if ident.name == "script") {
+            self.report(ToolDiagnostic::error("inline_script", "<script> elements are not allowed"), it.span);
+        }
+        walk::walk_jsx_opening_element(self, it);
+    }
+
+    fn visit_jsx_attribute(&mut self, it: &JSXAttribute<'a>) {
+        if it.name.as_identifier().is_some_and(|ident| ident.name == "dangerouslySetInnerHTML") {
+            self.report(ToolDiagnostic::error("inner_html", "dangerouslySetInnerHTML is not allowed"), it.span);
+        }
+        walk::walk_jsx_attribute(self, it);
+    }
+
+    fn visit_unary_expression(&mut self, it: &UnaryExpression<'a>) {
+        // `typeof window` only tests whether the global exists.
+        if it.operator == UnaryOperator::Typeof && matches!(&it.argument, Expression::Identifier(_)) {
+            return;
+        }
+        walk::walk_unary_expression(self, it);
+    }
+
+    fn visit_static_member_expression(&mut self, it: &StaticMemberExpression<'a>) {
+        self.check_member(&it.object, Some(it.property.name.as_str()), it.span);
+        // Walking the object would report `window` itself; only nested expressions matter.
+        if !is_global_object(self.scoping, &it.object) {
+            walk::walk_static_member_expression(self, it);
+        }
+    }
+
+    fn visit_computed_member_expression(&mut self, it: &ComputedMemberExpression<'a>) {
+        self.check_member(&it.object, it.static_property_name().map(|name| name.as_str()), it.span);
+        if !is_global_object(self.scoping, &it.object) {
+            self.visit_expression(&it.object);
+        }
+        self.visit_expression(&it.expression);
+    }
+}
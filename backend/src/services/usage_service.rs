@@ -0,0 +1,50 @@
+use uuid::Uuid;
+
+use crate::models::ai::{AiBudget, AiCall};
+use crate::utils::config::AppConfig;
+
+pub struct UsageService;
+
+impl UsageService {
+    pub fn default_budget(user_id: Uuid, config: &AppConfig) -> AiBudget {
+        AiBudget {
+            user_id,
+            daily_token_limit: (config.ai_daily_token_budget > 0).then_some(config.ai_daily_token_budget),
+            monthly_token_limit: (config.ai_monthly_token_budget > 0).then_some(config.ai_monthly_token_budget),
+            on_exceed: config.ai_budget_action.clone(),
+            downgrade_model: config.ai_downgrade_model.clone(),
+        }
+    }
+
+    pub fn exceeded_period(budget: &AiBudget, used_today: u64, used_this_month: u64) -> Option<&'static str> {
+        if budget.daily_token_limit.is_some_and(|limit| used_today >= limit) {
+            Some("day")
+        } else if budget.monthly_token_limit.is_some_and(|limit| used_this_month >= limit) {
+            Some("month")
+        } else {
+            None
+        }
+    }
+
+    pub fn apply_budget(call: &mut AiCall, budget: &AiBudget, used_today: u64, used_this_month: u64) {
+        let Some(period) = Self::exceeded_period(budget, used_today, used_this_month) else {
+            return;
+        };
+        match (budget.on_exceed.as_str(), &budget.downgrade_model) {
+            ("downgrade", Some(model)) if !model.trim().is_empty() => call.model = Some(model.clone()),
+            _ => call.blocked = Some(period.to_string()),
+        }
+    }
+
+    pub fn validate_budget(budget: &AiBudget) -> Result<(), String> {
+        if !matches!(budget.on_exceed.as_str(), "block" | "downgrade") {
+            return Err("on_exceed must be 'block' or 'downgrade'".to_string());
+        }
+        if budget.on_exceed == "downgrade"
+            && budget.downgrade_model.as_deref().is_none_or(|model| model.trim().is_empty())
+        {
+            return Err("downgrade_model is required when on_exceed is 'downgrade'".to_string());
+        }
+        Ok(())
+    }
+}
@@ -13,6 +13,39 @@ pub struct AppConfig {
     pub openrouter_model: String,
     pub openrouter_base_url: String,
     pub openrouter_referer: Option<String>,
+    pub ai_plan_generation: bool,
+    pub ai_plan_max_attempts: u32,
+    pub ai_phrase_suggestions: bool,
+    pub ai_connect_timeout_ms: u64,
+    pub ai_request_timeout_ms: u64,
+    pub ai_max_retries: u32,
+    pub ai_retry_base_ms: u64,
+    pub ai_retry_max_delay_ms: u64,
+    pub ai_breaker_threshold: u32,
+    pub ai_breaker_cooldown_ms: u64,
+    pub ai_prompt_cost_per_mtok: f64,
+    pub ai_completion_cost_per_mtok: f64,
+    pub ai_daily_token_budget: u64,
+    pub ai_monthly_token_budget: u64,
+    pub ai_budget_action: String,
+    pub ai_downgrade_model: Option<String>,
+    pub ai_cache_ttl_secs: u64,
+    pub ai_cache_max_entries: u64,
+    pub ai_cache_chat: bool,
+    pub retrieval_top_k: usize,
+    pub embeddings_base_url: Option<String>,
+    pub embeddings_model: String,
+    pub ai_redact_pii: bool,
+    pub ai_max_input_chars: usize,
+    pub ai_blocked_terms: Vec<String>,
+    pub preview_runtime_path: String,
+    pub tool_state_max_bytes: usize,
+    pub tool_state_max_total_bytes: usize,
+    pub flashcard_import_max_bytes: usize,
+    pub focus_idle_timeout_secs: i64,
+    pub tool_signing_key: Option<String>,
+    pub tool_trusted_keys: Vec<String>,
+    pub admin_token: Option<String>,
 }
 
 impl AppConfig {
@@ -25,6 +58,8 @@ impl AppConfig {
         let database_path = env::var("DATABASE_PATH")
             .unwrap_or_else(|_| "data/database/studyplanner.db".to_string());
         let data_dir = env::var("DATA_DIR").unwrap_or_else(|_| "data".to_string());
+        let preview_runtime_path = env::var("PREVIEW_RUNTIME_PATH")
+            .unwrap_or_else(|_| std::path::Path::new(&data_dir).join("preview-runtime.js").to_string_lossy().to_string());
         let ai_provider = env::var("AI_PROVIDER").unwrap_or_else(|_| "openrouter".to_string());
         let openrouter_api_key = env::var("OPENROUTER_API_KEY").ok();
         let openrouter_model = env::var("OPENROUTER_MODEL")
@@ -32,6 +67,12 @@ impl AppConfig {
         let openrouter_base_url = env::var("OPENROUTER_BASE_URL")
             .unwrap_or_else(|_| "https://openrouter.ai/api/v1".to_string());
         let openrouter_referer = env::var("OPENROUTER_REFERER").ok();
+        let ai_plan_generation = env::var("AI_PLAN_GENERATION")
+            .map(|value| value != "false" && value != "0")
+            .unwrap_or(true);
+        let ai_phrase_suggestions = env::var("AI_PHRASE_SUGGESTIONS")
+            .map(|value| value == "true" || value == "1")
+            .unwrap_or(false);
         Self {
             host,
             port,
@@ -42,6 +83,47 @@ impl AppConfig {
             openrouter_model,
             openrouter_base_url,
             openrouter_referer,
+            ai_plan_generation,
+            ai_plan_max_attempts: env_number("AI_PLAN_MAX_ATTEMPTS", 2),
+            ai_phrase_suggestions,
+            ai_connect_timeout_ms: env_number("AI_CONNECT_TIMEOUT_MS", 5_000),
+            ai_request_timeout_ms: env_number("AI_REQUEST_TIMEOUT_MS", 60_000),
+            ai_max_retries: env_number("AI_MAX_RETRIES", 3),
+            ai_retry_base_ms: env_number("AI_RETRY_BASE_MS", 500),
+            ai_retry_max_delay_ms: env_number("AI_RETRY_MAX_DELAY_MS", 10_000),
+            ai_breaker_threshold: env_number("AI_BREAKER_THRESHOLD", 5),
+            ai_breaker_cooldown_ms: env_number("AI_BREAKER_COOLDOWN_MS", 30_000),
+            ai_prompt_cost_per_mtok: env_number("AI_PROMPT_COST_PER_MTOK", 0.0),
+            ai_completion_cost_per_mtok: env_number("AI_COMPLETION_COST_PER_MTOK", 0.0),
+            ai_daily_token_budget: env_number("AI_DAILY_TOKEN_BUDGET", 0),
+            ai_monthly_token_budget: env_number("AI_MONTHLY_TOKEN_BUDGET", 0),
+            ai_budget_action: env::var("AI_BUDGET_ACTION").unwrap_or_else(|_| "block".to_string()),
+            ai_downgrade_model: env::var("AI_DOWNGRADE_MODEL").ok(),
+            ai_cache_ttl_secs: env_number("AI_CACHE_TTL_SECS", 86_400),
+            ai_cache_max_entries: env_number("AI_CACHE_MAX_ENTRIES", 1_000),
+            ai_cache_chat: env::var("AI_CACHE_CHAT")
+                .map(|value| value == "true" || value == "1")
+                .unwrap_or(false),
+            retrieval_top_k: env_number("RETRIEVAL_TOP_K", 5),
+            embeddings_base_url: env::var("EMBEDDINGS_BASE_URL").ok().filter(|value| !value.trim().is_empty()),
+            embeddings_model: env::var("EMBEDDINGS_MODEL").unwrap_or_else(|_| "nomic-embed-text".to_string()),
+            ai_redact_pii: env::var("AI_REDACT_PII")
+                .map(|value| value != "false" && value != "0")
+                .unwrap_or(true),
+            ai_max_input_chars: env_number("AI_MAX_INPUT_CHARS", 8_000),
+            ai_blocked_terms: env::var("AI_BLOCKED_TERMS")
+                .map(|value| value.split(',').map(|term| term.trim().to_string()).filter(|term| !term.is_empty()).collect())
+                .unwrap_or_default(),
+            preview_runtime_path,
+            tool_state_max_bytes: env_number("TOOL_STATE_MAX_BYTES", 65_536),
+            tool_state_max_total_bytes: env_number("TOOL_STATE_MAX_TOTAL_BYTES", 1_048_576),
+            flashcard_import_max_bytes: env_number("FLASHCARD_IMPORT_MAX_BYTES", 52_428_800),
+            focus_idle_timeout_secs: env_number("FOCUS_IDLE_TIMEOUT_SECS", 3_600),
+            tool_signing_key: env::var("TOOL_SIGNING_KEY").ok().filter(|value| !value.trim().is_empty()),
+            tool_trusted_keys: env::var("TOOL_TRUSTED_KEYS")
+                .map(|value| value.split(',').map(|key| key.trim().to_string()).filter(|key| !key.is_empty()).collect())
+                .unwrap_or_default(),
+            admin_token: env::var("ADMIN_TOKEN").ok().filter(|value| !value.trim().is_empty()),
         }
     }
 
@@ -57,6 +139,46 @@ impl AppConfig {
             openrouter_model: "openai/gpt-4o-mini".to_string(),
             openrouter_base_url: "https://openrouter.ai/api/v1".to_string(),
             openrouter_referer: None,
+            ai_plan_generation: true,
+            ai_plan_max_attempts: 2,
+            ai_phrase_suggestions: false,
+            ai_connect_timeout_ms: 1_000,
+            ai_request_timeout_ms: 5_000,
+            ai_max_retries: 2,
+            ai_retry_base_ms: 10,
+            ai_retry_max_delay_ms: 100,
+            ai_breaker_threshold: 3,
+            ai_breaker_cooldown_ms: 30_000,
+            ai_prompt_cost_per_mtok: 0.0,
+            ai_completion_cost_per_mtok: 0.0,
+            ai_daily_token_budget: 0,
+            ai_monthly_token_budget: 0,
+            ai_budget_action: "block".to_string(),
+            ai_downgrade_model: None,
+            ai_cache_ttl_secs: 3_600,
+            ai_cache_max_entries: 100,
+            ai_cache_chat: false,
+            retrieval_top_k: 5,
+            embeddings_base_url: None,
+            embeddings_model: "nomic-embed-text".to_string(),
+            ai_redact_pii: true,
+            ai_max_input_chars: 8_000,
+            ai_blocked_terms: Vec::new(),
+            preview_runtime_path: data_dir.join("preview-runtime.js").to_string_lossy().to_string(),
+            tool_state_max_bytes: 65_536,
+            tool_state_max_total_bytes: 1_048_576,
+            flashcard_import_max_bytes: 1_048_576,
+            focus_idle_timeout_secs: 3_600,
+            tool_signing_key: None,
+            tool_trusted_keys: Vec::new(),
+            admin_token: Some("test-admin-token".to_string()),
         }
     }
 }
+
+fn env_number<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse::<T>().ok())
+        .unwrap_or(default)
+}
@@ -1,56 +1,94 @@
 import React, { useState } from 'react';
-import { Box, Button, Paper, TextField, Typography } from '@mui/material';
+import { Box, Button, Paper, TextField, ThemeProvider, Typography, createTheme } from '@mui/material';
 
-const QuadraticCalculator: React.FC = () => {
-  const [a, setA] = useState('');
-  const [b, setB] = useState('');
-  const [c, setC] = useState('');
+const TITLE = {{title}};
+const FORMULA = {{formula}};
+const PRECISION = {{precision}};
+const theme = createTheme({ palette: { mode: {{theme_mode}} } });
+
+type Formula = { inputs: string[]; solve: (values: number[]) => string };
+
+const round = (value: number) => value.toFixed(PRECISION);
+
+const FORMULAS: Record<string, Formula> = {
+  basic: {
+    inputs: ['First number', 'Second number'],
+    solve: ([a, b]) =>
+      `a + b = ${round(a + b)}, a − b = ${round(a - b)}, a × b = ${round(a * b)}, a ÷ b = ${b === 0 ? 'undefined' : round(a / b)}`,
+  },
+  quadratic: {
+    inputs: ['a', 'b', 'c'],
+    solve: ([a, b, c]) => {
+      if (a === 0) {
+        return 'Coefficient "a" cannot be zero for quadratic equation';
+      }
+      const discriminant = b ** 2 - 4 * a * c;
+      if (discriminant < 0) {
+        return 'No real solutions';
+      }
+      const root = Math.sqrt(discriminant);
+      return `x₁ = ${round((-b + root) / (2 * a))}, x₂ = ${round((-b - root) / (2 * a))}`;
+    },
+  },
+  linear: {
+    inputs: ['a', 'b', 'c'],
+    solve: ([a, b, c]) => (a === 0 ? 'Coefficient "a" cannot be zero' : `x = ${round((c - b) / a)}`),
+  },
+  percentage: {
+    inputs: ['Value', 'Percent'],
+    solve: ([value, percent]) => `${percent}% of ${value} = ${round((value * percent) / 100)}`,
+  },
+  average: {
+    inputs: ['Values (comma separated)'],
+    solve: (values) => `Mean = ${round(values.reduce((sum, value) => sum + value, 0) / values.length)}`,
+  },
+  pythagoras: {
+    inputs: ['Leg a', 'Leg b'],
+    solve: ([a, b]) => (a <= 0 || b <= 0 ? 'Legs must be positive' : `Hypotenuse = ${round(Math.hypot(a, b))}`),
+  },
+};
+
+const {{component_name}}: React.FC = () => {
+  const formula = FORMULAS[FORMULA] ?? FORMULAS.basic;
+  const [fields, setFields] = useState<string[]>(formula.inputs.map(() => ''));
   const [solution, setSolution] = useState<string | null>(null);
 
   const solve = () => {
-    const numA = parseFloat(a);
-    const numB = parseFloat(b);
-    const numC = parseFloat(c);
-
-    if (Number.isNaN(numA) || Number.isNaN(numB) || Number.isNaN(numC)) {
+    const values = fields.flatMap((field) => (formula.inputs.length === 1 ? field.split(',') : [field])).map((field) => parseFloat(field));
+    if (values.length === 0 || values.some((value) => Number.isNaN(value))) {
       setSolution('Please enter valid numbers');
       return;
     }
-    if (numA === 0) {
-      setSolution('Coefficient "a" cannot be zero for quadratic equation');
-      return;
-    }
-    const discriminant = numB ** 2 - 4 * numA * numC;
-    if (discriminant < 0) {
-      setSolution('No real solutions');
-      return;
-    }
-    const root = Math.sqrt(discriminant);
-    const x1 = (-numB + root) / (2 * numA);
-    const x2 = (-numB - root) / (2 * numA);
-    setSolution(`x₁ = ${x1.toFixed(2)}, x₂ = ${x2.toFixed(2)}`);
+    setSolution(formula.solve(values));
   };
 
   return (
-    <Paper elevation={2} sx={{ p: 3 }}>
-      <Typography variant="h6" gutterBottom>
-        Quadratic Equation Solver
-      </Typography>
-      <Box sx={{ display: 'flex', gap: 2, flexDirection: 'column' }}>
-        <TextField label="a" value={a} onChange={(event) => setA(event.target.value)} />
-        <TextField label="b" value={b} onChange={(event) => setB(event.target.value)} />
-        <TextField label="c" value={c} onChange={(event) => setC(event.target.value)} />
-        <Button variant="contained" onClick={solve}>
-          Solve
-        </Button>
-        {solution && (
-          <Typography variant="body1" color="primary">
-            {solution}
-          </Typography>
-        )}
-      </Box>
-    </Paper>
+    <ThemeProvider theme={theme}>
+      <Paper elevation={2} sx={{ p: 3, maxWidth: {{max_width}}, mx: 'auto' }}>
+        <Typography variant="h6" gutterBottom>
+          {TITLE}
+        </Typography>
+        <Box sx={{ display: 'flex', gap: 2, flexDirection: 'column' }}>
+          {formula.inputs.map((label, index) => (
+            <TextField
+              key={label}
+              label={label}
+              value={fields[index]}
+              onChange={(event) => setFields(fields.map((field, position) => (position === index ? event.target.value : field)))}
+            />
+          ))}
+          <Button variant="contained" onClick={solve}>
+            Solve
+          </Button>
+          {solution && (
+            <Typography variant="body1" color="primary">
+              {solution}
+            </Typography>
+          )}
+        </Box>
+      </Paper>
+    </ThemeProvider>
   );
 };
 
-export default QuadraticCalculator;
+export default {{component_name}};
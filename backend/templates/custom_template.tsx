@@ -1,15 +1,42 @@
-import React from 'react';
-import { Box, Typography } from '@mui/material';
+import React, { useState } from 'react';
+import { Box, Checkbox, FormControlLabel, Paper, ThemeProvider, Typography, createTheme } from '@mui/material';
+
+const TITLE = {{title}};
+const DESCRIPTION = {{description}};
+const STEPS: string[] = {{steps}};
+const theme = createTheme({ palette: { mode: {{theme_mode}} } });
+
+const {{component_name}}: React.FC = () => {
+  const [done, setDone] = useState<boolean[]>(() => STEPS.map(() => false));
+
+  const toggle = (index: number) => {
+    setDone((values) => values.map((value, position) => (position === index ? !value : value)));
+  };
 
-const CustomTool: React.FC = () => {
   return (
-    <Box sx={{ p: 3 }}>
-      <Typography variant="h6">Custom Study Tool</Typography>
-      <Typography variant="body2" color="text.secondary">
-        This is a placeholder for AI-generated custom tools.
-      </Typography>
-    </Box>
+    <ThemeProvider theme={theme}>
+      <Paper elevation={2} sx={{ p: 3, maxWidth: {{max_width}}, mx: 'auto' }}>
+        <Typography variant="h6" gutterBottom>
+          {TITLE}
+        </Typography>
+        <Typography variant="body2" color="text.secondary" gutterBottom>
+          {DESCRIPTION}
+        </Typography>
+        <Box sx={{ display: 'flex', flexDirection: 'column' }}>
+          {STEPS.map((step, index) => (
+            <FormControlLabel
+              key={step}
+              control={<Checkbox checked={done[index]} onChange={() => toggle(index)} />}
+              label={step}
+            />
+          ))}
+        </Box>
+        <Typography variant="caption" color="text.secondary">
+          {done.filter(Boolean).length} of {STEPS.length} done
+        </Typography>
+      </Paper>
+    </ThemeProvider>
   );
 };
 
-export default CustomTool;
+export default {{component_name}};
@@ -1,8 +1,11 @@
 import React, { useEffect, useState } from 'react';
-import { Box, Button, LinearProgress, Typography } from '@mui/material';
+import { Box, Button, LinearProgress, Paper, ThemeProvider, Typography, createTheme } from '@mui/material';
 
-const PomodoroTimer: React.FC = () => {
-  const INITIAL_DURATION = 1500;
+const TITLE = {{title}};
+const theme = createTheme({ palette: { mode: {{theme_mode}} } });
+
+const {{component_name}}: React.FC = () => {
+  const INITIAL_DURATION = {{duration_seconds}};
   const [seconds, setSeconds] = useState(INITIAL_DURATION);
   const [isActive, setIsActive] = useState(false);
 
@@ -27,16 +30,29 @@ const PomodoroTimer: React.FC = () => {
   const secs = (seconds % 60).toString().padStart(2, '0');
 
   return (
-    <Box sx={{ textAlign: 'center', p: 3 }}>
-      <Typography variant="h4">
-        {minutes}:{secs}
-      </Typography>
-      <LinearProgress variant="determinate" value={progress} sx={{ my: 2 }} />
-      <Button onClick={() => setIsActive((value) => !value)}>
-        {isActive ? 'Pause' : 'Start'}
-      </Button>
-    </Box>
+    <ThemeProvider theme={theme}>
+      <Paper elevation={2} sx={{ textAlign: 'center', p: 3, maxWidth: {{max_width}}, mx: 'auto' }}>
+        <Typography variant="subtitle1">{TITLE}</Typography>
+        <Typography variant="h4">
+          {minutes}:{secs}
+        </Typography>
+        <LinearProgress variant="determinate" value={progress} sx={{ my: 2 }} />
+        <Box sx={{ display: 'flex', gap: 2, justifyContent: 'center' }}>
+          <Button onClick={() => setIsActive((value) => !value)}>
+            {isActive ? 'Pause' : 'Start'}
+          </Button>
+          <Button
+            onClick={() => {
+              setIsActive(false);
+              setSeconds(INITIAL_DURATION);
+            }}
+          >
+            Reset
+          </Button>
+        </Box>
+      </Paper>
+    </ThemeProvider>
   );
 };
 
-export default PomodoroTimer;
+export default {{component_name}};
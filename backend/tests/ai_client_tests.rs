@@ -0,0 +1,604 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use actix_web::{App, HttpResponse, HttpServer, test, web};
+
+use backend::{AppState, api};
+use backend::models::ai::AiCall;
+use backend::utils::config::AppConfig;
+use uuid::Uuid;
+
+#[derive(Clone)]
+struct MockReply {
+    status: u16,
+    retry_after: Option<&'static str>,
+    delay_ms: u64,
+    content: &'static str,
+}
+
+impl MockReply {
+    fn ok(content: &'static str) -> Self {
+        Self { status: 200, retry_after: None, delay_ms: 0, content }
+    }
+
+    fn error(status: u16) -> Self {
+        Self { status, retry_after: None, delay_ms: 0, content: "" }
+    }
+}
+
+struct MockState {
+    replies: Vec<MockReply>,
+    hits: Arc<AtomicUsize>,
+    bodies: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+async fn completions(state: web::Data<MockState>, body: web::Json<serde_json::Value>) -> HttpResponse {
+    let index = state.hits.fetch_add(1, Ordering::SeqCst);
+    state.bodies.lock().unwrap().push(body.0.clone());
+    let reply = state.replies.get(index).or(state.replies.last()).cloned().unwrap();
+    if reply.delay_ms > 0 {
+        actix_web::rt::time::sleep(Duration::from_millis(reply.delay_ms)).await;
+    }
+    let mut response = HttpResponse::build(actix_web::http::StatusCode::from_u16(reply.status).unwrap());
+    if let Some(retry_after) = reply.retry_after {
+        response.insert_header(("Retry-After", retry_after));
+    }
+    if reply.status == 200 {
+        response.json(serde_json::json!({
+            "model": body["model"],
+            "choices": [{"message": {"role": "assistant", "content": reply.content}}],
+            "usage": {"prompt_tokens": 120, "completion_tokens": 30, "total_tokens": 150}
+        }))
+    } else {
+        response.json(serde_json::json!({"error": {"message": "mock failure"}}))
+    }
+}
+
+fn start_mock(replies: Vec<MockReply>) -> (AppConfig, Arc<AtomicUsize>) {
+    let (config, hits, _) = start_recording_mock(replies);
+    (config, hits)
+}
+
+fn start_recording_mock(replies: Vec<MockReply>) -> (AppConfig, Arc<AtomicUsize>, Arc<Mutex<Vec<serde_json::Value>>>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let state = web::Data::new(MockState { replies, hits: hits.clone(), bodies: bodies.clone() });
+    let server = HttpServer::new(move || App::new().app_data(state.clone()).route("/chat/completions", web::post().to(completions)))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let address = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+
+    let mut config = AppConfig::for_test();
+    config.ai_provider = "openrouter".to_string();
+    config.openrouter_api_key = Some("test-key".to_string());
+    config.openrouter_base_url = format!("http://{}", address);
+    config.ai_request_timeout_ms = 300;
+    (config, hits, bodies)
+}
+
+async fn chat(config: AppConfig) -> (serde_json::Value, web::Data<AppState>) {
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let resp = chat_with(&state).await;
+    (resp, state)
+}
+
+async fn chat_with(state: &web::Data<AppState>) -> serde_json::Value {
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let req = test::TestRequest::post()
+        .uri("/api/ai/chat")
+        .set_json(serde_json::json!({"user_id": "00000000-0000-0000-0000-000000000010", "message": "Help me plan"}))
+        .to_request();
+    test::call_and_read_body_json(&app, req).await
+}
+
+#[actix_web::test]
+async fn retries_transient_failures_and_honors_retry_after() {
+    let (config, hits) = start_mock(vec![
+        MockReply { retry_after: Some("0"), ..MockReply::error(429) },
+        MockReply::error(503),
+        MockReply::ok("Study calculus first."),
+    ]);
+
+    let (resp, _) = chat(config).await;
+
+    assert_eq!(resp["data"]["response"], "Study calculus first.");
+    assert_eq!(resp["data"]["provider"], "openrouter");
+    assert_eq!(resp["data"]["model"], "openai/gpt-4o-mini");
+    assert!(resp["data"].get("fallback").is_none());
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[actix_web::test]
+async fn reports_fallback_reason_without_retrying_client_errors() {
+    let (config, hits) = start_mock(vec![MockReply::error(401)]);
+
+    let (resp, _) = chat(config).await;
+
+    assert_eq!(resp["data"]["provider"], "local");
+    assert_eq!(resp["data"]["fallback"]["code"], "AI_UPSTREAM_ERROR");
+    assert!(resp["data"]["fallback"]["message"].as_str().unwrap().contains("mock failure"));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[actix_web::test]
+async fn times_out_slow_responses() {
+    let (mut config, _) = start_mock(vec![MockReply { delay_ms: 1_000, ..MockReply::ok("too late") }]);
+    config.ai_max_retries = 0;
+
+    let (resp, _) = chat(config).await;
+
+    assert_eq!(resp["data"]["fallback"]["code"], "AI_TIMEOUT");
+}
+
+#[actix_web::test]
+async fn circuit_opens_after_repeated_failures() {
+    let (mut config, hits) = start_mock(vec![MockReply::error(500)]);
+    config.ai_max_retries = 0;
+    config.ai_breaker_threshold = 2;
+
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let messages = [serde_json::json!({"role": "user", "content": "hi"})];
+    let mut call = AiCall::new(Uuid::nil(), "chat");
+    for _ in 0..2 {
+        let error = state.ai.chat_completion(&messages, &mut call).await.unwrap_err();
+        assert_eq!(error.code(), "AI_UPSTREAM_ERROR");
+    }
+    let error = state.ai.chat_completion(&messages, &mut call).await.unwrap_err();
+
+    assert_eq!(error.code(), "AI_CIRCUIT_OPEN");
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
+
+#[actix_web::test]
+async fn a_dropped_probe_does_not_keep_the_circuit_open() {
+    let slow = MockReply { delay_ms: 200, ..MockReply::ok("Too late.") };
+    let (mut config, hits) = start_mock(vec![MockReply::error(500), slow, MockReply::ok("Back again.")]);
+    config.ai_max_retries = 0;
+    config.ai_breaker_threshold = 1;
+    config.ai_breaker_cooldown_ms = 20;
+
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let messages = [serde_json::json!({"role": "user", "content": "hi"})];
+    let mut call = AiCall::new(Uuid::nil(), "chat");
+    assert_eq!(state.ai.chat_completion(&messages, &mut call).await.unwrap_err().code(), "AI_UPSTREAM_ERROR");
+    actix_web::rt::time::sleep(Duration::from_millis(30)).await;
+
+    let probe = actix_web::rt::time::timeout(Duration::from_millis(50), state.ai.chat_completion(&messages, &mut call)).await;
+    assert!(probe.is_err());
+    let completion = state.ai.chat_completion(&messages, &mut call).await.unwrap();
+    assert_eq!(completion.content, "Back again.");
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[actix_web::test]
+async fn records_token_usage_and_enforces_budgets() {
+    let (config, hits) = start_mock(vec![MockReply::ok("Review limits.")]);
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+
+    let resp = chat_with(&state).await;
+    assert_eq!(resp["data"]["provider"], "openrouter");
+
+    let req = test::TestRequest::put()
+        .uri("/api/ai/budget")
+        .insert_header(("X-Admin-Token", "test-admin-token"))
+        .set_json(serde_json::json!({
+            "user_id": "00000000-0000-0000-0000-000000000010",
+            "daily_token_limit": 100,
+            "monthly_token_limit": null,
+            "on_exceed": "downgrade",
+            "downgrade_model": "meta/small-model"
+        }))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["used_today"], 150);
+
+    let resp = chat_with(&state).await;
+    assert_eq!(resp["data"]["model"], "meta/small-model");
+
+    let req = test::TestRequest::put()
+        .uri("/api/ai/budget")
+        .insert_header(("X-Admin-Token", "test-admin-token"))
+        .set_json(serde_json::json!({
+            "user_id": "00000000-0000-0000-0000-000000000010",
+            "daily_token_limit": 100,
+            "monthly_token_limit": null,
+            "on_exceed": "block",
+            "downgrade_model": null
+        }))
+        .to_request();
+    let _: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let resp = chat_with(&state).await;
+    assert_eq!(resp["data"]["fallback"]["code"], "AI_BUDGET_EXCEEDED");
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    let req = test::TestRequest::get()
+        .uri("/api/ai/usage?user_id=00000000-0000-0000-0000-000000000010")
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["totals"]["requests"], 3);
+    assert_eq!(resp["data"]["by_feature"][0]["key"], "chat");
+    assert!(resp["data"]["by_model"].as_array().unwrap().iter().any(|row| row["key"] == "meta/small-model" && row["total_tokens"] == 150));
+
+    let req = test::TestRequest::get()
+        .uri("/api/users/00000000-0000-0000-0000-000000000010/stats")
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["ai_interactions"], 3);
+
+    let budget = serde_json::json!({
+        "user_id": "00000000-0000-0000-0000-000000000010",
+        "daily_token_limit": null,
+        "monthly_token_limit": null,
+        "on_exceed": "block",
+        "downgrade_model": null
+    });
+    let req = test::TestRequest::put()
+        .uri("/api/ai/budget")
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+        .set_json(&budget)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "ADMIN_REQUIRED");
+
+    let req = test::TestRequest::get()
+        .uri("/api/ai/budget?user_id=00000000-0000-0000-0000-000000000010")
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000011"))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 403);
+    let req = test::TestRequest::get()
+        .uri("/api/ai/usage?user_id=00000000-0000-0000-0000-000000000010")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 401);
+}
+
+#[actix_web::test]
+async fn prompt_overrides_are_versioned_and_recorded() {
+    let (config, _) = start_mock(vec![MockReply::ok("Start with limits.")]);
+    let prompts_dir = std::path::Path::new(&config.data_dir).join("prompts");
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+
+    std::fs::create_dir_all(&prompts_dir).unwrap();
+    std::fs::write(prompts_dir.join("chat.txt"), "version: 2\n[system]\nYou are a terse tutor.\n[user]\n{{message}}\n").unwrap();
+    std::fs::write(prompts_dir.join("broken.txt"), "[user]\nno version\n").unwrap();
+    let req = test::TestRequest::post().uri("/api/ai/prompts/reload").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 403);
+    let req = test::TestRequest::post()
+        .uri("/api/ai/prompts/reload")
+        .insert_header(("X-Admin-Token", "test-admin-token"))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["errors"].as_array().unwrap().len(), 1);
+    let chat_prompt = resp["data"]["prompts"].as_array().unwrap().iter().find(|prompt| prompt["name"] == "chat").unwrap();
+    assert_eq!(chat_prompt["version"], "2");
+    assert_eq!(chat_prompt["system"], "You are a terse tutor.");
+
+    let resp = chat_with(&state).await;
+    assert_eq!(resp["data"]["prompt_version"], "chat@2");
+
+    let conn = state.db.lock().unwrap();
+    let stored: String = conn.query_row("SELECT prompt_version FROM ai_conversations", [], |row| row.get(0)).unwrap();
+    assert_eq!(stored, "chat@2");
+}
+
+#[actix_web::test]
+async fn rendering_an_unknown_prompt_is_an_error() {
+    let state = AppState::new(AppConfig::for_test()).unwrap();
+    assert!(state.prompts.render("chat", &[("sources", ""), ("message", "hi")]).is_ok());
+    let error = state.prompts.render("missing", &[]).unwrap_err();
+    assert_eq!(error.code, "PROMPT_NOT_FOUND");
+}
+
+#[actix_web::test]
+async fn caches_identical_requests_unless_opted_out() {
+    let (mut config, hits) = start_mock(vec![MockReply::ok("Cached answer.")]);
+    config.retrieval_top_k = 0;
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let send = |message: &str, cache: bool| {
+        test::TestRequest::post()
+            .uri("/api/ai/chat")
+            .set_json(serde_json::json!({"user_id": "00000000-0000-0000-0000-000000000010", "message": message, "cache": cache}))
+            .to_request()
+    };
+
+    let first: serde_json::Value = test::call_and_read_body_json(&app, send("Explain   limits", true)).await;
+    let second: serde_json::Value = test::call_and_read_body_json(&app, send("Explain limits", true)).await;
+    assert_eq!(first["data"]["cached"], false);
+    assert_eq!(second["data"]["cached"], true);
+    assert_eq!(second["data"]["response"], "Cached answer.");
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let opted_out: serde_json::Value = test::call_and_read_body_json(&app, send("Explain limits", false)).await;
+    assert_eq!(opted_out["data"]["cached"], false);
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    let req = test::TestRequest::get()
+        .uri("/api/ai/usage?user_id=00000000-0000-0000-0000-000000000010")
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["totals"]["total_tokens"], 300);
+
+    let req = test::TestRequest::put()
+        .uri("/api/ai/budget")
+        .insert_header(("X-Admin-Token", "test-admin-token"))
+        .set_json(serde_json::json!({
+            "user_id": "00000000-0000-0000-0000-000000000010",
+            "daily_token_limit": 100,
+            "monthly_token_limit": null,
+            "on_exceed": "block",
+            "downgrade_model": null
+        }))
+        .to_request();
+    let _: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let blocked: serde_json::Value = test::call_and_read_body_json(&app, send("Explain limits", true)).await;
+    assert_eq!(blocked["data"]["cached"], false);
+    assert_eq!(blocked["data"]["fallback"]["code"], "AI_BUDGET_EXCEEDED");
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
+
+#[actix_web::test]
+async fn redacts_personal_data_and_blocks_unsafe_replies() {
+    let (mut config, _, bodies) = start_recording_mock(vec![
+        MockReply::ok("Happy to help."),
+        MockReply::ok("Use this key: sk-abcdefghijklmnopqrstuvwxyz0123"),
+    ]);
+    config.retrieval_top_k = 0;
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let send = |message: &str| {
+        test::TestRequest::post()
+            .uri("/api/ai/chat")
+            .set_json(serde_json::json!({"user_id": "00000000-0000-0000-0000-000000000010", "message": message}))
+            .to_request()
+    };
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, send("Mail jane.doe@example.com or call +1 555-123-4567")).await;
+    assert_eq!(resp["data"]["response"], "Happy to help.");
+    let sent = bodies.lock().unwrap()[0]["messages"][1]["content"].as_str().unwrap().to_string();
+    assert!(sent.contains("Mail [email] or call [phone]"), "{}", sent);
+    assert!(!sent.contains("example.com"));
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, send("Which API key should I use?\n```\nconst support = 'help@example.com';\n```")).await;
+    assert_eq!(resp["data"]["provider"], "local");
+    assert_eq!(resp["data"]["fallback"]["code"], "POLICY_OUTPUT_BLOCKED");
+    assert!(!resp["data"]["response"].as_str().unwrap().contains("sk-"));
+    let sent = bodies.lock().unwrap()[1]["messages"][1]["content"].as_str().unwrap().to_string();
+    assert!(sent.contains("const support = 'help@example.com';"), "{}", sent);
+
+    let conn = state.db.lock().unwrap();
+    let stored: Vec<String> = conn
+        .prepare("SELECT response FROM ai_conversations")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(stored.iter().all(|response| !response.contains("sk-")));
+    let cached: i64 = conn.query_row("SELECT COUNT(*) FROM ai_response_cache", [], |row| row.get(0)).unwrap();
+    assert_eq!(cached, 0);
+}
+
+#[actix_web::test]
+async fn tool_generation_uses_the_model_and_falls_back_to_templates() {
+    let (config, hits) = start_mock(vec![
+        MockReply::ok(r#"{"name": "Chain Rule Drill", "description": "Five chain rule cards.", "component_code": "const ChainRuleDrill = () => null;\nexport default ChainRuleDrill;"}"#),
+        MockReply::ok("const Broken = () => { eval('x'); };\nexport default Broken;"),
+    ]);
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let send = |requirements: &str| {
+        test::TestRequest::post()
+            .uri("/api/tools/generate")
+            .set_json(serde_json::json!({
+                "user_id": "00000000-0000-0000-0000-000000000010",
+                "tool_type": "flashcard",
+                "context": "Chain rule",
+                "requirements": requirements,
+                "ui_preferences": {"theme": "dark", "size": "large"}
+            }))
+            .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+            .to_request()
+    };
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, send("Five cards")).await;
+    assert_eq!(resp["data"]["name"], "Chain Rule Drill");
+    assert_eq!(resp["data"]["metadata"]["prompt_version"], "tool_generation@2");
+    assert!(resp["data"]["component_code"].as_str().unwrap().contains("export default ChainRuleDrill;"));
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, send("Three cards")).await;
+    assert_eq!(resp["data"]["name"], "Chain rule Flashcards");
+    assert_eq!(resp["data"]["metadata"]["ai_model"], "local");
+    assert!(resp["data"]["component_code"].as_str().unwrap().contains("maxWidth: 720"));
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, send("Three cards")).await;
+    assert_eq!(resp["data"]["metadata"]["ai_model"], "local");
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    let conn = state.db.lock().unwrap();
+    let calls: i64 = conn.query_row("SELECT COUNT(*) FROM ai_usage WHERE feature = 'tool'", [], |row| row.get(0)).unwrap();
+    assert_eq!(calls, 3);
+    let cached: i64 = conn.query_row("SELECT COUNT(*) FROM ai_response_cache", [], |row| row.get(0)).unwrap();
+    assert_eq!(cached, 1);
+}
+
+#[actix_web::test]
+async fn tool_edits_bump_versions_and_reject_invalid_output() {
+    let (config, _) = start_mock(vec![
+        MockReply::ok(r#"{"name": "Drill", "component_code": "const Drill = () => null;\nexport default Drill;"}"#),
+        MockReply::ok(r#"{"change_type": "minor", "changes_summary": "Added a shuffle button.", "component_code": "const Drill = () => { const [n, setN] = useState(0); return null; };\nexport default Drill;"}"#),
+        MockReply::ok(r#"{"change_type": "patch", "changes_summary": "Loads cards.", "component_code": "const Drill = () => { fetch('/cards'); return null; };\nexport default Drill;"}"#),
+        MockReply::ok(r#"{"change_type": "patch", "changes_summary": "Broken.", "component_code": "const Drill = () => { return null;\nexport default Drill;"}"#),
+    ]);
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let req = test::TestRequest::post()
+        .uri("/api/tools/generate")
+        .set_json(serde_json::json!({"user_id": "00000000-0000-0000-0000-000000000010", "tool_type": "custom", "context": "Drills", "requirements": "Practice"}))
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let tool_id = resp["data"]["tool_id"].as_str().unwrap().to_string();
+    let edit = |instruction: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/tools/{}/edit", tool_id))
+            .set_json(serde_json::json!({"edit_instruction": instruction, "current_state": {"index": 2}}))
+            .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+            .to_request()
+    };
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, edit("Add a shuffle button")).await;
+    assert_eq!(resp["data"]["previous_version"], "1.0.0");
+    assert_eq!(resp["data"]["version"], "1.1.0");
+    assert_eq!(resp["data"]["change_type"], "minor");
+    assert_eq!(resp["data"]["changes_summary"], "Added a shuffle button.");
+    assert_eq!(resp["data"]["prompt_version"], "tool_editing@3");
+
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, edit("Load cards from the server")).await;
+    assert_eq!(resp["data"]["version"], "1.1.1");
+    assert_eq!(resp["data"]["diagnostics"][0]["severity"], "warning");
+    assert_eq!(resp["data"]["diagnostics"][0]["code"], "fetch");
+
+    let resp = test::call_service(&app, edit("Tidy up")).await;
+    assert_eq!(resp.status(), 422);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "TOOL_EDIT_INVALID");
+    assert_eq!(body["error"]["details"]["diagnostics"][0]["code"], "syntax_error");
+    assert_eq!(body["error"]["details"]["diagnostics"][0]["severity"], "error");
+
+    let req = test::TestRequest::get().uri(&format!("/api/tools/{}", tool_id)).insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010")).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["metadata"]["version"], "1.1.1");
+    assert!(resp["data"]["component_code"].as_str().unwrap().contains("fetch('/cards')"));
+}
+
+#[actix_web::test]
+async fn tool_edits_can_reference_stored_state() {
+    let (config, _, bodies) = start_recording_mock(vec![
+        MockReply::ok(r#"{"name": "Deck", "component_code": "const Deck = () => null;\nexport default Deck;"}"#),
+        MockReply::ok(r#"{"change_type": "patch", "changes_summary": "Shows the deck size.", "component_code": "const Deck = () => <p>Deck</p>;\nexport default Deck;"}"#),
+    ]);
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let user_id = "00000000-0000-0000-0000-000000000042";
+    let req = test::TestRequest::post()
+        .uri("/api/tools/generate")
+        .set_json(serde_json::json!({"user_id": user_id, "tool_type": "flashcard", "context": "Verbs", "requirements": "Practice"}))
+        .insert_header(("X-User-Id", user_id))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let tool_id = resp["data"]["tool_id"].as_str().unwrap().to_string();
+
+    for (key, value) in [("deck", serde_json::json!({"cards": [{"front": "ser", "back": "to be"}]})), ("settings", serde_json::json!({"shuffle": true}))] {
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/tools/{}/state/{}", tool_id, key))
+            .set_json(serde_json::json!({"user_id": user_id, "value": value}))
+            .insert_header(("X-User-Id", user_id))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"]["revision"], 1);
+    }
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/tools/{}/edit", tool_id))
+        .set_json(serde_json::json!({"edit_instruction": "Show how many cards are left", "state_ref": {"user_id": user_id, "keys": ["deck"]}}))
+        .insert_header(("X-User-Id", user_id))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["version"], "1.0.1");
+
+    let bodies = bodies.lock().unwrap();
+    let prompt = bodies[1]["messages"][1]["content"].as_str().unwrap();
+    assert!(prompt.contains("\"deck\"") && prompt.contains("to be"), "{}", prompt);
+    assert!(!prompt.contains("shuffle"));
+}
+
+#[actix_web::test]
+async fn tool_history_supports_diff_and_rollback() {
+    let (config, _) = start_mock(vec![
+        MockReply::ok(r#"{"name": "Drill", "component_code": "const Drill = () => null;\nexport default Drill;"}"#),
+        MockReply::ok(r#"{"change_type": "patch", "changes_summary": "Renders a heading.", "component_code": "const Drill = () => <h1>Drill</h1>;\nexport default Drill;"}"#),
+    ]);
+    let state = web::Data::new(AppState::new(config).unwrap());
+    let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+    let req = test::TestRequest::post()
+        .uri("/api/tools/generate")
+        .set_json(serde_json::json!({"user_id": "00000000-0000-0000-0000-000000000010", "tool_type": "custom", "context": "Drills", "requirements": "Practice"}))
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let tool_id = resp["data"]["tool_id"].as_str().unwrap().to_string();
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/tools/{}/edit", tool_id))
+        .set_json(serde_json::json!({"edit_instruction": "Show a heading"}))
+        .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+        .to_request();
+    let _: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::get().uri(&format!("/api/tools/{}/versions", tool_id)).insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010")).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["current_version"], "1.0.1");
+    let versions = resp["data"]["versions"].as_array().unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0]["instruction"], "Show a heading");
+    assert_eq!(versions[1]["change_type"], "initial");
+
+    let req = test::TestRequest::get().uri(&format!("/api/tools/{}/diff?from=1.0.0", tool_id)).insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010")).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["to"], "1.0.1");
+    assert_eq!((resp["data"]["additions"].as_u64(), resp["data"]["deletions"].as_u64()), (Some(1), Some(1)));
+    let diff = resp["data"]["diff"].as_str().unwrap();
+    assert!(diff.starts_with("--- v1.0.0\n+++ v1.0.1\n"));
+    assert!(diff.contains("-const Drill = () => null;\n+const Drill = () => <h1>Drill</h1>;"));
+
+    let rollback = |version: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/tools/{}/rollback", tool_id))
+            .set_json(serde_json::json!({"version": version}))
+            .insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010"))
+            .to_request()
+    };
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, rollback("1.0.0")).await;
+    assert_eq!(resp["data"]["version"], "1.0.2");
+    assert_eq!(resp["data"]["restored_from"], "1.0.0");
+    assert!(resp["data"]["component_code"].as_str().unwrap().contains("() => null"));
+    assert_eq!(test::call_service(&app, rollback("9.9.9")).await.status(), 404);
+    assert_eq!(test::call_service(&app, rollback("1.0.2")).await.status(), 400);
+
+    let req = test::TestRequest::get().uri(&format!("/api/tools/{}/versions", tool_id)).insert_header(("X-User-Id", "00000000-0000-0000-0000-000000000010")).to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["data"]["versions"].as_array().unwrap().len(), 3);
+    assert_eq!(resp["data"]["versions"][0]["change_type"], "rollback");
+}
+
+#[actix_web::test]
+async fn plan_fallbacks_say_whether_the_provider_failed_or_the_plan_was_invalid() {
+    let payload = serde_json::json!({
+        "user_id": "00000000-0000-0000-0000-000000000010",
+        "subjects": ["Math"],
+        "goals": "Exam prep",
+        "study_hours_per_day": 2,
+        "difficulty_level": "beginner",
+        "start_date": "2026-02-02"
+    });
+    for (reply, code, requests) in [(MockReply::ok("Study math every day."), "AI_PLAN_INVALID", 2), (MockReply::error(500), "AI_UPSTREAM_ERROR", 1)] {
+        let (mut config, hits) = start_mock(vec![reply]);
+        config.ai_max_retries = 0;
+        config.ai_cache_ttl_secs = 0;
+        let state = web::Data::new(AppState::new(config).unwrap());
+        let app = test::init_service(App::new().app_data(state.clone()).service(web::scope("/api").configure(api::configure))).await;
+        let req = test::TestRequest::post().uri("/api/plans/generate").set_json(&payload).to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"]["fallback"]["code"], code);
+        assert_eq!(resp["data"]["prompt_version"], serde_json::Value::Null);
+        assert_eq!(hits.load(Ordering::SeqCst), requests);
+    }
+}
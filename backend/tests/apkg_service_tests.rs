@@ -0,0 +1,185 @@
+use std::io::{Cursor, Write};
+
+use backend::models::flashcard::{Card, CardReview, CardSchedule, Deck};
+use backend::services::apkg_service::{ApkgError, ApkgService};
+use backend::services::flashcard_service::{FlashcardService, MAX_INTERVAL_DAYS};
+use chrono::{NaiveDate, TimeZone, Utc};
+use rusqlite::{Connection, params};
+use uuid::Uuid;
+
+fn legacy_package() -> Vec<u8> {
+    legacy_package_with(|_| {})
+}
+
+fn legacy_package_with(tweak: impl FnOnce(&Connection)) -> Vec<u8> {
+    let path = std::env::temp_dir().join(format!("apkg-test-{}.sqlite", Uuid::new_v4()));
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE col (id INTEGER PRIMARY KEY, crt INTEGER, models TEXT, decks TEXT);
+         CREATE TABLE notes (id INTEGER PRIMARY KEY, mid INTEGER, tags TEXT, flds TEXT);
+         CREATE TABLE cards (id INTEGER PRIMARY KEY, nid INTEGER, did INTEGER, ord INTEGER, type INTEGER, queue INTEGER, due INTEGER,
+                             ivl INTEGER, factor INTEGER, reps INTEGER, lapses INTEGER, odue INTEGER, odid INTEGER);
+         CREATE TABLE revlog (id INTEGER PRIMARY KEY, cid INTEGER, ease INTEGER, ivl INTEGER, factor INTEGER);",
+    )
+    .unwrap();
+    let crt = Utc.with_ymd_and_hms(2026, 1, 1, 4, 0, 0).unwrap().timestamp();
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?3)",
+        params![
+            crt,
+            serde_json::json!({"10": {"type": 0}, "20": {"type": 1}}).to_string(),
+            serde_json::json!({"1": {"name": "Default"}, "100": {"name": "Biology::Cells"}}).to_string()
+        ],
+    )
+    .unwrap();
+    let notes: [(i64, i64, &str, &str); 4] = [
+        (1, 10, " Organelles exam ", "What is the <b>powerhouse</b> of the cell?\u{1f}Mitochondria<br><img src=\"mito.png\">"),
+        (2, 20, "", "{{c1::Ribosomes}} build {{c2::proteins::molecule}}\u{1f}Found in the cytoplasm"),
+        (3, 10, "", "\u{1f}Empty front"),
+        (4, 10, "", "Osmosis &amp; diffusion\u{1f}Passive transport"),
+    ];
+    for (id, mid, tags, flds) in notes {
+        conn.execute("INSERT INTO notes VALUES (?1, ?2, ?3, ?4)", params![id, mid, tags, flds]).unwrap();
+    }
+    let cards: [[i64; 13]; 5] = [
+        [11, 1, 100, 0, 2, 2, 40, 15, 2300, 5, 1, 0, 0],
+        [12, 1, 100, 1, 0, 0, 7, 0, 0, 0, 0, 0, 0],
+        [21, 2, 999, 0, 2, 2, 0, 1, 2500, 1, 0, 45, 100],
+        [31, 3, 100, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0],
+        [41, 4, 100, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0],
+    ];
+    for card in cards {
+        conn.execute("INSERT INTO cards VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)", rusqlite::params_from_iter(card))
+            .unwrap();
+    }
+    let reviewed = Utc.with_ymd_and_hms(2026, 1, 26, 8, 0, 0).unwrap().timestamp_millis();
+    conn.execute("INSERT INTO revlog VALUES (?1, 11, 1, -600, 2500)", [reviewed - 86_400_000]).unwrap();
+    conn.execute("INSERT INTO revlog VALUES (?1, 11, 3, 15, 2300)", [reviewed]).unwrap();
+    tweak(&conn);
+    drop(conn);
+    let collection = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("collection.anki2", options).unwrap();
+    zip.write_all(&collection).unwrap();
+    zip.start_file("media", options).unwrap();
+    zip.write_all(br#"{"0": "mito.png"}"#).unwrap();
+    zip.start_file("0", options).unwrap();
+    zip.write_all(b"png").unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn legacy_collections_map_fields_and_keep_intervals() {
+    let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+    let package = ApkgService::read(&legacy_package(), today, 1 << 20).unwrap();
+
+    assert_eq!(package.media_files, 1);
+    assert_eq!(package.skipped_notes, 1);
+    assert_eq!(package.decks.len(), 1);
+    let deck = &package.decks[0];
+    assert_eq!(deck.name, "Biology::Cells");
+    assert_eq!(deck.cards.len(), 3);
+
+    let mito = &deck.cards[0];
+    assert_eq!(mito.front, "What is the powerhouse of the cell?");
+    assert_eq!(mito.back, "Mitochondria\n[image: mito.png]");
+    assert_eq!(mito.tags, vec!["organelles", "exam"]);
+    assert_eq!(mito.schedule.interval_days, 15);
+    assert_eq!(mito.schedule.ease_factor, 2.3);
+    assert_eq!(mito.schedule.repetitions, 4);
+    assert_eq!(mito.schedule.due_date, "2026-02-10");
+    assert_eq!(mito.schedule.last_reviewed, Some(Utc.with_ymd_and_hms(2026, 1, 26, 8, 0, 0).unwrap()));
+    assert_eq!(mito.reviews.iter().map(|review| (review.0, review.2)).collect::<Vec<_>>(), vec![(1, 0), (4, 15)]);
+
+    let cloze = &deck.cards[1];
+    assert_eq!(cloze.front, "[...] build [molecule]");
+    assert_eq!(cloze.back, "Ribosomes build proteins\nFound in the cytoplasm");
+    assert_eq!(cloze.schedule.due_date, "2026-02-15");
+
+    let fresh = &deck.cards[2];
+    assert_eq!(fresh.front, "Osmosis & diffusion");
+    assert_eq!(fresh.schedule, FlashcardService::new_schedule(today));
+}
+
+#[test]
+fn out_of_range_schedules_are_clamped_or_rejected() {
+    let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+    let huge_interval = legacy_package_with(|conn| {
+        conn.execute_batch("UPDATE cards SET ivl = 1000000000 WHERE id = 11; UPDATE revlog SET ivl = 1000000000 WHERE ease = 3;").unwrap();
+    });
+    let package = ApkgService::read(&huge_interval, today, 1 << 20).unwrap();
+    let mito = &package.decks[0].cards[0];
+    assert_eq!(mito.schedule.interval_days, MAX_INTERVAL_DAYS);
+    assert_eq!(mito.reviews[1].2, MAX_INTERVAL_DAYS);
+
+    let huge_due = legacy_package_with(|conn| {
+        conn.execute("UPDATE cards SET due = ?1, queue = 3 WHERE id = 11", [i64::MAX / 2]).unwrap();
+    });
+    let Err(error) = ApkgService::read(&huge_due, today, 1 << 20) else {
+        panic!("an unrepresentable due date must be rejected");
+    };
+    assert!(matches!(error, ApkgError::BadSchedule { card_id: 11, .. }));
+    assert_eq!(error.code(), "INVALID_APKG_SCHEDULE");
+}
+
+#[test]
+fn exported_decks_round_trip_and_new_formats_are_rejected() {
+    let created = Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+    let deck = Deck {
+        deck_id: Uuid::new_v4(),
+        user_id: Uuid::nil(),
+        name: "Spanish verbs".to_string(),
+        subject: "Spanish".to_string(),
+        task_id: None,
+        created_at: created,
+    };
+    let reviewed = FlashcardService::schedule(
+        &FlashcardService::schedule(&FlashcardService::new_schedule(created.date_naive()), 5, created),
+        4,
+        Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap(),
+    );
+    let card = |front: &str, back: &str, schedule: CardSchedule| Card {
+        card_id: Uuid::new_v4(),
+        deck_id: deck.deck_id,
+        front: front.to_string(),
+        back: back.to_string(),
+        tags: vec!["irregular".to_string()],
+        schedule,
+        created_at: created,
+    };
+    let cards = vec![card("tener <yo>", "tengo\nto have", reviewed.clone()), card("ir", "voy", FlashcardService::new_schedule(created.date_naive()))];
+    let reviews = vec![CardReview {
+        review_id: Uuid::new_v4(),
+        card_id: cards[0].card_id,
+        user_id: Uuid::nil(),
+        quality: 4,
+        ease_factor: reviewed.ease_factor,
+        interval_days: reviewed.interval_days,
+        reviewed_at: Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap(),
+    }];
+
+    let bytes = ApkgService::write(&deck, &cards, &reviews).unwrap();
+    let today = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+    let package = ApkgService::read(&bytes, today, 1 << 20).unwrap();
+
+    assert_eq!(package.decks.len(), 1);
+    assert_eq!(package.decks[0].name, "Spanish verbs");
+    let imported = &package.decks[0].cards;
+    assert_eq!((imported[0].front.as_str(), imported[0].back.as_str()), ("tener <yo>", "tengo\nto have"));
+    assert_eq!(imported[0].tags, vec!["irregular"]);
+    assert_eq!(imported[0].schedule.due_date, reviewed.due_date);
+    assert_eq!(imported[0].schedule.interval_days, 6);
+    assert_eq!(imported[0].schedule.ease_factor, reviewed.ease_factor);
+    assert_eq!(imported[0].reviews.len(), 1);
+    assert_eq!(imported[1].schedule.repetitions, 0);
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("collection.anki21b", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"zstd").unwrap();
+    let modern = zip.finish().unwrap().into_inner();
+    assert!(matches!(ApkgService::read(&modern, today, 1 << 20), Err(ApkgError::Unsupported)));
+    assert!(matches!(ApkgService::read(b"not a zip", today, 1 << 20), Err(ApkgError::Invalid(_))));
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use actix_web::{App, HttpResponse, HttpServer, web};
+use backend::AppState;
+use backend::models::ai::AiCall;
+use backend::models::plan::PlanGenerateRequest;
+use backend::services::eval_service::EvalService;
+use backend::services::plan_service::PlanService;
+use backend::services::response_cache::ResponseCache;
+use backend::utils::config::AppConfig;
+use uuid::Uuid;
+
+type Replies = Arc<Mutex<VecDeque<String>>>;
+
+async fn replay(replies: web::Data<Replies>, body: web::Json<serde_json::Value>) -> HttpResponse {
+    match replies.lock().unwrap().pop_front() {
+        Some(content) => HttpResponse::Ok().json(serde_json::json!({
+            "model": body["model"],
+            "choices": [{"message": {"role": "assistant", "content": content}}],
+        })),
+        None => HttpResponse::BadRequest().json(serde_json::json!({"error": {"message": "no recorded response"}})),
+    }
+}
+
+#[actix_web::test]
+async fn recorded_corpus_matches_baseline() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("eval");
+    let cases = EvalService::load_corpus(&root.join("corpus")).unwrap();
+    assert!(!cases.is_empty());
+
+    let replies: Replies = Arc::new(Mutex::new(VecDeque::new()));
+    let data = web::Data::new(replies.clone());
+    let server = HttpServer::new(move || App::new().app_data(data.clone()).route("/chat/completions", web::post().to(replay)))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let address = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+
+    let mut config = AppConfig::for_test();
+    config.ai_provider = "openrouter".to_string();
+    config.openrouter_api_key = Some("replay".to_string());
+    config.openrouter_base_url = format!("http://{}", address);
+    config.ai_max_retries = 0;
+    config.ai_breaker_threshold = u32::MAX;
+    config.ai_cache_ttl_secs = 0;
+    config.ai_plan_generation = true;
+    let state = AppState::new(config).unwrap();
+
+    let mut reports = Vec::new();
+    for case in &cases {
+        *replies.lock().unwrap() = case.responses.iter().cloned().collect();
+        let mut call = AiCall::new(case.request.user_id, "plan");
+        let cache = ResponseCache::new(&state.db, &state.config);
+        let (_, transcript) = PlanService::generate_traced(&case.request, &state.ai, &state.prompts, &cache, &state.config, &mut call).await;
+        reports.push(EvalService::score_plan_output(&case.name, &case.request, transcript.last().map(String::as_str).unwrap_or_default()));
+    }
+    let comparisons = EvalService::compare(&reports, &EvalService::load_baseline(&root.join("baseline.json")), 0.0);
+
+    assert!(comparisons.iter().all(|comparison| comparison.baseline.is_some()));
+    assert!(comparisons.iter().all(|comparison| !comparison.regressed), "{}", EvalService::render_report(&reports, &comparisons));
+}
+
+#[test]
+fn regressions_are_flagged_against_baseline() {
+    let request = PlanGenerateRequest {
+        user_id: Uuid::nil(),
+        subjects: vec!["Math".to_string()],
+        goals: "Exam prep".to_string(),
+        study_hours_per_day: 1,
+        difficulty_level: "beginner".to_string(),
+        start_date: "2026-02-02".to_string(),
+    };
+    let report = EvalService::score_plan_output("prose", &request, "Study math every day.");
+    assert_eq!(report.score, 0.0);
+
+    let baseline = [("prose".to_string(), 0.6)].into_iter().collect();
+    let comparisons = EvalService::compare(&[report], &baseline, 0.1);
+    assert!(comparisons[0].regressed);
+    assert_eq!(comparisons[0].delta, Some(-0.6));
+}
@@ -0,0 +1,133 @@
+use backend::models::flashcard::DeckDueCount;
+use backend::models::plan::PlanGenerateRequest;
+use backend::services::flashcard_service::{FlashcardService, MAX_INTERVAL_DAYS};
+use backend::services::plan_service::PlanService;
+use chrono::{NaiveDate, TimeZone, Utc};
+use uuid::Uuid;
+
+#[test]
+fn sm2_grows_intervals_and_resets_on_lapse() {
+    let new = FlashcardService::new_schedule(NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+    assert_eq!(new.due_date, "2026-03-01");
+
+    let first = FlashcardService::schedule(&new, 5, Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap());
+    assert_eq!((first.repetitions, first.interval_days), (1, 1));
+    assert_eq!(first.due_date, "2026-03-02");
+    assert_eq!(first.ease_factor, 2.6);
+
+    let second = FlashcardService::schedule(&first, 4, Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap());
+    assert_eq!((second.repetitions, second.interval_days), (2, 6));
+    assert_eq!(second.ease_factor, 2.6);
+
+    let third = FlashcardService::schedule(&second, 3, Utc.with_ymd_and_hms(2026, 3, 8, 9, 0, 0).unwrap());
+    assert_eq!((third.repetitions, third.interval_days), (3, 16));
+    assert_eq!(third.due_date, "2026-03-24");
+    assert_eq!(third.ease_factor, 2.46);
+
+    let lapse = FlashcardService::schedule(&third, 1, Utc.with_ymd_and_hms(2026, 3, 24, 9, 0, 0).unwrap());
+    assert_eq!((lapse.repetitions, lapse.interval_days), (0, 1));
+    assert_eq!(lapse.due_date, "2026-03-25");
+    assert!(lapse.ease_factor < third.ease_factor);
+
+    let mut floor = lapse;
+    for _ in 0..10 {
+        floor = FlashcardService::schedule(&floor, 0, Utc.with_ymd_and_hms(2026, 3, 25, 9, 0, 0).unwrap());
+    }
+    assert_eq!(floor.ease_factor, 1.3);
+}
+
+#[test]
+fn perfect_reviews_cap_the_interval_instead_of_overflowing() {
+    let reviewed_at = Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap();
+    let mut schedule = FlashcardService::new_schedule(reviewed_at.date_naive());
+    for _ in 0..40 {
+        schedule = FlashcardService::schedule(&schedule, 5, reviewed_at);
+    }
+    assert_eq!(schedule.interval_days, MAX_INTERVAL_DAYS);
+    assert_eq!(schedule.due_date, (reviewed_at.date_naive() + chrono::Duration::days(i64::from(MAX_INTERVAL_DAYS))).to_string());
+}
+
+#[test]
+fn due_cards_become_review_tasks_in_the_plan() {
+    let request = PlanGenerateRequest {
+        user_id: Uuid::nil(),
+        subjects: vec!["Biology".to_string()],
+        goals: "Exam prep".to_string(),
+        study_hours_per_day: 2,
+        difficulty_level: "beginner".to_string(),
+        start_date: "2026-02-02".to_string(),
+    };
+    let mut plan = PlanService::generate_plan(&request);
+    let deck_id = Uuid::new_v4();
+    let due = |date: &str, count| DeckDueCount {
+        deck_id,
+        name: "Cell biology".to_string(),
+        subject: "Biology".to_string(),
+        due_date: date.to_string(),
+        count,
+    };
+    let budget = PlanService::daily_budget(&request);
+
+    PlanService::schedule_flashcard_reviews(&mut plan, &[due("2026-01-28", 30), due("2026-02-02", 10), due("2026-02-05", 2), due("2026-03-01", 5)], budget);
+
+    let reviews = |index: usize| {
+        plan.weekly_plan.daily_plans[index]
+            .tasks
+            .iter()
+            .filter(|task| task.topic == "Flashcards: Cell biology")
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let monday = reviews(0);
+    assert_eq!(monday.len(), 1);
+    assert_eq!(monday[0].duration_minutes, 20);
+    assert_eq!(monday[0].resources[0], "40 due cards in Cell biology");
+    assert_eq!(reviews(3)[0].duration_minutes, 10);
+    assert!(reviews(1).is_empty());
+    assert!(plan.ai_rationale.contains("42 due cards"));
+    for day in &plan.weekly_plan.daily_plans {
+        assert_eq!(day.total_study_time, day.tasks.iter().map(|task| task.duration_minutes).sum::<u32>());
+        assert!(day.total_study_time <= budget, "{} is over budget", day.date);
+        let mut slots = day
+            .tasks
+            .iter()
+            .map(|task| {
+                let (hour, minute) = task.start_time.split_once(':').unwrap();
+                let start = hour.parse::<u32>().unwrap() * 60 + minute.parse::<u32>().unwrap();
+                (start, start + task.duration_minutes)
+            })
+            .collect::<Vec<_>>();
+        slots.sort();
+        assert!(slots.windows(2).all(|pair| pair[0].1 <= pair[1].0), "{} has overlapping tasks", day.date);
+        assert!(slots.last().unwrap().1 <= 24 * 60);
+    }
+}
+
+#[test]
+fn reviews_that_cannot_fit_before_midnight_are_dropped() {
+    let request = PlanGenerateRequest {
+        user_id: Uuid::nil(),
+        subjects: vec!["Biology".to_string()],
+        goals: "Exam prep".to_string(),
+        study_hours_per_day: 2,
+        difficulty_level: "beginner".to_string(),
+        start_date: "2026-02-02".to_string(),
+    };
+    let mut plan = PlanService::generate_plan(&request);
+    plan.weekly_plan.daily_plans[0].tasks[2].start_time = "23:55".to_string();
+    let before = plan.weekly_plan.daily_plans[0].tasks.clone();
+    let due = DeckDueCount {
+        deck_id: Uuid::new_v4(),
+        name: "Cell biology".to_string(),
+        subject: "Biology".to_string(),
+        due_date: "2026-02-02".to_string(),
+        count: 40,
+    };
+
+    PlanService::schedule_flashcard_reviews(&mut plan, &[due], PlanService::daily_budget(&request));
+
+    let monday = &plan.weekly_plan.daily_plans[0];
+    assert_eq!(monday.tasks.len(), before.len());
+    assert!(monday.tasks.iter().zip(&before).all(|(task, before)| task.duration_minutes == before.duration_minutes));
+    assert!(!plan.ai_rationale.contains("due card"));
+}
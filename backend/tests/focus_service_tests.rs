@@ -0,0 +1,49 @@
+use backend::services::focus_service::{FocusError, FocusService};
+use chrono::{Duration, TimeZone, Utc};
+use uuid::Uuid;
+
+#[test]
+fn pauses_are_recorded_as_interruptions_and_excluded_from_focus_time() {
+    let start = Utc.with_ymd_and_hms(2026, 2, 2, 9, 0, 0).unwrap();
+    let mut session = FocusService::start(Uuid::new_v4(), Uuid::nil(), start);
+
+    FocusService::pause(&mut session, Some("  Phone call ".to_string()), start + Duration::minutes(25)).unwrap();
+    assert_eq!(session.focused_seconds, 25 * 60);
+    assert_eq!(FocusService::elapsed_seconds(&session, start + Duration::minutes(40)), 25 * 60);
+    assert!(matches!(
+        FocusService::pause(&mut session, None, start + Duration::minutes(26)),
+        Err(FocusError::InvalidTransition { action: "pause", .. })
+    ));
+
+    FocusService::resume(&mut session, start + Duration::minutes(35)).unwrap();
+    assert_eq!(FocusService::elapsed_seconds(&session, start + Duration::minutes(45)), 35 * 60);
+    FocusService::stop(&mut session, start + Duration::minutes(55) + Duration::seconds(40)).unwrap();
+
+    assert_eq!(session.status, "completed");
+    assert_eq!(session.focused_seconds, 45 * 60 + 40);
+    assert_eq!(FocusService::credited_minutes(&session), 46);
+    assert_eq!(session.interruptions.len(), 1);
+    assert_eq!(session.interruptions[0].reason.as_deref(), Some("Phone call"));
+    assert_eq!(session.interruptions[0].resumed_at, Some(start + Duration::minutes(35)));
+    assert!(FocusService::resume(&mut session, start + Duration::hours(1)).is_err());
+}
+
+#[test]
+fn idle_sessions_expire_at_the_cutoff() {
+    let start = Utc.with_ymd_and_hms(2026, 2, 2, 9, 0, 0).unwrap();
+    let timeout = Duration::minutes(30);
+    let mut session = FocusService::start(Uuid::new_v4(), Uuid::nil(), start);
+    FocusService::heartbeat(&mut session, start + Duration::minutes(20)).unwrap();
+
+    assert!(!FocusService::expire(&mut session, timeout, start + Duration::minutes(49)));
+    assert!(FocusService::expire(&mut session, timeout, start + Duration::hours(3)));
+    assert_eq!(session.status, "abandoned");
+    assert_eq!(session.ended_at, Some(start + Duration::minutes(50)));
+    assert_eq!(session.focused_seconds, 50 * 60);
+
+    let mut paused = FocusService::start(Uuid::new_v4(), Uuid::nil(), start);
+    FocusService::pause(&mut paused, None, start + Duration::minutes(10)).unwrap();
+    assert!(FocusService::expire(&mut paused, timeout, start + Duration::hours(1)));
+    assert_eq!(paused.focused_seconds, 10 * 60);
+    assert!(!FocusService::expire(&mut paused, timeout, start + Duration::hours(2)));
+}
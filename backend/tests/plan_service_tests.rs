@@ -0,0 +1,58 @@
+use backend::models::plan::PlanGenerateRequest;
+use backend::services::plan_service::PlanService;
+use uuid::Uuid;
+
+fn request() -> PlanGenerateRequest {
+    PlanGenerateRequest {
+        user_id: Uuid::nil(),
+        subjects: vec!["Math".to_string(), "Physics".to_string()],
+        goals: "Exam prep".to_string(),
+        study_hours_per_day: 2,
+        difficulty_level: "beginner".to_string(),
+        start_date: "2026-02-02".to_string(),
+    }
+}
+
+#[test]
+fn ai_plan_is_repaired_and_clipped_to_budget() {
+    let days = (0..7)
+        .map(|offset| {
+            serde_json::json!({
+                "date": format!("2026-02-{:02}", 2 + offset),
+                "tasks": [
+                    {"subject": "math", "topic": "Integrals", "duration_minutes": 90, "start_time": "09:00", "priority": "HIGH"},
+                    {"subject": "Physics", "duration_minutes": 90, "start_time": "10:00"}
+                ]
+            })
+        })
+        .collect::<Vec<_>>();
+    let raw = format!(
+        "Here you go:\n```json\n{}\n```",
+        serde_json::json!({"rationale": "Alternate subjects daily.", "weekly_plan": {"daily_plans": days}})
+    );
+
+    let (plan, rationale) = PlanService::parse_ai_plan(&raw, &request()).unwrap();
+
+    assert_eq!(rationale, "Alternate subjects daily.");
+    assert_eq!(plan.daily_plans.len(), 7);
+    let monday = &plan.daily_plans[0];
+    assert_eq!(monday.total_study_time, 120);
+    assert_eq!(monday.tasks[0].subject, "Math");
+    assert_eq!(monday.tasks[0].priority, "high");
+    assert_eq!(monday.tasks[1].start_time, "10:30");
+    assert_eq!(monday.tasks[1].duration_minutes, 30);
+    assert_eq!(monday.tasks[1].topic, "Physics review");
+}
+
+#[test]
+fn ai_plan_with_unknown_subjects_is_rejected() {
+    let raw = serde_json::json!({
+        "weekly_plan": {"daily_plans": [{"date": "2026-02-02", "tasks": [{"subject": "Chemistry", "duration_minutes": 30}]}]}
+    })
+    .to_string();
+
+    let errors = PlanService::parse_ai_plan(&raw, &request()).unwrap_err();
+
+    assert!(errors.iter().any(|error| error.contains("Chemistry")));
+    assert!(PlanService::parse_ai_plan("not json", &request()).is_err());
+}
@@ -0,0 +1,29 @@
+use backend::services::quiz_service::QuizService;
+
+#[test]
+fn ai_questions_are_validated_and_answers_resolved() {
+    let raw = r#"Sure! {"questions": [
+        {"type": "multiple_choice", "question": "What is 2 + 2?", "options": ["3", "4", "5", "22"], "answer": "B", "explanation": "Basic addition."},
+        {"type": "multiple_choice", "question": "Pick the prime", "options": ["4", "6"], "answer": "7"},
+        {"type": "short_answer", "question": "Define a derivative.", "options": [], "answer": "The instantaneous rate of change of a function."},
+        {"type": "short_answer", "question": "", "answer": "missing question"}
+    ]}"#;
+
+    let questions = QuizService::parse_questions(raw);
+
+    assert_eq!(questions.len(), 2);
+    assert_eq!(questions[0].question_type, "multiple_choice");
+    assert_eq!(questions[0].answer, "4");
+    assert_eq!(questions[1].question_type, "short_answer");
+}
+
+#[test]
+fn local_bank_fills_requested_count_for_any_subject() {
+    let math = QuizService::from_bank("Mathematics", "Integrals", 5);
+    assert_eq!(math.len(), 5);
+    assert!(math.iter().all(|question| question.question_type != "multiple_choice" || question.options.contains(&question.answer)));
+
+    let unknown = QuizService::from_bank("Latin", "Declensions", 3);
+    assert_eq!(unknown.len(), 3);
+    assert!(unknown.iter().all(|question| question.prompt.contains("Declensions") || question.prompt.contains("Latin")));
+}
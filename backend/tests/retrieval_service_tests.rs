@@ -0,0 +1,22 @@
+use backend::services::retrieval_service::RetrievalService;
+use chrono::NaiveDate;
+
+fn day(value: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn date_phrases_become_inclusive_ranges() {
+    let today = day("2026-10-21");
+    assert_eq!(RetrievalService::date_range("What did I note about integrals last week?", today), Some((day("2026-10-12"), day("2026-10-18"))));
+    assert_eq!(RetrievalService::date_range("notes from this week", today), Some((day("2026-10-19"), today)));
+    assert_eq!(RetrievalService::date_range("what did I do yesterday", today), Some((day("2026-10-20"), day("2026-10-20"))));
+    assert_eq!(RetrievalService::date_range("Last month's chemistry", today), Some((day("2026-09-01"), day("2026-09-30"))));
+    assert_eq!(RetrievalService::date_range("integrals in the past 10 days", today), Some((day("2026-10-11"), today)));
+    assert_eq!(RetrievalService::date_range("What did I note about integrals?", today), None);
+}
+
+#[test]
+fn date_phrases_are_not_search_terms() {
+    assert_eq!(RetrievalService::match_query("integrals in the past 10 days").as_deref(), Some("\"integrals\""));
+}
@@ -0,0 +1,43 @@
+use backend::models::search::SearchResult;
+use backend::services::search_service::{MATCH_END, MATCH_START, SEARCH_TYPES, SearchService};
+
+fn hit(result_type: &str, id: &str, date: &str, score: f64) -> SearchResult {
+    SearchResult {
+        result_type: result_type.to_string(),
+        id: id.to_string(),
+        title: "Math: Integrals".to_string(),
+        snippet: String::new(),
+        date: Some(date.to_string()),
+        score,
+    }
+}
+
+#[test]
+fn queries_match_every_word_as_a_prefix_and_ignore_fts_syntax() {
+    assert_eq!(SearchService::match_query("Integral* OR \"parts\"").as_deref(), Some("\"integral\"* AND \"or\"* AND \"parts\"*"));
+    assert_eq!(SearchService::match_query("  -- () "), None);
+}
+
+#[test]
+fn types_default_to_everything_and_reject_unknown_names() {
+    assert_eq!(SearchService::parse_types(None).unwrap(), SEARCH_TYPES.to_vec());
+    assert_eq!(SearchService::parse_types(Some("tool, task,tool")).unwrap(), vec!["tool", "task"]);
+    assert!(SearchService::parse_types(Some("tool,email")).is_err());
+}
+
+#[test]
+fn snippets_are_escaped_around_highlights() {
+    let snippet = format!("pick <u> & {}log{} term", MATCH_START, MATCH_END);
+    assert_eq!(SearchService::highlight(&snippet), "pick &lt;u&gt; &amp; <mark>log</mark> term");
+}
+
+#[test]
+fn ranking_keeps_each_documents_best_chunk() {
+    let hits = vec![hit("conversation", "c1", "2026-02-03", 1.0), hit("user_note", "t1", "2026-02-01", 3.0), hit("user_note", "t1", "2026-02-01", 2.0)];
+
+    let by_relevance = SearchService::rank(hits.clone(), "relevance");
+    assert_eq!(by_relevance.len(), 2);
+    assert_eq!((by_relevance[0].id.as_str(), by_relevance[0].score), ("t1", 3.0));
+    let by_date = SearchService::rank(hits, "recent");
+    assert_eq!(by_date[0].id, "c1");
+}
@@ -0,0 +1,108 @@
+use backend::models::tool::{Tool, ToolMetadata, ToolStateEntry, ToolVersion};
+use backend::services::tool_bundle_service::{BundleError, ToolBundleService};
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+const SEED: &str = "AQIDBAUGBwgJCgsMDQ4PEBESExQVFhcYGRobHB0eHyA=";
+
+fn tool() -> (Tool, ToolVersion) {
+    let created_at = Utc.with_ymd_and_hms(2026, 2, 2, 9, 0, 0).unwrap();
+    let tool = Tool {
+        tool_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        tool_type: "calculator".to_string(),
+        name: "Quadratic Solver".to_string(),
+        description: "Solves ax² + bx + c = 0".to_string(),
+        component_code: "const Solver = () => null;\nexport default Solver;".to_string(),
+        metadata: ToolMetadata {
+            version: "1.1.0".to_string(),
+            created_at,
+            ai_model: "local".to_string(),
+            prompt_version: None,
+            template: Some("calculator@1".to_string()),
+        },
+        usage_count: 3,
+        last_used: None,
+    };
+    let head = ToolVersion {
+        version_id: Uuid::new_v4(),
+        tool_id: tool.tool_id,
+        version: "1.1.0".to_string(),
+        component_code: tool.component_code.clone(),
+        instruction: Some("Show the discriminant".to_string()),
+        changes_summary: "Added the discriminant".to_string(),
+        change_type: "minor".to_string(),
+        ai_model: Some("local".to_string()),
+        prompt_version: None,
+        diagnostics: Vec::new(),
+        created_at,
+    };
+    (tool, head)
+}
+
+#[test]
+fn signed_bundles_round_trip_through_json_and_detect_tampering() {
+    let (tool, head) = tool();
+    let state = vec![ToolStateEntry {
+        tool_id: tool.tool_id,
+        user_id: tool.user_id,
+        key: "history".to_string(),
+        value: serde_json::json!({"roots": [1.5, -2.25], "label": "x² − 1"}),
+        revision: 4,
+        size_bytes: 40,
+        updated_at: Utc::now(),
+    }];
+    let bundle = ToolBundleService::export(&tool, &head, Some(&state), Some(SEED), Utc::now()).unwrap();
+    let public_key = ToolBundleService::public_key(SEED).unwrap();
+    assert_eq!(bundle.signature.as_ref().unwrap().public_key, public_key);
+
+    let parsed: serde_json::Value = serde_json::from_str(&serde_json::to_string_pretty(&bundle).unwrap()).unwrap();
+    let (_, verification) = ToolBundleService::verify(&parsed, std::slice::from_ref(&public_key)).unwrap();
+    assert_eq!(verification.content_hash, bundle.content_hash);
+    assert_eq!(verification.signer_public_key.as_deref(), Some(public_key.as_str()));
+    assert!(verification.trusted);
+    assert!(!ToolBundleService::verify(&parsed, &[]).unwrap().1.trusted);
+
+    let mut tampered = parsed.clone();
+    tampered["content"]["tool"]["component_code"] = serde_json::json!(format!("{}\n// changed", tool.component_code));
+    assert!(matches!(ToolBundleService::verify(&tampered, &[]), Err(BundleError::HashMismatch { .. })));
+
+    tampered["content_hash"] = serde_json::json!(ToolBundleService::content_hash(&tampered["content"]));
+    assert!(matches!(ToolBundleService::verify(&tampered, &[]), Err(BundleError::BadSignature)));
+    tampered["signature"] = serde_json::Value::Null;
+    let (_, unsigned) = ToolBundleService::verify(&tampered, &[]).unwrap();
+    assert_eq!((unsigned.signer_public_key, unsigned.trusted), (None, false));
+}
+
+#[test]
+fn the_hash_covers_the_received_content_not_the_parsed_struct() {
+    let (tool, head) = tool();
+    let bundle = ToolBundleService::export(&tool, &head, None, Some(SEED), Utc::now()).unwrap();
+    let mut received = serde_json::to_value(&bundle).unwrap();
+
+    let content = received["content"].as_object().unwrap().clone();
+    received["content"] = serde_json::Value::Object(content.into_iter().rev().collect());
+    received["content"]["exported_at"] = serde_json::json!(bundle.content.exported_at.to_rfc3339());
+    assert!(matches!(ToolBundleService::verify(&received, &[]), Err(BundleError::HashMismatch { .. })));
+    received["content"]["exported_at"] = serde_json::to_value(bundle.content.exported_at).unwrap();
+    assert_eq!(ToolBundleService::verify(&received, &[]).unwrap().1.content_hash, bundle.content_hash);
+
+    received["content"]["tool"]["metadata"]["reviewed_by"] = serde_json::json!("someone else");
+    assert!(matches!(ToolBundleService::verify(&received, &[]), Err(BundleError::HashMismatch { .. })));
+}
+
+#[test]
+fn bundles_without_a_signing_key_are_unsigned_and_checked_for_shape() {
+    let (tool, head) = tool();
+    let mut bundle = ToolBundleService::export(&tool, &head, None, None, Utc::now()).unwrap();
+    assert!(bundle.signature.is_none() && bundle.content.state.is_none());
+    assert_eq!(bundle.content.head.change_type, "minor");
+    assert!(matches!(ToolBundleService::export(&tool, &head, None, Some("short"), Utc::now()), Err(BundleError::SigningKey)));
+
+    bundle.content.tool.tool_type = "Not A Type".to_string();
+    bundle.content_hash = ToolBundleService::content_hash(&serde_json::to_value(&bundle.content).unwrap());
+    assert_eq!(ToolBundleService::verify(&serde_json::to_value(&bundle).unwrap(), &[]).unwrap_err().code(), "INVALID_TOOL_BUNDLE");
+    bundle.format = "zip".to_string();
+    assert_eq!(ToolBundleService::verify(&serde_json::to_value(&bundle).unwrap(), &[]).unwrap_err().to_string(), "Not a tool bundle: format must be \"studyplanner.tool-bundle\"");
+    assert_eq!(ToolBundleService::verify(&serde_json::json!({"format": "zip"}), &[]).unwrap_err().code(), "INVALID_TOOL_BUNDLE");
+}
@@ -0,0 +1,37 @@
+use backend::services::tool_catalogue_service::{ForkError, ToolCatalogueService};
+
+const BASE: &str = "const Timer = () => {\n  const minutes = 25;\n  return null;\n};\nexport default Timer;\n";
+
+#[test]
+fn upstream_changes_merge_into_separate_fork_edits() {
+    let fork = BASE.replace("return null;", "return minutes;");
+    let upstream = BASE.replace("const Timer = () => {", "const Timer = () => {\n  // Pomodoro defaults");
+
+    let merged = ToolCatalogueService::merge_upstream(BASE, &fork, &upstream, "1.1.0").unwrap();
+    assert!(merged.contains("// Pomodoro defaults"));
+    assert!(merged.contains("return minutes;"));
+    assert_eq!(ToolCatalogueService::merge_upstream(BASE, &fork, BASE, "1.0.0").unwrap(), fork);
+}
+
+#[test]
+fn overlapping_edits_conflict_with_marked_code() {
+    let fork = BASE.replace("25", "50");
+    let upstream = BASE.replace("25", "30");
+
+    let error = ToolCatalogueService::merge_upstream(BASE, &fork, &upstream, "1.1.0").unwrap_err();
+    assert_eq!(error.code(), "MERGE_CONFLICT");
+    let ForkError::Conflict { upstream_version, merged } = error else { panic!("expected a conflict") };
+    assert_eq!(upstream_version, "1.1.0");
+    assert!(merged.contains("<<<<<<<") && merged.contains("const minutes = 50;") && merged.contains("const minutes = 30;"));
+}
+
+#[test]
+fn ratings_and_subjects_are_validated() {
+    assert!(ToolCatalogueService::validate_rating(1).is_ok());
+    assert!(ToolCatalogueService::validate_rating(5).is_ok());
+    assert!(ToolCatalogueService::validate_rating(0).is_err());
+    assert!(ToolCatalogueService::validate_rating(6).is_err());
+    assert_eq!(ToolCatalogueService::normalize_subject(Some("  Chemistry ")).unwrap().as_deref(), Some("Chemistry"));
+    assert_eq!(ToolCatalogueService::normalize_subject(Some("   ")).unwrap(), None);
+    assert!(ToolCatalogueService::normalize_subject(Some(&"x".repeat(101))).is_err());
+}
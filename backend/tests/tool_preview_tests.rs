@@ -0,0 +1,101 @@
+use backend::services::tool_preview::ToolPreview;
+use oxc_allocator::Allocator;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+
+#[test]
+fn transpiles_tsx_into_a_classic_script_against_the_runtime() {
+    let code = r#"import React, { useState } from 'react';
+import type { ReactNode } from 'react';
+import { Button as MuiButton, Stack } from '@mui/material';
+import Add from '@mui/icons-material/Add';
+
+interface CardProps {
+  title: string;
+  hint?: ReactNode;
+}
+
+type Mode = 'front' | 'back';
+
+const Card = ({ title, hint }: CardProps) => (
+  <Stack spacing={2} data-role="card">
+    <h2 className="title">{title as string}</h2>
+    {hint && <>
+      Hint:&nbsp;{hint}
+    </>}
+  </Stack>
+);
+
+export default function Drill(): JSX.Element {
+  const [mode, setMode] = useState<Mode>('front');
+  const label = (value?: string) => value!.toUpperCase();
+  return (
+    <div>
+      {/* toggles the card */}
+      <Card title={label(mode)} {...{ hint: 'flip' }} />
+      <MuiButton startIcon={<Add />} disabled onClick={() => setMode(mode === 'front' ? 'back' : 'front')}>
+        Flip   card
+      </MuiButton>
+    </div>
+  );
+}
+"#;
+    let script = ToolPreview::transpile(code).unwrap();
+    assert_valid_script(&script);
+
+    assert!(script.contains(r#"const React = __default(__require("react"));"#), "{}", script);
+    assert!(script.contains(r#"const { useState } = __require("react");"#));
+    assert!(script.contains(r#"const { "Button": MuiButton, Stack } = __require("@mui/material");"#));
+    assert!(script.contains(r#"const Add = __default(__require("@mui/icons-material/Add"));"#));
+    assert!(script.contains(r#"__h("h2", { className: "title" }, title)"#));
+    assert!(script.contains(r#"__h(__Fragment, null, "Hint:\xA0", hint)"#));
+    assert!(script.contains("startIcon: __h(Add, null)"));
+    assert!(script.contains(r#""Flip   card")"#));
+    assert!(script.contains("function Drill() {"));
+    assert!(script.ends_with("const __tool = Drill;\n"));
+    assert!(script.contains("(value) => value.toUpperCase()"));
+    for leftover in ["interface", "type Mode", "useState<", ": CardProps", "JSX.Element", "import ", "export ", "<div", "toggles"] {
+        assert!(!script.contains(leftover), "{} left in {}", leftover, script);
+    }
+}
+
+#[test]
+fn class_members_and_typescript_only_syntax_compile_to_valid_javascript() {
+    let code = r#"import React from 'react';
+enum Side { Front, Back }
+class Ticker extends React.Component<{ start: number }> {
+  private ticks?: number;
+  readonly label: string = 'Ticks';
+  timer!: number;
+  constructor(props: { start: number }, private readonly step: number = 1) {
+    super(props);
+  }
+  render() {
+    return <p>{this.label}: {this.ticks ?? this.props.start} {Side.Front}</p>;
+  }
+}
+export default class extends Ticker {}
+"#;
+    let script = ToolPreview::transpile(code).unwrap();
+    assert_valid_script(&script);
+    assert!(script.contains("this.step = step;"), "{}", script);
+    assert!(script.contains("const __tool = class extends Ticker {};"), "{}", script);
+    for leftover in ["private", "readonly", "ticks?", "timer!", "enum "] {
+        assert!(!script.contains(leftover), "{} left in {}", leftover, script);
+    }
+}
+
+#[test]
+fn reports_components_that_cannot_be_previewed() {
+    let missing = ToolPreview::transpile("const Drill = () => <div />;").unwrap_err();
+    assert_eq!(missing[0].code, "missing_default_export");
+
+    let syntax = ToolPreview::transpile("export default () => <div>;").unwrap_err();
+    assert_eq!(syntax[0].code, "syntax_error");
+}
+
+fn assert_valid_script(script: &str) {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, script, SourceType::cjs()).parse();
+    assert!(parsed.errors.is_empty(), "{:?} in {}", parsed.errors, script);
+}
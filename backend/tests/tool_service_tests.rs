@@ -0,0 +1,161 @@
+use backend::models::tool::{ToolGenerateRequest, UiPreferences};
+use backend::services::template_registry::{TemplateRegistry, ToolTemplate};
+use backend::services::tool_service::{ToolLayout, ToolService};
+use backend::services::tool_validator::ToolValidator;
+use backend::utils::config::AppConfig;
+use uuid::Uuid;
+
+fn request(tool_type: &str, context: &str, requirements: &str) -> ToolGenerateRequest {
+    ToolGenerateRequest {
+        user_id: Uuid::new_v4(),
+        tool_type: tool_type.to_string(),
+        context: context.to_string(),
+        requirements: requirements.to_string(),
+        subject: None,
+        ui_preferences: Some(UiPreferences {
+            theme: Some("dark".to_string()),
+            size: Some("small".to_string()),
+        }),
+        template: None,
+        parameters: None,
+    }
+}
+
+fn builtin(tool_type: &str) -> ToolTemplate {
+    TemplateRegistry::load(&AppConfig::for_test().data_dir).select(tool_type, None).unwrap()
+}
+
+#[test]
+fn offline_templates_are_filled_from_the_request() {
+    let flashcards = request("flashcard", "Calculus", "derivative of x^2 -> 2x\nQ: integral of 1/x\nA: ln|x| + C\nMake them short: one line each");
+    let layout = ToolLayout::from_preferences(flashcards.ui_preferences.as_ref());
+    let tool = ToolService::from_template(&flashcards, &builtin("flashcard"), &layout).unwrap();
+    assert_eq!(tool.name, "Calculus Flashcards");
+    assert_eq!(tool.description, "2 flashcards on Calculus.");
+    assert!(tool.component_code.contains(r#""answer": "ln|x| + C""#));
+    assert!(tool.component_code.contains(r#"mode: "dark""#));
+    assert!(tool.component_code.contains("maxWidth: 360"));
+    assert!(tool.component_code.contains("export default CalculusFlashcards;"));
+    assert!(!["{{title}}", "{{cards}}", "{{component_name}}"].iter().any(|key| tool.component_code.contains(key)));
+
+    let bank = ToolService::from_template(&request("flashcard", "Physics", "8 cards"), &builtin("flashcard"), &layout).unwrap();
+    assert_eq!(bank.description, "8 flashcards on Physics.");
+
+    let timer = ToolService::from_template(&request("timer", "Deep work", "45 minute focus blocks"), &builtin("timer"), &ToolLayout::from_preferences(None)).unwrap();
+    assert_eq!(timer.name, "45-Minute Deep work Timer");
+    assert!(timer.component_code.contains("const INITIAL_DURATION = 2700;"));
+    assert!(timer.component_code.contains(r#"mode: "light""#));
+    assert!(timer.component_code.contains("export default Study45MinuteDeepWorkTimer;"));
+}
+
+#[test]
+fn calculators_follow_the_request_and_cards_need_an_explicit_format() {
+    let layout = ToolLayout::from_preferences(None);
+    let calculator = |context: &str, requirements: &str| ToolService::from_template(&request("calculator", context, requirements), &builtin("calculator"), &layout).unwrap();
+    let quadratic = calculator("Exam revision", "Find both roots, 3 decimal places");
+    assert!(quadratic.component_code.contains("const FORMULA = \"quadratic\";\nconst PRECISION = 3;"));
+    assert_eq!(quadratic.description, "Solves ax² + bx + c = 0 for Exam revision, rounded to 3 decimal places.");
+    assert!(calculator("Geometry", "Hypotenuse from two legs").component_code.contains("const FORMULA = \"pythagoras\";"));
+    assert!(calculator("Statistics", "Mean of my test scores").component_code.contains("const FORMULA = \"average\";"));
+    assert!(calculator("Shopping", "Discount as a percent").component_code.contains("const FORMULA = \"percentage\";"));
+    let basic = calculator("Homework", "Check my arithmetic");
+    assert!(basic.component_code.contains("const FORMULA = \"basic\";"));
+    assert!(basic.description.starts_with("Adds, subtracts, multiplies and divides two numbers for Homework"));
+    let mut explicit = request("calculator", "Quadratics", "");
+    explicit.parameters = Some(serde_json::json!({"formula": "linear"}));
+    assert!(ToolService::from_template(&explicit, &builtin("calculator"), &layout).unwrap().component_code.contains("const FORMULA = \"linear\";"));
+    explicit.parameters = Some(serde_json::json!({"formula": "cubic"}));
+    assert!(ToolService::from_template(&explicit, &builtin("calculator"), &layout).is_err());
+
+    let notes = ToolService::from_template(&request("flashcard", "Cell division", "Note: keep it simple\nTheme: dark\n2 cards"), &builtin("flashcard"), &layout).unwrap();
+    assert!(!notes.component_code.contains("keep it simple") && !notes.component_code.contains("\"question\": \"Theme\""));
+    assert_eq!(notes.description, "2 flashcards on Cell division.");
+    assert!(notes.component_code.contains("Cell division"));
+
+    let mut biology = request("flashcard", "Cell division", "20 cards");
+    biology.subject = Some("Biology".to_string());
+    let tool = ToolService::from_template(&biology, &builtin("flashcard"), &layout).unwrap();
+    assert!(tool.component_code.contains("Mitochondrion"));
+    assert!(tool.component_code.contains("Which earlier idea in Biology does Cell division build on?"));
+}
+
+#[test]
+fn generated_code_must_export_and_pass_the_policy_scan() {
+    let raw = r#"Here you go: {"name": "Limit Drill", "description": "Practice limits.", "component_code": "const LimitDrill = () => null;\nexport default LimitDrill;"}"#;
+    let (name, description, code) = ToolService::parse_generated(raw).unwrap();
+    assert_eq!(name.as_deref(), Some("Limit Drill"));
+    assert_eq!(description.as_deref(), Some("Practice limits."));
+    assert!(code.starts_with("const LimitDrill"));
+
+    let fenced = "```tsx\nconst Tool = () => null;\nexport default Tool;\n```";
+    assert_eq!(ToolService::parse_generated(fenced).unwrap().0, None);
+
+    let unsafe_code = r#"{"name": "Bad", "component_code": "eval('1');\nexport default Bad;"}"#;
+    assert!(ToolService::parse_generated(unsafe_code).is_none());
+    assert!(ToolService::parse_generated(r#"{"name": "No export", "component_code": "const X = 1;"}"#).is_none());
+}
+
+#[test]
+fn edits_are_versioned_by_change_type_and_validated() {
+    assert_eq!(ToolService::bump_version("1.0.0", "patch"), "1.0.1");
+    assert_eq!(ToolService::bump_version("1.2.3", "minor"), "1.3.0");
+    assert_eq!(ToolService::bump_version("1.2.3", "major"), "2.0.0");
+    assert_eq!(ToolService::bump_version("not-semver", "patch"), "1.0.1");
+
+    let before = "const A = () => <Button>Go</Button>;\nexport default A;";
+    assert_eq!(ToolService::infer_change_type(before, "const A = () => <Button>Start</Button>;\nexport default A;"), "patch");
+    assert_eq!(ToolService::infer_change_type(before, "const A = () => { const [x] = useState(0); return <Button>Go</Button>; };\nexport default A;"), "minor");
+
+    for tool_type in ["calculator", "timer", "flashcard", "custom"] {
+        let tool = ToolService::from_template(&request(tool_type, "Algebra", "Don't forget: signs"), &builtin(tool_type), &ToolLayout::from_preferences(None)).unwrap();
+        assert_eq!(ToolValidator::validate(&tool.component_code), vec![], "{}", tool_type);
+    }
+}
+
+#[test]
+fn disk_templates_add_tool_kinds_and_reload_on_change() {
+    let config = AppConfig::for_test();
+    let dir = std::path::Path::new(&config.data_dir).join("templates");
+    std::fs::create_dir_all(&dir).unwrap();
+    let registry = TemplateRegistry::load(&config.data_dir);
+    assert_eq!(registry.tool_types(), vec!["calculator", "custom", "flashcard", "timer"]);
+
+    std::fs::write(
+        dir.join("unit_converter.json"),
+        r#"{"name": "unit_converter", "tool_type": "unit_converter", "version": "1", "description": "Converts between units.",
+            "parameters": {"type": "object", "properties": {"units": {"type": "array", "items": {"type": "string"}, "default": ["m", "cm"]},
+            "factor": {"type": "number", "minimum": 0}}, "required": ["factor"]}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("unit_converter.tsx"),
+        "const UNITS = {{units}};\nconst FACTOR = {{factor}};\nconst {{component_name}} = () => <div style={{ maxWidth: {{max_width}} }}>{UNITS.join(', ')} x {FACTOR}</div>;\nexport default {{component_name}};\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("broken.json"), r#"{"name": "Broken!", "tool_type": "custom", "version": "1"}"#).unwrap();
+    let template = registry.select("unit_converter", None).unwrap();
+    assert_eq!(template.id(), "unit_converter@1");
+    assert_eq!(registry.reload().len(), 1);
+
+    let mut converter = request("unit_converter", "Physics", "");
+    let missing = ToolService::from_template(&converter, &template, &ToolLayout::from_preferences(None)).unwrap_err();
+    assert_eq!(missing.to_string(), "The tool parameters do not match the template schema: parameters.factor is required");
+    converter.parameters = Some(serde_json::json!({"factor": -1}));
+    assert!(ToolService::from_template(&converter, &template, &ToolLayout::from_preferences(None)).is_err());
+
+    converter.parameters = Some(serde_json::json!({"factor": 100}));
+    let tool = ToolService::from_template(&converter, &template, &ToolLayout::from_preferences(None)).unwrap();
+    assert_eq!(tool.name, "Physics Unit converter");
+    assert_eq!(tool.description, "Converts between units.");
+    assert_eq!(tool.metadata.template.as_deref(), Some("unit_converter@1"));
+    assert!(tool.component_code.contains("const UNITS = [\"m\",\"cm\"];\nconst FACTOR = 100;"));
+    assert_eq!(ToolValidator::validate(&tool.component_code), vec![]);
+
+    let mut timer = request("timer", "Deep work", "45 minute focus blocks");
+    timer.parameters = Some(serde_json::json!({"duration_seconds": 600}));
+    let tool = ToolService::from_template(&timer, &builtin("timer"), &ToolLayout::from_preferences(None)).unwrap();
+    assert!(tool.component_code.contains("const INITIAL_DURATION = 600;"));
+
+    std::fs::remove_file(dir.join("unit_converter.json")).unwrap();
+    assert!(registry.select("unit_converter", None).is_err());
+}
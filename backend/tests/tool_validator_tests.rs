@@ -0,0 +1,128 @@
+use backend::services::tool_validator::ToolValidator;
+
+fn codes(code: &str) -> Vec<(String, String)> {
+    ToolValidator::validate(code)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.severity, diagnostic.code))
+        .collect()
+}
+
+#[test]
+fn accepts_default_exported_components_in_common_shapes() {
+    let shapes = [
+        "import React from 'react';\nconst Drill: React.FC = () => <p>Don't stop</p>;\nexport default Drill;",
+        "export default function Drill() { return <div />; }",
+        "export default () => <div />;",
+        "import { memo } from 'react';\nconst Drill = () => <div />;\nexport default memo(Drill);",
+        "import { Button } from '@mui/material';\nimport Add from '@mui/icons-material/Add';\nfunction Drill() { return <Button startIcon={<Add />}>Go</Button>; }\nexport default Drill;",
+    ];
+    for code in shapes {
+        assert!(codes(code).is_empty(), "{}: {:?}", code, codes(code));
+    }
+}
+
+#[test]
+fn reports_structure_problems_and_forbidden_apis_with_locations() {
+    let syntax = ToolValidator::validate("const Drill = () => (<p>{x</p>);\nexport default Drill;");
+    assert_eq!(syntax[0].code, "syntax_error");
+    assert_eq!(syntax[0].line, Some(1));
+
+    assert_eq!(codes("const Drill = () => null;"), vec![("error".into(), "missing_default_export".into())]);
+    assert_eq!(codes("const settings = { a: 1 };\nexport default settings;"), vec![("error".into(), "default_export_not_component".into())]);
+
+    let code = r#"import axios from 'axios';
+const Drill = () => {
+  eval('1 + 1');
+  fetch('https://example.com/cards');
+  fetch('/api/cards');
+  const fs = require('fs');
+  window.open('https://example.com');
+  import('./other');
+  localStorage.clear();
+  localStorage.setItem('drill', '1');
+  return null;
+};
+export default Drill;"#;
+    let diagnostics = ToolValidator::validate(code);
+    let found = diagnostics.iter().map(|diagnostic| (diagnostic.severity.as_str(), diagnostic.code.as_str(), diagnostic.line)).collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            ("error", "unsupported_import", Some(1)),
+            ("error", "eval", Some(3)),
+            ("error", "external_fetch", Some(4)),
+            ("warning", "fetch", Some(5)),
+            ("error", "require", Some(6)),
+            ("error", "window_open", Some(7)),
+            ("error", "dynamic_import", Some(8)),
+            ("error", "storage_abuse", Some(9)),
+            ("warning", "storage_access", Some(10)),
+        ]
+    );
+    assert!(ToolValidator::has_errors(&diagnostics));
+}
+
+#[test]
+fn forbidden_apis_are_caught_through_the_global_object_and_jsx() {
+    let cases = [
+        ("window['eval']('1 + 1');", "eval"),
+        ("window.eval('1 + 1');", "eval"),
+        ("globalThis.fetch('https://example.com');", "external_fetch"),
+        ("self.fetch(`https://example.com/${id}`);", "external_fetch"),
+        ("const load = fetch;", "external_fetch"),
+        ("window.Function('return 1')();", "eval"),
+        ("new XMLHttpRequest();", "network_access"),
+        ("new window.XMLHttpRequest();", "network_access"),
+        ("new WebSocket('wss://example.com');", "network_access"),
+        ("new globalThis['WebSocket']('wss://example.com');", "network_access"),
+        ("navigator.sendBeacon('https://example.com', '1');", "network_access"),
+        ("window.navigator.sendBeacon('https://example.com', '1');", "network_access"),
+        ("globalThis[name]('1 + 1');", "dynamic_global"),
+        ("const global = window;", "dynamic_global"),
+        ("globalThis.window.open('https://example.com');", "window_open"),
+        ("window.localStorage.clear();", "storage_abuse"),
+        ("document.cookie = 'a=1';", "document_access"),
+        ("window.location.href = 'https://example.com';", "navigation"),
+        ("location.assign('https://example.com');", "navigation"),
+        ("const key = process.env.SECRET;", "host_access"),
+        ("const html = <div dangerouslySetInnerHTML={{ __html: name }} />;", "inner_html"),
+        ("const tag = <script>{name}</script>;", "inline_script"),
+        ("document.defaultView.fetch('https://example.com');", "external_fetch"),
+        ("const view = document.defaultView;", "dynamic_global"),
+        ("top.fetch('https://example.com');", "external_fetch"),
+        ("parent.location.href = 'https://example.com';", "navigation"),
+        ("window.top.location.assign('https://example.com');", "navigation"),
+        ("frames[0].fetch('https://example.com');", "dynamic_global"),
+        ("opener.fetch('https://example.com');", "external_fetch"),
+        ("new Image().src = `https://example.com/?q=${name}`;", "network_access"),
+        ("const script = document.createElement('script');", "document_access"),
+        ("window.document.createElement('img');", "document_access"),
+        ("import(name);", "dynamic_import"),
+    ];
+    for (statement, expected) in cases {
+        let code = format!("const Drill = () => {{ const id = 1; const name = 'x'; {} return null; }};\nexport default Drill;", statement);
+        assert_eq!(codes(&code), vec![("error".to_string(), expected.to_string())], "{}", statement);
+    }
+
+    let allowed = "const Drill = () => { if (typeof window !== 'undefined') { window.addEventListener('resize', () => null); } fetch('/cards'); return null; };\nexport default Drill;";
+    assert_eq!(codes(allowed), vec![("warning".to_string(), "fetch".to_string())]);
+}
+
+#[test]
+fn local_bindings_that_shadow_globals_are_not_reported() {
+    let code = r#"import React from 'react';
+import Image from '@mui/icons-material/Image';
+const Drill = () => {
+  const fetch = (url: string) => url;
+  const location = { href: '/cards' };
+  const window = { open: () => null };
+  const [frames, setFrames] = React.useState(0);
+  fetch('https://example.com');
+  window.open();
+  const require = (name: string) => name;
+  require('fs');
+  return React.createElement('p', { onClick: () => setFrames(frames + 1) }, location.href, <Image />);
+};
+export default Drill;"#;
+    assert!(codes(code).is_empty(), "{:?}", codes(code));
+}
@@ -0,0 +1,13 @@
+// Entry for the pinned runtime that tool previews (GET /tools/preview/{id}) load from
+// /tools/preview/runtime.js with a nonce. Build it with `npm run build:preview-runtime`; the output lands in backend/data.
+import * as React from 'react'
+import * as ReactDOMClient from 'react-dom/client'
+import * as MaterialUI from '@mui/material'
+import * as MaterialIcons from '@mui/icons-material'
+
+export const modules: Record<string, unknown> = {
+  react: React,
+  'react-dom/client': ReactDOMClient,
+  '@mui/material': MaterialUI,
+  '@mui/icons-material': MaterialIcons,
+}
@@ -1,10 +1,14 @@
 import { Alert, Box, Typography } from '@mui/material'
-import { useEffect, useMemo, useState } from 'react'
+import { useEffect, useMemo, useRef, useState } from 'react'
 import Loading from '../Common/Loading'
+import { recordToolUsage } from '../../store/api/toolApi'
 
 interface ToolRendererProps {
   code: string
   title?: string
+  toolId?: string
+  userId?: string
+  taskId?: string
 }
 
 const stripTypeScript = (source: string) => {
@@ -18,9 +22,31 @@ const stripTypeScript = (source: string) => {
   return output
 }
 
-const ToolRenderer = ({ code, title }: ToolRendererProps) => {
+const ToolRenderer = ({ code, title, toolId, userId, taskId }: ToolRendererProps) => {
   const [Component, setComponent] = useState<React.ComponentType | null>(null)
   const [error, setError] = useState<string | null>(null)
+  const interacted = useRef(false)
+
+  useEffect(() => {
+    if (!toolId || !userId) {
+      return
+    }
+    const openedAt = Date.now()
+    interacted.current = false
+    void recordToolUsage(toolId, { user_id: userId, event_type: 'open', task_id: taskId }).catch(() => undefined)
+    return () => {
+      const duration_seconds = Math.round((Date.now() - openedAt) / 1000)
+      void recordToolUsage(toolId, { user_id: userId, event_type: 'close', task_id: taskId, duration_seconds }).catch(() => undefined)
+    }
+  }, [toolId, userId, taskId])
+
+  const handleInteraction = () => {
+    if (!toolId || !userId || interacted.current) {
+      return
+    }
+    interacted.current = true
+    void recordToolUsage(toolId, { user_id: userId, event_type: 'interact', task_id: taskId }).catch(() => undefined)
+  }
 
   const sanitizedCode = useMemo(() => stripTypeScript(code), [code])
 
@@ -59,7 +85,7 @@ const ToolRenderer = ({ code, title }: ToolRendererProps) => {
   }, [code, sanitizedCode])
 
   return (
-    <Box sx={{ border: '1px solid #e8eaed', borderRadius: 2, p: 2 }}>
+    <Box sx={{ border: '1px solid #e8eaed', borderRadius: 2, p: 2 }} onPointerDown={handleInteraction} onKeyDown={handleInteraction}>
       {title && (
         <Typography variant="subtitle1" sx={{ mb: 1 }}>
           {title}
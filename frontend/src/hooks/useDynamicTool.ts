@@ -1,6 +1,7 @@
 import { useCallback, useState } from 'react'
 import type { Tool, ToolGenerateRequest, ToolListResponse } from '../types/tool'
 import { editTool, fetchTool, fetchTools, generateTool } from '../store/api/toolApi'
+import { getUserId } from '../utils/user'
 
 export function useDynamicTool() {
   const [tools, setTools] = useState<ToolListResponse | null>(null)
@@ -57,7 +58,7 @@ export function useDynamicTool() {
     setLoading(true)
     setError(null)
     try {
-      const response = await editTool(toolId, { edit_instruction: instruction })
+      const response = await editTool(toolId, { edit_instruction: instruction, state_ref: { user_id: getUserId() } })
       if (currentTool && currentTool.tool_id === toolId) {
         setCurrentTool({ ...currentTool, component_code: response.updated_component_code })
       }
@@ -84,7 +84,12 @@ const ToolsPage = () => {
       {currentTool ? (
         <Stack spacing={2}>
           <Typography variant="h5">{currentTool.name}</Typography>
-          <ToolRenderer code={currentTool.component_code} title="Generated component" />
+          <ToolRenderer
+            code={currentTool.component_code}
+            title="Generated component"
+            toolId={currentTool.tool_id}
+            userId={getUserId()}
+          />
           <ToolEditor onEdit={handleEdit} loading={loading} />
         </Stack>
       ) : (
@@ -0,0 +1,69 @@
+import type { ApiEnvelope } from '../../types/api'
+import type {
+  ApkgImportResponse,
+  CardCreateRequest,
+  CardReviewResponse,
+  Deck,
+  DeckCreateRequest,
+  DeckDetailResponse,
+  DeckListResponse,
+  DueCardsResponse,
+  Flashcard,
+  ReviewQuality,
+} from '../../types/flashcard'
+import api from '../../utils/api'
+
+export async function createDeck(payload: DeckCreateRequest) {
+  const response = await api.post<ApiEnvelope<Deck>>('/flashcards/decks', payload)
+  return response.data.data
+}
+
+export async function fetchDecks(userId: string, subject?: string) {
+  const response = await api.get<ApiEnvelope<DeckListResponse>>('/flashcards/decks', {
+    params: { user_id: userId, subject },
+  })
+  return response.data.data
+}
+
+export async function fetchDeck(deckId: string, userId: string) {
+  const response = await api.get<ApiEnvelope<DeckDetailResponse>>(`/flashcards/decks/${deckId}`, {
+    params: { user_id: userId },
+  })
+  return response.data.data
+}
+
+export async function addCard(deckId: string, payload: CardCreateRequest) {
+  const response = await api.post<ApiEnvelope<Flashcard>>(`/flashcards/decks/${deckId}/cards`, payload)
+  return response.data.data
+}
+
+export async function fetchDueCards(userId: string, options?: { date?: string; deck_id?: string; limit?: number }) {
+  const response = await api.get<ApiEnvelope<DueCardsResponse>>('/flashcards/due', {
+    params: { user_id: userId, ...options },
+  })
+  return response.data.data
+}
+
+export async function reviewCard(cardId: string, userId: string, quality: ReviewQuality) {
+  const response = await api.post<ApiEnvelope<CardReviewResponse>>(`/flashcards/cards/${cardId}/review`, {
+    user_id: userId,
+    quality,
+  })
+  return response.data.data
+}
+
+export async function importApkg(file: Blob, userId: string, subject: string) {
+  const response = await api.post<ApiEnvelope<ApkgImportResponse>>('/flashcards/import', file, {
+    params: { user_id: userId, subject },
+    headers: { 'Content-Type': 'application/octet-stream' },
+  })
+  return response.data.data
+}
+
+export async function exportDeck(deckId: string, userId: string) {
+  const response = await api.get<Blob>(`/flashcards/decks/${deckId}/export`, {
+    params: { user_id: userId },
+    responseType: 'blob',
+  })
+  return response.data
+}
@@ -0,0 +1,38 @@
+import type { ApiEnvelope } from '../../types/api'
+import type { FocusSession, FocusSessionListResponse, FocusSessionStatus } from '../../types/focus'
+import api from '../../utils/api'
+
+export async function startFocusSession(userId: string, taskId: string) {
+  const response = await api.post<ApiEnvelope<FocusSession>>('/focus-sessions', { user_id: userId, task_id: taskId })
+  return response.data.data
+}
+
+export async function pauseFocusSession(sessionId: string, userId: string, reason?: string) {
+  const response = await api.post<ApiEnvelope<FocusSession>>(`/focus-sessions/${sessionId}/pause`, { user_id: userId, reason })
+  return response.data.data
+}
+
+export async function resumeFocusSession(sessionId: string, userId: string) {
+  const response = await api.post<ApiEnvelope<FocusSession>>(`/focus-sessions/${sessionId}/resume`, { user_id: userId })
+  return response.data.data
+}
+
+export async function sendFocusHeartbeat(sessionId: string, userId: string) {
+  const response = await api.post<ApiEnvelope<FocusSession>>(`/focus-sessions/${sessionId}/heartbeat`, { user_id: userId })
+  return response.data.data
+}
+
+export async function stopFocusSession(sessionId: string, userId: string, completeTask = false) {
+  const response = await api.post<ApiEnvelope<FocusSession>>(`/focus-sessions/${sessionId}/stop`, {
+    user_id: userId,
+    complete_task: completeTask,
+  })
+  return response.data.data
+}
+
+export async function fetchFocusSessions(userId: string, filters?: { task_id?: string; status?: FocusSessionStatus }) {
+  const response = await api.get<ApiEnvelope<FocusSessionListResponse>>('/focus-sessions', {
+    params: { user_id: userId, ...filters },
+  })
+  return response.data.data
+}
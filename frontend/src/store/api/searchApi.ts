@@ -0,0 +1,10 @@
+import type { ApiEnvelope } from '../../types/api'
+import type { SearchQuery, SearchResponse } from '../../types/search'
+import api from '../../utils/api'
+
+export async function search(userId: string, { types, ...query }: SearchQuery) {
+  const response = await api.get<ApiEnvelope<SearchResponse>>('/search', {
+    params: { user_id: userId, ...query, types: types?.join(',') },
+  })
+  return response.data.data
+}
@@ -1,6 +1,28 @@
 import api from '../../utils/api'
 import type { ApiEnvelope } from '../../types/api'
-import type { Tool, ToolEditResponse, ToolGenerateRequest, ToolListResponse } from '../../types/tool'
+import type {
+  CatalogueEntry,
+  CatalogueQuery,
+  CatalogueResponse,
+  Tool,
+  ToolBundle,
+  ToolEditRequest,
+  ToolEditResponse,
+  ToolForkResponse,
+  ToolGenerateRequest,
+  ToolImportResponse,
+  ToolListResponse,
+  ToolMergeResponse,
+  ToolRatingResponse,
+  ToolStateEntry,
+  ToolStateListResponse,
+  ToolTemplateListResponse,
+  ToolUpstreamStatus,
+  ToolUsageEventRequest,
+  ToolUsageEventResponse,
+  ToolUsageResponse,
+  UserToolUsageResponse,
+} from '../../types/tool'
 
 export async function fetchTools(userId: string, toolType?: string) {
   const response = await api.get<ApiEnvelope<ToolListResponse>>('/tools', {
@@ -14,12 +36,105 @@ export async function generateTool(payload: ToolGenerateRequest) {
   return response.data.data
 }
 
+export async function fetchToolTemplates() {
+  const response = await api.get<ApiEnvelope<ToolTemplateListResponse>>('/tools/templates')
+  return response.data.data
+}
+
+export async function exportTool(toolId: string, includeState = false) {
+  const response = await api.get<ToolBundle>(`/tools/${toolId}/export`, {
+    params: { include_state: includeState },
+  })
+  return response.data
+}
+
+export async function importTool(userId: string, bundle: ToolBundle) {
+  const response = await api.post<ApiEnvelope<ToolImportResponse>>('/tools/import', bundle, {
+    params: { user_id: userId },
+  })
+  return response.data.data
+}
+
+export async function fetchToolSigningKey() {
+  const response = await api.get<ApiEnvelope<{ public_key: string | null }>>('/tools/signing-key')
+  return response.data.data
+}
+
+export async function fetchCatalogue(query: CatalogueQuery = {}) {
+  const response = await api.get<ApiEnvelope<CatalogueResponse>>('/tools/catalogue', { params: query })
+  return response.data.data
+}
+
+export async function publishTool(toolId: string, userId: string, subject?: string) {
+  const response = await api.put<ApiEnvelope<CatalogueEntry>>(`/tools/${toolId}/publish`, { user_id: userId, subject })
+  return response.data.data
+}
+
+export async function unpublishTool(toolId: string, userId: string) {
+  const response = await api.delete<ApiEnvelope<{ tool_id: string; published: boolean }>>(`/tools/${toolId}/publish`, {
+    params: { user_id: userId },
+  })
+  return response.data.data
+}
+
+export async function rateTool(toolId: string, userId: string, rating: number) {
+  const response = await api.put<ApiEnvelope<ToolRatingResponse>>(`/tools/${toolId}/rating`, { user_id: userId, rating })
+  return response.data.data
+}
+
+export async function forkTool(toolId: string, userId: string) {
+  const response = await api.post<ApiEnvelope<ToolForkResponse>>(`/tools/${toolId}/fork`, { user_id: userId })
+  return response.data.data
+}
+
+export async function fetchUpstreamStatus(toolId: string) {
+  const response = await api.get<ApiEnvelope<ToolUpstreamStatus>>(`/tools/${toolId}/upstream`)
+  return response.data.data
+}
+
+export async function mergeUpstream(toolId: string, userId: string) {
+  const response = await api.post<ApiEnvelope<ToolMergeResponse>>(`/tools/${toolId}/upstream/merge`, { user_id: userId })
+  return response.data.data
+}
+
 export async function fetchTool(toolId: string) {
   const response = await api.get<ApiEnvelope<Tool>>(`/tools/${toolId}`)
   return response.data.data
 }
 
-export async function editTool(toolId: string, payload: { edit_instruction: string; current_state?: Record<string, unknown> }) {
+export async function editTool(toolId: string, payload: ToolEditRequest) {
   const response = await api.post<ApiEnvelope<ToolEditResponse>>(`/tools/${toolId}/edit`, payload)
   return response.data.data
 }
+
+export async function recordToolUsage(toolId: string, payload: ToolUsageEventRequest) {
+  const response = await api.post<ApiEnvelope<ToolUsageEventResponse>>(`/tools/${toolId}/usage`, payload)
+  return response.data.data
+}
+
+export async function fetchToolUsage(toolId: string, range?: { from_date?: string; to_date?: string }) {
+  const response = await api.get<ApiEnvelope<ToolUsageResponse>>(`/tools/${toolId}/usage`, { params: range })
+  return response.data.data
+}
+
+export async function fetchUserToolUsage(userId: string, range?: { from_date?: string; to_date?: string; top?: number }) {
+  const response = await api.get<ApiEnvelope<UserToolUsageResponse>>('/tools/usage', {
+    params: { user_id: userId, ...range },
+  })
+  return response.data.data
+}
+
+export async function fetchToolState(toolId: string, userId: string) {
+  const response = await api.get<ApiEnvelope<ToolStateListResponse>>(`/tools/${toolId}/state`, { params: { user_id: userId } })
+  return response.data.data
+}
+
+export async function saveToolState<T>(toolId: string, key: string, payload: { user_id: string; value: T; expected_revision?: number }) {
+  const response = await api.put<ApiEnvelope<ToolStateEntry<T>>>(`/tools/${toolId}/state/${encodeURIComponent(key)}`, payload)
+  return response.data.data
+}
+
+export async function patchToolState<T>(toolId: string, key: string, payload: { user_id: string; patch: Partial<T>; expected_revision?: number }) {
+  const response = await api.patch<ApiEnvelope<ToolStateEntry<T>>>(`/tools/${toolId}/state/${encodeURIComponent(key)}`, payload)
+  return response.data.data
+}
@@ -4,13 +4,44 @@ export interface AIAction {
   action_data?: Record<string, unknown>
 }
 
+export interface AIFallback {
+  code: string
+  message: string
+}
+
+export interface AICitation {
+  index: number
+  source_type: string
+  source_id: string
+  title: string
+  date?: string | null
+  snippet: string
+}
+
 export interface AIMessage {
   response: string
   suggested_actions: AIAction[]
   conversation_id: string
+  provider: string
+  model?: string | null
+  prompt_version?: string | null
+  cached: boolean
+  citations: AICitation[]
+  fallback?: AIFallback
+}
+
+export interface AISuggestion {
+  type: string
+  message: string
+  priority: string
+  score: number
+  subject?: string | null
+  related_task_ids: string[]
 }
 
 export interface AISuggestionResponse {
   suggestions: string[]
   priority: string
+  items: AISuggestion[]
+  phrased_by: string
 }
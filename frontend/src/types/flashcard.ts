@@ -0,0 +1,84 @@
+export interface Deck {
+  deck_id: string
+  user_id: string
+  name: string
+  subject: string
+  task_id?: string | null
+  created_at: string
+  card_count: number
+  due_count: number
+}
+
+export interface DeckCreateRequest {
+  user_id: string
+  name: string
+  subject?: string
+  task_id?: string
+}
+
+export interface DeckListResponse {
+  decks: Deck[]
+  total: number
+}
+
+export interface Flashcard {
+  card_id: string
+  deck_id: string
+  front: string
+  back: string
+  tags: string[]
+  ease_factor: number
+  interval_days: number
+  repetitions: number
+  due_date: string
+  last_reviewed?: string | null
+  created_at: string
+}
+
+export interface DeckDetailResponse extends Deck {
+  cards: Flashcard[]
+}
+
+export interface CardCreateRequest {
+  user_id: string
+  front: string
+  back: string
+  tags?: string[]
+}
+
+export interface DueCard extends Flashcard {
+  deck_name: string
+  subject: string
+}
+
+export interface DueCardsResponse {
+  date: string
+  cards: DueCard[]
+  total: number
+}
+
+/** SM-2 grade: 0-2 means the card was forgotten, 3-5 recalled with decreasing effort. */
+export type ReviewQuality = 0 | 1 | 2 | 3 | 4 | 5
+
+export interface CardReview {
+  review_id: string
+  card_id: string
+  user_id: string
+  quality: ReviewQuality
+  ease_factor: number
+  interval_days: number
+  reviewed_at: string
+}
+
+export interface CardReviewResponse {
+  card: Flashcard
+  review: CardReview
+}
+
+export interface ApkgImportResponse {
+  decks: Deck[]
+  imported_cards: number
+  imported_reviews: number
+  skipped_notes: number
+  media_skipped: number
+}
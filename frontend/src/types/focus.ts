@@ -0,0 +1,28 @@
+export type FocusSessionStatus = 'active' | 'paused' | 'completed' | 'abandoned'
+
+export interface FocusInterruption {
+  paused_at: string
+  resumed_at?: string | null
+  reason?: string | null
+}
+
+export interface FocusSession {
+  session_id: string
+  task_id: string
+  user_id: string
+  status: FocusSessionStatus
+  started_at: string
+  ended_at?: string | null
+  last_activity_at: string
+  segment_started_at?: string | null
+  focused_seconds: number
+  interruptions: FocusInterruption[]
+  elapsed_seconds: number
+  task_actual_duration?: number | null
+}
+
+export interface FocusSessionListResponse {
+  sessions: FocusSession[]
+  total: number
+  focused_seconds: number
+}
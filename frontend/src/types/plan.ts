@@ -45,6 +45,8 @@ export interface PlanResponse {
   weekly_plan: WeeklyPlan
   ai_rationale: string
   generated_at: string
+  prompt_version?: string | null
+  cached: boolean
 }
 
 export interface PlanGenerateRequest {
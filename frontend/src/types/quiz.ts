@@ -0,0 +1,61 @@
+export interface QuizQuestion {
+  id: string
+  type: 'multiple_choice' | 'short_answer'
+  prompt: string
+  options: string[]
+}
+
+export interface Quiz {
+  quiz_id: string
+  task_id?: string | null
+  subject: string
+  topic: string
+  questions: QuizQuestion[]
+  source: 'ai' | 'local'
+  prompt_version?: string | null
+  created_at: string
+}
+
+export interface QuizGenerateRequest {
+  user_id: string
+  task_id?: string
+  subject?: string
+  topic?: string
+  question_count?: number
+  difficulty_level?: string
+}
+
+export interface QuizAnswer {
+  question_id: string
+  answer: string
+}
+
+export interface QuestionResult {
+  question_id: string
+  correct: boolean
+  score: number
+  given: string
+  expected: string
+  explanation: string
+  feedback?: string | null
+  graded_by: 'exact' | 'model' | 'keyword'
+}
+
+export interface TopicMastery {
+  subject: string
+  topic: string
+  average_score: number
+  attempts: number
+}
+
+export interface QuizAttempt {
+  attempt_id: string
+  quiz_id: string
+  user_id: string
+  score: number
+  correct: number
+  total: number
+  results: QuestionResult[]
+  submitted_at: string
+  mastery?: TopicMastery
+}
@@ -0,0 +1,29 @@
+export type SearchResultType = 'tool' | 'task' | 'user_note' | 'ai_note' | 'conversation'
+
+export interface SearchQuery {
+  q: string
+  types?: SearchResultType[]
+  from?: string
+  to?: string
+  sort?: 'relevance' | 'recent'
+  limit?: number
+  offset?: number
+}
+
+export interface SearchResult {
+  type: SearchResultType
+  id: string
+  title: string
+  /** Escaped HTML with matched words wrapped in `<mark>`. */
+  snippet: string
+  date?: string | null
+  score: number
+}
+
+export interface SearchResponse {
+  query: string
+  results: SearchResult[]
+  total: number
+  limit: number
+  offset: number
+}
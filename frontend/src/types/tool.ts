@@ -2,6 +2,16 @@ export interface ToolMetadata {
   version: string
   created_at: string
   ai_model: string
+  prompt_version?: string | null
+  template?: string | null
+}
+
+export interface ToolDiagnostic {
+  severity: 'error' | 'warning'
+  code: string
+  message: string
+  line?: number | null
+  column?: number | null
 }
 
 export interface Tool {
@@ -12,6 +22,7 @@ export interface Tool {
   component_code: string
   metadata: ToolMetadata
   preview_url: string
+  diagnostics: ToolDiagnostic[]
 }
 
 export interface ToolListItem {
@@ -37,13 +48,244 @@ export interface ToolDetailResponse {
   description: string
   component_code: string
   metadata: ToolMetadata
+  provenance?: ToolProvenance | null
+  fork?: ToolFork | null
+}
+
+export interface ToolProvenance {
+  tool_id: string
+  source_tool_id: string
+  source_version: string
+  content_hash: string
+  exported_at: string
+  signer_public_key?: string | null
+  signature_trusted: boolean
+  imported_at: string
+}
+
+export interface ToolBundle {
+  format: 'studyplanner.tool-bundle'
+  content: {
+    format_version: number
+    exported_at: string
+    source_tool_id: string
+    tool: {
+      name: string
+      tool_type: string
+      description: string
+      component_code: string
+      metadata: ToolMetadata
+    }
+    head: {
+      version: string
+      changes_summary: string
+      change_type: string
+      ai_model?: string | null
+      prompt_version?: string | null
+      created_at: string
+    }
+    state?: { key: string; value: unknown }[] | null
+  }
+  content_hash: string
+  signature?: { algorithm: 'ed25519'; public_key: string; value: string } | null
+}
+
+export interface ToolImportResponse extends Tool {
+  provenance: ToolProvenance
+  imported_state_keys: number
+}
+
+export interface CatalogueEntry {
+  tool_id: string
+  owner_id: string
+  owner_name?: string | null
+  name: string
+  tool_type: string
+  description: string
+  subject?: string | null
+  version: string
+  published_at: string
+  rating_average?: number | null
+  rating_count: number
+  fork_count: number
+}
+
+export interface CatalogueQuery {
+  type?: string
+  subject?: string
+  q?: string
+  sort?: 'recent' | 'rating' | 'forks'
+  limit?: number
+  offset?: number
+}
+
+export interface CatalogueResponse {
+  tools: CatalogueEntry[]
+  total: number
+  limit: number
+  offset: number
+}
+
+export interface ToolRatingResponse {
+  tool_id: string
+  rating: number
+  rating_average?: number | null
+  rating_count: number
+}
+
+export interface ToolFork {
+  tool_id: string
+  upstream_tool_id: string
+  synced_version: string
+  forked_at: string
+}
+
+export interface ToolForkResponse extends Tool {
+  fork: ToolFork
+}
+
+export interface ToolUpstreamStatus {
+  tool_id: string
+  upstream_tool_id: string
+  synced_version: string
+  upstream_version: string
+  behind: boolean
+  mergeable: boolean
+  diff?: ToolDiffResponse | null
+}
+
+export interface ToolMergeResponse {
+  tool_id: string
+  previous_version: string
+  version: string
+  upstream_version: string
+  component_code: string
+  diagnostics: ToolDiagnostic[]
 }
 
 export interface ToolEditResponse {
   tool_id: string
   updated_component_code: string
   changes_summary: string
+  change_type: 'patch' | 'minor' | 'major'
+  previous_version: string
+  version: string
+  prompt_version: string
+  diagnostics: ToolDiagnostic[]
+}
+
+export interface ToolVersion {
+  version_id: string
+  tool_id: string
+  version: string
+  instruction?: string | null
+  changes_summary: string
+  change_type: 'initial' | 'patch' | 'minor' | 'major' | 'rollback'
+  ai_model?: string | null
+  prompt_version?: string | null
+  diagnostics: ToolDiagnostic[]
+  created_at: string
+}
+
+export interface ToolVersionListResponse {
+  tool_id: string
+  current_version: string
+  versions: ToolVersion[]
+}
+
+export interface ToolDiffResponse {
+  tool_id: string
+  from: string
+  to: string
+  additions: number
+  deletions: number
+  diff: string
+}
+
+export interface ToolRollbackResponse {
+  tool_id: string
+  restored_from: string
+  previous_version: string
   version: string
+  component_code: string
+}
+
+export type ToolUsageEventType = 'open' | 'interact' | 'close'
+
+export interface ToolUsageEventRequest {
+  user_id: string
+  event_type: ToolUsageEventType
+  task_id?: string
+  duration_seconds?: number
+}
+
+export interface ToolUsageEventResponse {
+  event_id: string
+  tool_id: string
+  usage_count: number
+  last_used?: string | null
+}
+
+export interface ToolUsageTotals {
+  opens: number
+  interactions: number
+  duration_seconds: number
+}
+
+export interface ToolUsageBreakdown extends ToolUsageTotals {
+  key: string
+}
+
+export interface ToolUsageRanking extends ToolUsageTotals {
+  tool_id: string
+  name: string
+  tool_type: string
+}
+
+export interface ToolUsageResponse {
+  tool_id: string
+  usage_count: number
+  last_used?: string | null
+  totals: ToolUsageTotals
+  by_subject: ToolUsageBreakdown[]
+  by_day: ToolUsageBreakdown[]
+}
+
+export interface UserToolUsageResponse {
+  user_id: string
+  totals: ToolUsageTotals
+  top_tools: ToolUsageRanking[]
+  by_subject: { subject: string; tools: ToolUsageRanking[] }[]
+  by_day: ToolUsageBreakdown[]
+}
+
+export interface ToolStateEntry<T = unknown> {
+  tool_id: string
+  user_id: string
+  key: string
+  value: T
+  revision: number
+  size_bytes: number
+  updated_at: string
+}
+
+export interface ToolStateListResponse {
+  tool_id: string
+  user_id: string
+  entries: ToolStateEntry[]
+  total_bytes: number
+  limit_bytes: number
+}
+
+export interface ToolStateRef {
+  user_id: string
+  keys?: string[]
+}
+
+export interface ToolEditRequest {
+  edit_instruction: string
+  current_state?: Record<string, unknown>
+  state_ref?: ToolStateRef
 }
 
 export interface ToolGenerateRequest {
@@ -51,8 +293,25 @@ export interface ToolGenerateRequest {
   tool_type: string
   context: string
   requirements: string
+  subject?: string
   ui_preferences?: {
     theme?: string
     size?: string
   }
+  template?: string
+  parameters?: Record<string, unknown>
+}
+
+export interface ToolTemplate {
+  name: string
+  tool_type: string
+  version: string
+  description: string
+  source: string
+  parameters: Record<string, unknown>
+}
+
+export interface ToolTemplateListResponse {
+  templates: ToolTemplate[]
+  errors?: string[]
 }
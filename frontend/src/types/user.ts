@@ -29,5 +29,6 @@ export interface UserStats {
   tools_created: number
   ai_interactions: number
   subjects_progress: Record<string, number>
+  quiz_scores: Record<string, number>
   weekly_activity: WeeklyActivity[]
 }
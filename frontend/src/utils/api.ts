@@ -1,4 +1,5 @@
 import axios from 'axios'
+import { getUserId } from './user'
 
 const api = axios.create({
   baseURL: 'http://localhost:8080/api',
@@ -7,4 +8,10 @@ const api = axios.create({
   },
 })
 
+// Tool routes check ownership against the caller named here.
+api.interceptors.request.use((config) => {
+  config.headers.set('X-User-Id', getUserId())
+  return config
+})
+
 export default api
@@ -0,0 +1,20 @@
+import { fileURLToPath } from 'node:url'
+import { defineConfig } from 'vite'
+
+// Bundles React and MUI into one classic script that the backend serves from /tools/preview/runtime.js.
+// Preview pages load it with a CSP nonce, so they never load code from a CDN.
+export default defineConfig({
+  define: {
+    'process.env.NODE_ENV': JSON.stringify('production'),
+  },
+  build: {
+    outDir: fileURLToPath(new URL('../backend/data', import.meta.url)),
+    emptyOutDir: false,
+    lib: {
+      entry: fileURLToPath(new URL('./preview-runtime/main.ts', import.meta.url)),
+      name: 'StudyToolRuntime',
+      formats: ['iife'],
+      fileName: () => 'preview-runtime.js',
+    },
+  },
+})